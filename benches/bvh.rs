@@ -0,0 +1,129 @@
+//! Compares queries going through the level [ObjBvh] with checking every triangle.
+//! Run with `cargo bench --bench bvh`
+
+use flashdark::*;
+use geng::prelude::*;
+use std::time::{Duration, Instant};
+
+const QUERIES: usize = 1000;
+
+fn time<T>(f: impl FnOnce() -> T) -> (Duration, T) {
+    let start = Instant::now();
+    let result = f();
+    (start.elapsed(), result)
+}
+
+fn report(name: &str, brute_force: Duration, bvh: Duration) {
+    println!(
+        "{name}: {:.2?} per query checking every triangle, {:.2?} with the bvh, {:.0}x faster",
+        brute_force / QUERIES as u32,
+        bvh / QUERIES as u32,
+        brute_force.as_secs_f64() / bvh.as_secs_f64(),
+    );
+}
+
+fn main() -> anyhow::Result<()> {
+    let assets = static_path().join("assets");
+    let config: Config = serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(
+        assets.join("config.json"),
+    )?))?;
+    let level = Level::load_headless(&assets.join("level").join("roomMVP.obj"))?;
+    let (build_time, level) = time(|| {
+        LevelData::generate(
+            &level,
+            &config.puzzles,
+            &config.collision_layers,
+            None,
+            &mut StdRng::seed_from_u64(0),
+        )
+    });
+    println!("Generated the level with its bvh in {build_time:.2?}");
+
+    let bounds = AABB::points_bounding_box(
+        level
+            .obj
+            .meshes
+            .iter()
+            .flat_map(|mesh| mesh.geometry.iter().map(|v| v.a_v.xy())),
+    );
+    let mut rng = StdRng::seed_from_u64(0);
+    let points: Vec<Vec3<f32>> = (0..QUERIES)
+        .map(|_| {
+            vec3(
+                rng.gen_range(bounds.x_min..bounds.x_max),
+                rng.gen_range(bounds.y_min..bounds.y_max),
+                rng.gen_range(0.0..3.0),
+            )
+        })
+        .collect();
+    let rays: Vec<geng::CameraRay> = points
+        .iter()
+        .map(|&from| geng::CameraRay {
+            from,
+            dir: vec3(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize_or_zero(),
+        })
+        .collect();
+
+    let (brute_force_time, brute_force): (_, Vec<Option<f32>>) = time(|| {
+        rays.iter()
+            .map(|&ray| {
+                intersect_ray_with_obj(
+                    &level.obj,
+                    Mat4::identity(),
+                    0.0,
+                    ray,
+                    &config.collision_layers,
+                    CollisionLayer::Sight,
+                )
+            })
+            .collect()
+    });
+    let (bvh_time, bvh): (_, Vec<Option<f32>>) = time(|| {
+        rays.iter()
+            .map(|&ray| {
+                level
+                    .bvh
+                    .raycast(Mat4::identity(), 0.0, ray, CollisionLayer::Sight)
+            })
+            .collect()
+    });
+    assert_eq!(brute_force, bvh);
+    report("Raycasts", brute_force_time, bvh_time);
+
+    let (brute_force_time, brute_force): (_, Vec<f32>) = time(|| {
+        points
+            .iter()
+            .map(|&p| {
+                vector_from_obj(
+                    &level.obj,
+                    Mat4::identity(),
+                    p,
+                    &config.collision_layers,
+                    CollisionLayer::Player,
+                )
+                .len()
+            })
+            .collect()
+    });
+    let (bvh_time, bvh): (_, Vec<f32>) = time(|| {
+        points
+            .iter()
+            .map(|&p| {
+                level
+                    .bvh
+                    .vector_from(Mat4::identity(), p, CollisionLayer::Player)
+                    .unwrap()
+                    .len()
+            })
+            .collect()
+    });
+    assert_eq!(brute_force, bvh);
+    report("Closest points", brute_force_time, bvh_time);
+
+    Ok(())
+}
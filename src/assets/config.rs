@@ -7,6 +7,7 @@ pub struct InteractableConfig {
     pub use_item: bool,
     pub transform_on_use: Option<String>,
     pub give_item: Option<String>,
+    /// Not there at the start, only shows up when something turns into it
     #[serde(default)]
     pub hidden: bool,
     #[serde(default)]
@@ -19,8 +20,298 @@ pub struct InteractableConfig {
     pub open: bool,
     #[serde(default)]
     pub open_inverse: bool,
+    /// Applied once it is all the way closed
+    #[serde(default)]
+    pub on_closed: Vec<Effect>,
     pub sfx: Option<String>,
     pub sfx_volume: Option<f64>,
+    /// The sfx comes from the interactable unless this is `false`, then it plays everywhere
+    pub positioned_sfx: Option<bool>,
+    /// Track that stops playing when this is used
+    pub stop_track: Option<Track>,
+    /// Happens instead of opening or closing
+    pub on_use: Option<OnUseConfig>,
+    /// Name of the puzzle that has to be solved before this can be opened
+    pub locked_until: Option<String>,
+    /// Key puzzle states in which this can not be used
+    #[serde(default)]
+    pub locked_during: Vec<KeyPuzzleState>,
+    /// Additional key puzzle states in which the monster does not open this
+    #[serde(default)]
+    pub monster_locked_during: Vec<KeyPuzzleState>,
+    /// The monster only opens this while the player is inside the house
+    #[serde(default)]
+    pub monster_waits_for_player_inside: bool,
+    /// The player can hide behind this
+    pub hide: Option<HideConfig>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OnUseConfig {
+    /// Any key puzzle state if empty
+    #[serde(default)]
+    pub key_puzzle_states: Vec<KeyPuzzleState>,
+    pub actions: Vec<Effect>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HideConfig {
+    /// How far behind the closed mesh the player hides, 0 is right in the middle of it
+    #[serde(default)]
+    pub depth: f32,
+    /// How far to the sides the player can peek, in degrees
+    pub peek_angle: f32,
+    /// The player lies down to this camera height, like under a bed
+    pub lying_height: Option<f32>,
+}
+
+/// A puzzle built from level interactables, names are matched by prefix
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PuzzleConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: PuzzleKind,
+    #[serde(default)]
+    pub on_solved: Vec<Effect>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PuzzleKind {
+    /// Every button cycles through `symbols` variants,
+    /// the randomized solution is shown on the `hints` (one per button)
+    CombinationLock {
+        buttons: Vec<String>,
+        symbols: u8,
+        hints: Vec<String>,
+        sfx: Option<Sfx>,
+    },
+    /// Solved once `required` of the toggles are open,
+    /// every open toggle moves the `slides` by `step` until then
+    Toggles {
+        toggles: Vec<String>,
+        required: usize,
+        #[serde(default)]
+        slides: Vec<String>,
+        #[serde(default)]
+        step: f32,
+    },
+    /// The lock accepts any key with the same configuration as `key`.
+    /// Items with `keys` in their name get random configurations,
+    /// `key` is all of them that have the one shown on the `hint` interactable
+    KeyMatch {
+        lock: String,
+        key: String,
+        keys: String,
+        hint: String,
+    },
+    /// Solved when the socket is used with the item it requires
+    ItemSocket { socket: String },
+    /// Solved when all of the candles are blown out
+    AllExtinguished {
+        candles: String,
+        sfx: Option<Sfx>,
+        #[serde(default)]
+        dim_ambient_light: bool,
+    },
+    /// Only solved by [Effect::SolvePuzzle]
+    Scripted,
+}
+
+/// Parts of the world state that effects can turn on and off
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorldFlag {
+    FuseSpawned,
+    FusePlaced,
+    BatReleased,
+    /// The player counts as inside the house
+    PlayerInsideHouse,
+    /// The monster is out and hunting
+    MonsterSpawned,
+    CrouchTutorial,
+    Flashdark,
+    Ending,
+}
+
+/// What solving a puzzle or firing an event does.
+/// Places are named by trigger cube, room, level mesh or interactable
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    PlaySfx {
+        sfx: Sfx,
+    },
+    PlaySfxAt {
+        sfx: Sfx,
+        at: String,
+    },
+    StartTrack {
+        track: Track,
+    },
+    StopTrack {
+        track: Track,
+    },
+    StopSounds,
+    SetAmbientLight {
+        color: Rgba<f32>,
+    },
+    RemoveInteractables {
+        containing: String,
+    },
+    RemoveItems {
+        containing: String,
+    },
+    Click {
+        interactable: String,
+    },
+    /// Clicks the interactable if it is closed, opening it away from `from`
+    Open {
+        interactable: String,
+        from: Option<String>,
+    },
+    /// Clicks the interactable if it is open
+    Close {
+        interactable: String,
+    },
+    /// At one of the item's spawns, picked at random
+    SpawnItem {
+        item: String,
+    },
+    MoveMonster {
+        to: String,
+    },
+    /// Makes the monster come and look, like after a noise
+    AlertMonster {
+        to: String,
+    },
+    /// Rattles a level mesh for a while
+    Shake {
+        mesh: String,
+        duration: f32,
+    },
+    SetKeyPuzzleState {
+        state: KeyPuzzleState,
+    },
+    SetFlag {
+        flag: WorldFlag,
+        value: bool,
+    },
+    SetMonsterState {
+        state: MonsterState,
+    },
+    /// Solves the puzzle with its effects, unless it is already solved
+    SolvePuzzle {
+        puzzle: String,
+        /// Only marks it solved, for restoring a checkpoint without redoing what it did
+        #[serde(default)]
+        without_effects: bool,
+    },
+    /// Uses the interactable with the item in hand, like the player would
+    UseItem {
+        interactable: String,
+        item: String,
+    },
+    PlayCutscene {
+        cutscene: String,
+        /// Seconds in, see [World::play_cutscene]
+        #[serde(default)]
+        start: f32,
+    },
+    /// Opens or closes every interactable with `containing` in its name without clicking it
+    SetOpen {
+        containing: String,
+        open: bool,
+        /// Skips the animation
+        #[serde(default)]
+        instantly: bool,
+    },
+    PlayMusic {
+        music: BackgroundMusic,
+    },
+    /// Dying restarts from here if the player chooses so
+    ReachCheckpoint {
+        checkpoint: String,
+    },
+    FlickerLights {
+        duration: f32,
+    },
+    /// Puts every key of the [PuzzleKind::KeyMatch] puzzle at all of its spawns and shows the hint
+    RevealKeys {
+        puzzle: String,
+    },
+}
+
+/// When an event happens, checked every tick
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventTrigger {
+    /// The player is inside a trigger cube or room
+    Inside {
+        area: String,
+    },
+    /// The player is not inside a trigger cube or room
+    Outside {
+        area: String,
+    },
+    LookAt {
+        interactable: String,
+    },
+    /// The cutscene has just finished
+    CutsceneEnd {
+        cutscene: String,
+    },
+    /// The flashdark has just been turned on or off
+    FlashdarkToggled,
+}
+
+/// Horizontal direction the player has to look in
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Facing {
+    /// In degrees, counterclockwise from the x axis
+    pub direction: f32,
+    /// Allowed difference in degrees
+    pub within: f32,
+}
+
+/// A scripted scare or level event, every condition that is set has to hold
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct EventConfig {
+    /// Unique
+    pub name: String,
+    pub when: EventTrigger,
+    /// Fires only once per run
+    #[serde(default)]
+    pub once: bool,
+    /// Seconds before it can fire again
+    #[serde(default)]
+    pub cooldown: f32,
+    /// Item that has to be in the inventory
+    pub require_item: Option<String>,
+    /// Puzzle that has to be solved
+    pub require_solved: Option<String>,
+    /// Interactable that has to be there and open
+    pub require_open: Option<String>,
+    /// Interactable that has to still be there
+    pub require_interactable: Option<String>,
+    /// Fires in any key puzzle state if empty
+    #[serde(default)]
+    pub key_puzzle_states: Vec<KeyPuzzleState>,
+    /// Checkpoint reached before, in this run or an earlier one
+    pub require_seen: Option<String>,
+    pub facing: Option<Facing>,
+    pub actions: Vec<Effect>,
+}
+
+/// Point of a run that it can be restarted from after dying
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CheckpointConfig {
+    /// Unique, see [Effect::ReachCheckpoint]
+    pub name: String,
+    /// Shown on the game over screen
+    pub title: String,
+    /// Puts the world past the checkpoint, without moving the player
+    pub restore: Vec<Effect>,
 }
 
 #[derive(geng::Assets, Deserialize, Serialize, Clone, Debug)]
@@ -29,14 +320,38 @@ pub struct Difficulty {
     pub peek_distance: f32,
     pub monster_180_range: f32,
     pub monster_detect_time: f32,
-    pub monster_scan_time: f32,
+    pub monster_walk_speed: f32,
+    /// How long the monster stays in a room before going to another one
+    pub monster_wander_time: f32,
+    /// How long the monster looks around a noise or where the player was lost
+    pub monster_search_time: f32,
+    /// Radius of the area looked around while wandering or searching
     pub monster_scan_radius: f32,
+    /// How far ahead the player's movement is extrapolated after losing them
+    pub monster_predict_time: f32,
+    /// How many rooms away from where it started the search spreads
+    pub monster_search_rounds: usize,
+    /// How long the monster stands still screaming before chasing
+    pub monster_scream_time: f32,
+    /// Seconds the monster waits in front of a door it opened
+    pub monster_door_open_time: f32,
     pub ghost_stand_still_time: (f32, f32),
     pub monster_view_distance: f32,
     pub monster_fov: f32,
     pub crouch_detect_time_multiplier: f32,
     pub monster_chase_speed: ((f32, f32), (f32, f32)),
-    pub max_ghost_sound_distance: f64,
+    /// Multiplier for how far the monster hears noises
+    pub monster_hearing: f32,
+    /// How close the monster has to get to find a player it saw hiding
+    pub monster_discover_distance: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct NoiseConfig {
+    /// Distance along the navmesh at which the monster still hears the noise
+    pub loudness: HashMap<Noise, f32>,
+    /// Distance lost for every closed door on the way
+    pub closed_door_attenuation: f32,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -46,11 +361,15 @@ pub struct Controls {
     pub god_mode: Vec<Button>,
     pub toggle_fullscreen: Vec<Button>,
     pub toggle_flashdark: Vec<Button>,
+    pub hide: Vec<Button>,
     pub move_forward: Vec<Button>,
     pub move_backward: Vec<Button>,
     pub move_left: Vec<Button>,
     pub move_right: Vec<Button>,
     pub drop_item: Vec<Button>,
+    pub inspect_item: Vec<Button>,
+    /// One button per inventory slot
+    pub select_slot: Vec<Button>,
     pub pause: Vec<Button>,
 }
 
@@ -65,13 +384,118 @@ pub struct Config {
     pub tv_detection_angle: f32,
     pub sky_color: Rgba<f32>,
     pub ambient_light: Rgba<f32>,
-    pub ambient_light_after_fuse: Rgba<f32>,
     pub ambient_light_inside_house: Rgba<f32>,
     pub footstep_dist: f32,
     pub max_sound_distance: f64,
+    pub noise: NoiseConfig,
+    /// Meshes and interactables the monster looks behind and under when searching
+    pub monster_hiding_spots: Vec<String>,
+    /// Level meshes the player can hide in or under, by name
+    #[serde(default)]
+    pub hiding_meshes: HashMap<String, HideConfig>,
     pub arms_horizontal_length: f32,
     pub arms_vertical_length: f32,
+    pub inventory_size: usize,
+    /// Items that are drawn with the first spawn of another item once dropped, like a doll lying down
+    #[serde(default)]
+    pub dropped_meshes: HashMap<String, String>,
     pub parents: HashMap<String, String>,
     pub open_interactables: HashSet<String>,
     pub interactables: HashMap<String, Rc<InteractableConfig>>,
+    #[serde(default)]
+    pub puzzles: Vec<PuzzleConfig>,
+    #[serde(default)]
+    pub collision_layers: CollisionRules,
+    #[serde(default)]
+    pub events: Vec<EventConfig>,
+    #[serde(default)]
+    pub cutscenes: HashMap<String, Cutscene>,
+    /// In the order they are reached in
+    #[serde(default)]
+    pub checkpoints: Vec<CheckpointConfig>,
+}
+
+impl Config {
+    /// Every effect in the config, wherever it is
+    pub fn effects(&self) -> Vec<&Effect> {
+        let mut result = Vec::new();
+        for config in self.interactables.values() {
+            result.extend(&config.on_closed);
+            if let Some(on_use) = &config.on_use {
+                result.extend(&on_use.actions);
+            }
+        }
+        for puzzle in &self.puzzles {
+            result.extend(&puzzle.on_solved);
+        }
+        for event in &self.events {
+            result.extend(&event.actions);
+        }
+        for cutscene in self.cutscenes.values() {
+            for cue in &cutscene.cues {
+                if let CueAction::Effect { effect } = &cue.action {
+                    result.push(effect);
+                }
+            }
+        }
+        for checkpoint in &self.checkpoints {
+            result.extend(&checkpoint.restore);
+        }
+        result
+    }
+
+    /// Names of every [Sfx::Named] the config can play
+    pub fn named_sfx(&self) -> Vec<&str> {
+        let mut result: Vec<&str> = self
+            .interactables
+            .values()
+            .filter_map(|config| config.sfx.as_deref())
+            .collect();
+        let mut sfx: Vec<&Sfx> = self
+            .puzzles
+            .iter()
+            .filter_map(|puzzle| match &puzzle.kind {
+                PuzzleKind::CombinationLock { sfx, .. }
+                | PuzzleKind::AllExtinguished { sfx, .. } => sfx.as_ref(),
+                _ => None,
+            })
+            .collect();
+        sfx.extend(
+            self.effects()
+                .into_iter()
+                .filter_map(|effect| match effect {
+                    Effect::PlaySfx { sfx } | Effect::PlaySfxAt { sfx, .. } => Some(sfx),
+                    _ => None,
+                }),
+        );
+        result.extend(sfx.into_iter().filter_map(|sfx| match sfx {
+            Sfx::Named(name) => Some(name.as_str()),
+            _ => None,
+        }));
+        result
+    }
+
+    /// Fails on sounds that are not in [SfxAssets::NAMED]
+    pub fn check_sfx(&self) -> anyhow::Result<()> {
+        for name in self.named_sfx() {
+            if !SfxAssets::NAMED.contains(&name) {
+                anyhow::bail!(
+                    "there is no sound {name:?}, it has to be one of {:?}",
+                    SfxAssets::NAMED
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Interactables that [PuzzleKind::Toggles] puzzles move out of the way
+    pub fn slides(&self) -> Vec<String> {
+        self.puzzles
+            .iter()
+            .flat_map(|puzzle| match &puzzle.kind {
+                PuzzleKind::Toggles { slides, .. } => slides.clone(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,168 @@
+use super::*;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    Origin,
+    PlayerSpawn,
+    /// Where the player looks from
+    Eyes,
+    Monster,
+    /// Trigger cube, room, level mesh or interactable
+    Place(String),
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Self::Origin
+    }
+}
+
+/// Point in the level, `offset` away from the `anchor`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Spot {
+    #[serde(default)]
+    pub anchor: Anchor,
+    #[serde(default = "no_offset")]
+    pub offset: Vec3<f32>,
+}
+
+fn no_offset() -> Vec3<f32> {
+    Vec3::ZERO
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MonsterSprite {
+    Front,
+    Back,
+    Dying,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CutsceneImage {
+    Intro,
+    Title,
+    PentagramFire,
+    ToBeContinued,
+}
+
+/// Pairs of values go from the first to the second over the cue
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CueAction {
+    Effect {
+        effect: Effect,
+    },
+    /// Along a line if `from` is given, otherwise towards `to`
+    MovePlayer {
+        from: Option<Spot>,
+        to: Spot,
+        /// Keeps the height of the player
+        #[serde(default)]
+        horizontal: bool,
+        /// Seconds to get most of the way there, 0 is instant
+        #[serde(default)]
+        smoothing: f32,
+    },
+    /// Turns the player's view towards the spot
+    Look {
+        target: Spot,
+        #[serde(default)]
+        smoothing: f32,
+    },
+    PlayerHeight {
+        height: f32,
+        #[serde(default)]
+        smoothing: f32,
+    },
+    /// Also keeps the flashdark from flickering
+    Flashdark {
+        on: bool,
+        dark: Option<[f32; 2]>,
+    },
+    /// Along a line if `from` is given, otherwise to `to` right away
+    MoveMonster {
+        from: Option<Spot>,
+        to: Spot,
+        /// Keeps the height of the monster
+        #[serde(default)]
+        horizontal: bool,
+        /// How far it randomly jitters around
+        #[serde(default)]
+        shake: f32,
+    },
+    MonsterState {
+        state: MonsterState,
+    },
+    TurnMonster {
+        dir: Vec3<f32>,
+    },
+    /// Draws the monster with this sprite, even before it has spawned
+    MonsterSprite {
+        sprite: MonsterSprite,
+        size: [f32; 2],
+        fade: [f32; 2],
+    },
+    /// Covers the screen
+    Fade {
+        color: Rgba<f32>,
+        alpha: [f32; 2],
+    },
+    /// Drawn over the screen, moving from `from` to `to`
+    Image {
+        image: CutsceneImage,
+        from: Vec2<f32>,
+        to: Option<Vec2<f32>>,
+        /// Half of the height
+        size: f32,
+        /// Half of the width, keeps the aspect ratio of the image if not set
+        width: Option<f32>,
+        alpha: [f32; 2],
+        #[serde(default)]
+        flicker: bool,
+    },
+    /// Pulsing light drawn in the level
+    Glow {
+        spot: Spot,
+        size: f32,
+    },
+}
+
+/// Part of a cutscene, happens once at `at` or keeps going until `until`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Cue {
+    pub at: f32,
+    pub until: Option<f32>,
+    #[serde(flatten)]
+    pub action: CueAction,
+}
+
+impl Cue {
+    /// How far along the cue is at `time`, if it is going on then
+    pub fn progress(&self, time: f32) -> Option<f32> {
+        let until = self.until?;
+        if time < self.at || time > until {
+            return None;
+        }
+        Some(if until > self.at {
+            (time - self.at) / (until - self.at)
+        } else {
+            1.0
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Cutscene {
+    /// Seconds
+    pub duration: f32,
+    /// Holding any key jumps to `skip_to`, or to the end
+    #[serde(default)]
+    pub skippable: bool,
+    pub skip_to: Option<f32>,
+    #[serde(default)]
+    pub lock_controls: bool,
+    pub cues: Vec<Cue>,
+}
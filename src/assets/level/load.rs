@@ -51,249 +51,251 @@ fn update_key_uvs(mesh: &mut ObjMesh, config: KeyConfiguration) {
     );
 }
 
-fn update_storage_lock_uvs(mesh: &mut ObjMesh, num: u8) {
+fn update_symbol_uvs(mesh: &mut ObjMesh, num: u8) {
     let mid = mesh.geometry.iter().map(|v| v.a_vt.x).sum::<f32>() / mesh.geometry.len() as f32;
     for v in mesh.geometry.iter_mut() {
         v.a_vt.x = 0.25 * num as f32 + if v.a_vt.x < mid { 0.0 } else { 0.25 };
     }
 }
 
-impl LevelData {
-    pub fn generate(geng: &Geng, obj: &Obj) -> Self {
-        let mut obj: Obj = Obj {
-            meshes: obj
-                .meshes
-                .iter()
-                .map(|mesh| ObjMesh {
-                    name: mesh.name.clone(),
-                    geometry: ugli::VertexBuffer::new_static(
-                        geng.ugli(),
-                        mesh.geometry.iter().cloned().collect(),
-                    ),
-                    material: mesh.material.clone(),
-                })
-                .collect(),
-        };
+/// Takes all the meshes with the name out of the obj
+fn take_meshes(obj: &mut Obj, name: &str) -> Vec<ObjMesh> {
+    let (taken, left): (Vec<_>, Vec<_>) = mem::take(&mut obj.meshes)
+        .into_iter()
+        .partition(|mesh| mesh.name == name);
+    obj.meshes = left;
+    taken
+}
 
-        obj.meshes.retain(|mesh| {
-            if mesh.name.starts_with("H_") {
-                // TODO window holes?
-                return false;
-            }
-            true
-        });
+fn bounding_cube(meshes: &[ObjMesh]) -> TriggerCube {
+    let mut min = meshes[0].geometry[0].a_v;
+    let mut max = min;
+    for v in meshes.iter().flat_map(|mesh| mesh.geometry.iter()) {
+        min.x = min.x.min(v.a_v.x);
+        min.y = min.y.min(v.a_v.y);
+        min.z = min.z.min(v.a_v.z);
+        max.x = max.x.max(v.a_v.x);
+        max.y = max.y.max(v.a_v.y);
+        max.z = max.z.max(v.a_v.z);
+    }
+    TriggerCube {
+        min_x: min.x,
+        min_y: min.y,
+        min_z: min.z,
+        max_x: max.x,
+        max_y: max.y,
+        max_z: max.z,
+    }
+}
 
-        let storage_lock_combination = std::array::from_fn(|_| global_rng().gen_range(0..4));
+impl LevelData {
+    /// The manifest has to be [validated](LevelManifest::validate) already,
+    /// which [Level] does when it is loaded
+    pub fn generate(
+        level: &Level,
+        puzzles: &[PuzzleConfig],
+        collision: &CollisionRules,
+        saved: Option<&LevelRandomization>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let manifest = &level.manifest;
+        let mut obj = level.obj.clone();
+        obj.meshes
+            .retain(|mesh| !manifest.removed.contains(&mesh.name));
 
-        for mesh in &mut obj.meshes {
-            if mesh.name.starts_with("S_Grass")
-                || mesh.name.starts_with("S_Ceiling")
-                || mesh.name.starts_with("S_RefFloor")
+        let mut combinations = HashMap::new();
+        let mut hints = HashMap::new();
+        let mut buttons = HashMap::new();
+        for puzzle in puzzles {
+            if let PuzzleKind::CombinationLock {
+                buttons: names,
+                symbols,
+                hints: hint_names,
+                ..
+            } = &puzzle.kind
             {
-                for v in mesh.geometry.iter_mut() {
-                    v.a_vt = v.a_v.xy() / 2.0;
-                }
-            }
-            if mesh.name.starts_with("S_Walls") || mesh.name.starts_with("S_Fence") {
-                for v in mesh.geometry.iter_mut() {
-                    v.a_vt = vec2((v.a_v.x + v.a_v.y) / 2.0, v.a_v.z / 2.0);
+                let combination: Vec<u8> = match saved {
+                    Some(saved) => saved.combinations[&puzzle.name].clone(),
+                    None => names.iter().map(|_| rng.gen_range(0..*symbols)).collect(),
+                };
+                for (hint, &value) in hint_names.iter().zip(&combination) {
+                    hints.insert(hint.clone(), value);
                 }
-            }
-            if mesh.name.starts_with("S_Treeline") {
-                for v in mesh.geometry.iter_mut() {
-                    v.a_vt = vec2((v.a_v.x + v.a_v.y), v.a_v.z) / 4.0;
+                for name in names {
+                    buttons.insert(name.clone(), *symbols);
                 }
-            }
-
-            if mesh.name == "S_StudySymbol2" {
-                update_storage_lock_uvs(mesh, storage_lock_combination[0]);
-            }
-            if mesh.name == "S_LivingSymbol1" {
-                update_storage_lock_uvs(mesh, storage_lock_combination[1]);
-            }
-            if mesh.name == "S_LivingSymbol2" {
-                update_storage_lock_uvs(mesh, storage_lock_combination[2]);
-            }
-            if mesh.name == "S_StudySymbol1" {
-                update_storage_lock_uvs(mesh, storage_lock_combination[3]);
+                combinations.insert(puzzle.name.clone(), combination);
             }
         }
 
-        let mut skybox = None;
-        let mut trigger_cubes = HashMap::new();
-        let mut room_data = HashMap::new();
-        for i in (0..obj.meshes.len()).rev() {
-            // info!("{:?}", obj.meshes[i].name);
-            let name = &obj.meshes[i].name;
-            if name == "Skybox" {
-                skybox = Some(obj.meshes.remove(i));
-                continue;
-            }
-            let name = name.to_owned();
+        for mesh in &mut obj.meshes {
+            if let Some(rule) = manifest
+                .uv_rules
+                .iter()
+                .find(|rule| mesh.name.starts_with(&rule.prefix))
             {
-                let trigger_name = if let Some(name) = name.strip_prefix("TC_") {
-                    Some(name)
-                } else if name == "GhostSpawn" {
-                    Some(name.as_str())
-                } else {
-                    None
-                };
-                if let Some(name) = trigger_name {
-                    let name = name.to_owned();
-                    info!("Found trigger cube: {:?}", name);
-                    let mesh = obj.meshes.remove(i);
-                    let mut min = mesh.geometry[0].a_v;
-                    let mut max = mesh.geometry[0].a_v;
-                    for v in mesh.geometry.iter() {
-                        min.x = min.x.min(v.a_v.x);
-                        min.y = min.y.min(v.a_v.y);
-                        min.z = min.z.min(v.a_v.z);
-                        max.x = max.x.max(v.a_v.x);
-                        max.y = max.y.max(v.a_v.y);
-                        max.z = max.z.max(v.a_v.z);
-                    }
-                    trigger_cubes.insert(
-                        name,
-                        TriggerCube {
-                            min_x: min.x,
-                            min_y: min.y,
-                            min_z: min.z,
-                            max_x: max.x,
-                            max_y: max.y,
-                            max_z: max.z,
-                        },
-                    );
-                }
+                rule.apply(mesh);
             }
-            if let Some(name) = name.strip_prefix("RDC_") {
-                let name = name.to_owned();
-                info!("Found room data: {:?}", name);
-                let mesh = obj.meshes.remove(i);
-                let mut min = mesh.geometry[0].a_v;
-                let mut max = mesh.geometry[0].a_v;
-                for v in mesh.geometry.iter() {
-                    min.x = min.x.min(v.a_v.x);
-                    min.y = min.y.min(v.a_v.y);
-                    min.z = min.z.min(v.a_v.z);
-                    max.x = max.x.max(v.a_v.x);
-                    max.y = max.y.max(v.a_v.y);
-                    max.z = max.z.max(v.a_v.z);
-                }
-                room_data.insert(
-                    name,
-                    TriggerCube {
-                        min_x: min.x,
-                        min_y: min.y,
-                        min_z: min.z,
-                        max_x: max.x,
-                        max_y: max.y,
-                        max_z: max.z,
-                    },
-                );
+            if let Some(&value) = hints.get(&mesh.name) {
+                update_symbol_uvs(mesh, value);
             }
         }
 
+        let skybox = take_meshes(&mut obj, &manifest.skybox).remove(0);
+        let mut trigger_cubes: HashMap<String, TriggerCube> = manifest
+            .triggers
+            .iter()
+            .map(|(name, mesh)| (name.clone(), bounding_cube(&take_meshes(&mut obj, mesh))))
+            .collect();
+        trigger_cubes.insert(
+            "GhostSpawn".to_owned(),
+            bounding_cube(&take_meshes(&mut obj, &manifest.ghost_spawn)),
+        );
+        let room_data: HashMap<String, TriggerCube> = manifest
+            .rooms
+            .iter()
+            .map(|(name, mesh)| (name.clone(), bounding_cube(&take_meshes(&mut obj, mesh))))
+            .collect();
+
+        let interactable_manifests: HashMap<&str, &InteractableManifest> = manifest
+            .interactables
+            .iter()
+            .map(|interactable| (interactable.mesh.as_str(), interactable))
+            .collect();
         let mut interactables = Vec::new();
         let mut planks = Vec::new();
         for i in (0..obj.meshes.len()).rev() {
-            if obj.meshes[i].name.starts_with("D_") || obj.meshes[i].name.starts_with("DR_") {
-                let mesh = obj.meshes.remove(i);
-                let pivot = mesh
-                    .geometry
-                    .iter()
-                    .max_by_key(|v| r32(v.a_vt.x))
-                    .unwrap()
-                    .a_v;
-                interactables.push(InteractableData {
-                    obj: Obj { meshes: vec![mesh] },
-                    typ: InteractableType::RDoor { pivot },
-                });
-            }
-            if obj.meshes[i].name.starts_with("DL_") {
-                let mesh = obj.meshes.remove(i);
-                let pivot = mesh
-                    .geometry
-                    .iter()
-                    .min_by_key(|v| r32(v.a_vt.x))
-                    .unwrap()
-                    .a_v;
-                interactables.push(InteractableData {
-                    obj: Obj { meshes: vec![mesh] },
-                    typ: InteractableType::LDoor { pivot },
-                });
-            }
-            if obj.meshes[i].name.starts_with("I_")
-                || obj.meshes[i].name.starts_with("B_SingingGirl")
-                || obj.meshes[i].name.starts_with("B_Candle")
-            {
-                let mesh = obj.meshes.remove(i);
-                info!("{}", mesh.name);
-                let front_face = mesh
-                    .geometry
-                    .chunks(3)
-                    .max_by_key(|face| face.iter().map(|v| r32(v.a_vt.x)).max().unwrap())
-                    .unwrap();
-                let shift = Vec3::cross(
-                    front_face[1].a_v - front_face[0].a_v,
-                    front_face[2].a_v - front_face[0].a_v,
-                )
-                .normalize_or_zero()
-                    * 0.3;
-                if mesh.name.starts_with("I_StorageButtonIcon") {
-                    for n in 0..4 {
-                        let mut mesh = ObjMesh {
-                            name: format!("{}{}", mesh.name, n),
-                            geometry: ugli::VertexBuffer::new_static(
-                                geng.ugli(),
-                                mesh.geometry.clone(),
-                            ),
-                            material: mesh.material.clone(),
-                        };
-                        update_storage_lock_uvs(&mut mesh, n);
-                        interactables.push(InteractableData {
-                            obj: Obj { meshes: vec![mesh] },
-                            typ: InteractableType::Drawer { shift },
-                        });
-                    }
-                } else if mesh.name.starts_with("I_LoosePlank") {
-                    planks.push(InteractableData {
-                        obj: Obj { meshes: vec![mesh] },
-                        typ: InteractableType::Drawer { shift },
-                    });
-                } else if mesh.name.contains("BookshelfLibrary") {
-                    interactables.push(InteractableData {
-                        obj: Obj { meshes: vec![mesh] },
-                        typ: InteractableType::Static,
-                    });
-                } else {
-                    interactables.push(InteractableData {
-                        obj: Obj { meshes: vec![mesh] },
-                        typ: InteractableType::Drawer { shift },
+            let interactable = match interactable_manifests.get(obj.meshes[i].name.as_str()) {
+                Some(&interactable) => interactable,
+                None => continue,
+            };
+            let mesh = obj.meshes.remove(i);
+            match interactable.kind {
+                InteractableKind::Door { hinge, pivot, .. } => {
+                    let pivot = pivot.unwrap_or_else(|| {
+                        let by_u = |v: &&Vertex| r32(v.a_vt.x);
+                        match hinge {
+                            DoorHinge::Left => mesh.geometry.iter().min_by_key(by_u),
+                            DoorHinge::Right => mesh.geometry.iter().max_by_key(by_u),
+                        }
+                        .unwrap()
+                        .a_v
                     });
+                    let typ = match hinge {
+                        DoorHinge::Left => InteractableType::LDoor { pivot },
+                        DoorHinge::Right => InteractableType::RDoor { pivot },
+                    };
+                    interactables.push(InteractableData::new(
+                        Obj { meshes: vec![mesh] },
+                        typ,
+                        interactable,
+                        collision,
+                    ));
+                }
+                InteractableKind::Static => {
+                    interactables.push(InteractableData::new(
+                        Obj { meshes: vec![mesh] },
+                        InteractableType::Static,
+                        interactable,
+                        collision,
+                    ));
+                }
+                InteractableKind::Drawer | InteractableKind::Plank => {
+                    info!("{}", mesh.name);
+                    let front_face = mesh
+                        .geometry
+                        .chunks(3)
+                        .max_by_key(|face| face.iter().map(|v| r32(v.a_vt.x)).max().unwrap())
+                        .unwrap();
+                    let shift = Vec3::cross(
+                        front_face[1].a_v - front_face[0].a_v,
+                        front_face[2].a_v - front_face[0].a_v,
+                    )
+                    .normalize_or_zero()
+                        * 0.3;
+                    if let Some(&symbols) = buttons.get(&mesh.name) {
+                        for n in 0..symbols {
+                            let mut mesh = ObjMesh {
+                                name: format!("{}{}", mesh.name, n),
+                                geometry: mesh.geometry.clone(),
+                                material: mesh.material.clone(),
+                            };
+                            update_symbol_uvs(&mut mesh, n);
+                            interactables.push(InteractableData::new(
+                                Obj { meshes: vec![mesh] },
+                                InteractableType::Drawer { shift },
+                                interactable,
+                                collision,
+                            ));
+                        }
+                    } else {
+                        let data = InteractableData::new(
+                            Obj { meshes: vec![mesh] },
+                            InteractableType::Drawer { shift },
+                            interactable,
+                            collision,
+                        );
+                        if interactable.kind == InteractableKind::Plank {
+                            planks.push(data);
+                        } else {
+                            interactables.push(data);
+                        }
+                    }
                 }
             }
         }
 
-        const PLANKS_N: usize = 4;
-        for _ in 0..PLANKS_N {
-            let i = global_rng().gen_range(0..planks.len());
-            interactables.push(planks.remove(i));
+        let mut chosen_planks = Vec::new();
+        match saved {
+            Some(saved) => {
+                for name in &saved.planks {
+                    if let Some(i) = planks
+                        .iter()
+                        .position(|plank| plank.obj.meshes[0].name == *name)
+                    {
+                        chosen_planks.push(name.clone());
+                        interactables.push(planks.remove(i));
+                    }
+                }
+            }
+            None => {
+                for _ in 0..manifest.planks.as_ref().map_or(0, |planks| planks.count) {
+                    let i = rng.gen_range(0..planks.len());
+                    chosen_planks.push(planks[i].obj.meshes[0].name.clone());
+                    interactables.push(planks.remove(i));
+                }
+            }
         }
 
+        let key_puzzles: Vec<(&String, &String, &String)> = puzzles
+            .iter()
+            .filter_map(|puzzle| match &puzzle.kind {
+                PuzzleKind::KeyMatch {
+                    key, keys, hint, ..
+                } => Some((key, keys, hint)),
+                _ => None,
+            })
+            .collect();
         let mut items = HashMap::<String, ItemData>::new();
         let mut key_configs = HashMap::new();
+        let item_names: HashMap<&str, &str> = manifest
+            .items
+            .iter()
+            .map(|spawn| (spawn.mesh.as_str(), spawn.item.as_str()))
+            .collect();
         for i in (0..obj.meshes.len()).rev() {
-            if let Some(name) = obj.meshes[i].name.strip_prefix("Spawn_") {
-                let mut name = name.to_owned();
+            if let Some(&name) = item_names.get(obj.meshes[i].name.as_str()) {
+                let name = name.to_owned();
                 let mut mesh = obj.meshes.remove(i);
-                if name.contains("StudyKey") {
-                    let config = KeyConfiguration::random();
+                if key_puzzles
+                    .iter()
+                    .any(|(_, keys, _)| name.contains(keys.as_str()))
+                {
+                    let config = saved
+                        .and_then(|saved| saved.key_configs.get(&name).copied())
+                        .unwrap_or_else(|| KeyConfiguration::random(rng));
                     update_key_uvs(&mut mesh, config);
                     key_configs.insert(name.clone(), config);
-                } else {
-                    if let Some(index) = name.find('.') {
-                        name = name[..index].to_owned();
-                    }
                 }
                 let mut sum = Vec3::ZERO;
                 for v in mesh.geometry.iter() {
@@ -350,60 +352,78 @@ impl LevelData {
             }
         }
 
+        if let Some(item) = manifest
+            .planks
+            .as_ref()
+            .and_then(|planks| planks.item.as_ref())
         {
-            let doll_spawns = &mut items.get_mut("Doll").unwrap().spawns;
-            doll_spawns.retain(|spawn| spawn.parent_interactable.is_some());
-            for spawn in doll_spawns.iter_mut() {
-                spawn.parent_interactable = None;
+            if let Some(data) = items.get_mut(item) {
+                data.spawns.retain(|spawn| {
+                    spawn
+                        .parent_interactable
+                        .as_ref()
+                        .map_or(false, |parent| chosen_planks.contains(parent))
+                });
+                for spawn in &mut data.spawns {
+                    spawn.parent_interactable = None;
+                }
             }
-            assert!(doll_spawns.len() == PLANKS_N);
         }
 
-        let hint_key_config = **key_configs
-            .values()
-            .collect::<Vec<_>>()
-            .choose(&mut global_rng())
-            .unwrap();
-        for interactable in &mut interactables {
-            if interactable.obj.meshes[0].name == "I_HintKey" {
-                update_key_uvs(&mut interactable.obj.meshes[0], hint_key_config);
+        // Sorted so that the choices only depend on the rng
+        let mut key_names: Vec<String> = key_configs.keys().cloned().collect();
+        key_names.sort();
+        for (key, keys, hint) in key_puzzles {
+            let names: Vec<&String> = key_names
+                .iter()
+                .filter(|name| name.contains(keys.as_str()))
+                .collect();
+            let hint_config = match saved.and_then(|saved| saved.key_configs.get(key)) {
+                Some(&config) => config,
+                None => match names.choose(rng) {
+                    Some(&name) => key_configs[name],
+                    None => {
+                        warn!("There are no {keys:?} keys for {key:?}");
+                        continue;
+                    }
+                },
+            };
+            for interactable in &mut interactables {
+                if interactable.obj.meshes[0].name == *hint {
+                    update_key_uvs(&mut interactable.obj.meshes[0], hint_config);
+                }
             }
-        }
-        let mut key_solution_item_data = ItemData {
-            spawns: vec![],
-            texture_aabb: AABB::point(Vec2::ZERO),
-        };
-        for (item_name, config) in &key_configs {
-            if *config == hint_key_config {
-                let data = items.remove(item_name).unwrap();
-                key_solution_item_data.spawns.extend(data.spawns);
+            let mut solution = ItemData {
+                spawns: vec![],
+                texture_aabb: AABB::point(Vec2::ZERO),
+            };
+            for name in names {
+                if key_configs[name] == hint_config {
+                    if let Some(data) = items.remove(name) {
+                        solution.spawns.extend(data.spawns);
+                    }
+                }
             }
+            items.insert(key.clone(), solution);
+            key_configs.insert(key.clone(), hint_config);
         }
-        items.insert(
-            "TheStudyKeyPuzzleSolution".to_owned(),
-            key_solution_item_data,
-        );
-        key_configs.insert("TheStudyKeyPuzzleSolution".to_owned(), hint_key_config);
 
         LevelData {
             room_data,
-            skybox: skybox.unwrap(),
+            skybox,
             key_configs,
-            storage_lock_combination,
-            hint_key_config,
+            combinations,
+            planks: chosen_planks,
             spawn_point: {
-                let index = obj
-                    .meshes
-                    .iter()
-                    .position(|mesh| mesh.name == "PlayerSpawn")
-                    .unwrap();
-                let mesh = obj.meshes.remove(index);
+                let mesh = &take_meshes(&mut obj, &manifest.player_spawn)[0];
                 let mut sum = Vec3::ZERO;
                 for v in mesh.geometry.iter() {
                     sum += v.a_v;
                 }
                 sum / mesh.geometry.len() as f32
             },
+            // After the spawn point is taken out of the obj
+            bvh: ObjBvh::new(&obj, collision),
             interactables: interactables.into_iter().map(Rc::new).collect(),
             items,
             obj,
@@ -0,0 +1,387 @@
+use super::*;
+
+use std::collections::BTreeMap;
+
+/// Side of the door it turns around, going by the texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoorHinge {
+    /// Where the texture starts
+    Left,
+    /// Where the texture ends
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InteractableKind {
+    Door {
+        hinge: DoorHinge,
+        /// Found from the texture coordinates if not given
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pivot: Option<Vec3<f32>>,
+        /// Always swings the same way instead of away from whoever opens it, like closet doors
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        fixed_swing: bool,
+    },
+    /// Slides out away from the face with the right side of the texture,
+    /// combination lock buttons are drawers too
+    Drawer,
+    Static,
+    /// Drawer that is only there in some of the runs
+    Plank,
+}
+
+impl InteractableKind {
+    /// Seconds it takes to open or close if the manifest does not say
+    pub fn default_open_time(&self) -> f32 {
+        match self {
+            Self::Door { .. } => 0.6,
+            _ => 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractableManifest {
+    pub mesh: String,
+    #[serde(flatten)]
+    pub kind: InteractableKind,
+    /// Seconds it takes to open or close, see [InteractableKind::default_open_time]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_time: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemSpawnManifest {
+    pub mesh: String,
+    /// Every spawn of the same item is a place it can show up at
+    pub item: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UvProjection {
+    /// From above, for floors and ceilings
+    Top,
+    /// From the side, for walls that go along the diagonals
+    Side,
+}
+
+/// Only some of the [InteractableKind::Plank]s are there in a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanksManifest {
+    /// How many of the planks are picked for a run
+    pub count: usize,
+    /// Hidden under the planks, only the spawns under the picked ones are kept
+    /// and they stay where they are when the plank is taken off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+}
+
+/// Texture coordinates computed from the positions, so that textures tile across meshes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UvRule {
+    /// Applies to every mesh with a name starting with this
+    pub prefix: String,
+    pub projection: UvProjection,
+    /// World units per texture
+    pub scale: f32,
+}
+
+impl UvRule {
+    pub fn apply(&self, mesh: &mut ObjMesh) {
+        for v in mesh.geometry.iter_mut() {
+            v.a_vt = match self.projection {
+                UvProjection::Top => v.a_v.xy(),
+                UvProjection::Side => vec2(v.a_v.x + v.a_v.y, v.a_v.z),
+            } / self.scale;
+        }
+    }
+}
+
+/// What the meshes of the level obj are, kept next to it as json.
+/// Meshes are referred to by name, every mesh with that name is used
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelManifest {
+    pub skybox: String,
+    pub player_spawn: String,
+    /// Also a trigger cube named `GhostSpawn`
+    pub ghost_spawn: String,
+    /// Meshes by trigger cube name
+    pub triggers: BTreeMap<String, String>,
+    /// Meshes by room name
+    pub rooms: BTreeMap<String, String>,
+    pub interactables: Vec<InteractableManifest>,
+    pub items: Vec<ItemSpawnManifest>,
+    /// Without it none of the planks are there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planks: Option<PlanksManifest>,
+    /// Meshes left out of the level
+    pub removed: Vec<String>,
+    pub uv_rules: Vec<UvRule>,
+}
+
+/// Everything that is wrong with a [LevelManifest]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelManifestError {
+    pub problems: Vec<String>,
+}
+
+impl std::fmt::Display for LevelManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid level manifest:")?;
+        for problem in &self.problems {
+            write!(f, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LevelManifestError {}
+
+impl LevelManifest {
+    /// Every mesh the manifest refers to with what it is used as
+    fn mesh_roles(&self) -> Vec<(&str, String)> {
+        let mut result = vec![
+            (self.skybox.as_str(), "skybox".to_owned()),
+            (self.player_spawn.as_str(), "player spawn".to_owned()),
+            (self.ghost_spawn.as_str(), "ghost spawn".to_owned()),
+        ];
+        result.extend(
+            self.triggers
+                .iter()
+                .map(|(name, mesh)| (mesh.as_str(), format!("trigger {name:?}"))),
+        );
+        result.extend(
+            self.rooms
+                .iter()
+                .map(|(name, mesh)| (mesh.as_str(), format!("room {name:?}"))),
+        );
+        result.extend(
+            self.interactables
+                .iter()
+                .map(|interactable| (interactable.mesh.as_str(), "interactable".to_owned())),
+        );
+        result.extend(
+            self.items
+                .iter()
+                .map(|spawn| (spawn.mesh.as_str(), format!("spawn of {:?}", spawn.item))),
+        );
+        result.extend(
+            self.removed
+                .iter()
+                .map(|mesh| (mesh.as_str(), "removed".to_owned())),
+        );
+        result
+    }
+
+    /// Checks that every mesh is there and is only used for one thing
+    pub fn validate(&self, obj: &Obj) -> Result<(), LevelManifestError> {
+        let mut problems = Vec::new();
+        let mut used = HashMap::<&str, String>::new();
+        for (mesh, role) in self.mesh_roles() {
+            if !obj.meshes.iter().any(|m| m.name == mesh) {
+                problems.push(format!("{role} mesh {mesh:?} is not in the obj"));
+            }
+            if let Some(other) = used.insert(mesh, role.clone()) {
+                problems.push(format!("mesh {mesh:?} is used as both {other} and {role}"));
+            }
+        }
+        if self.triggers.contains_key("GhostSpawn") {
+            problems.push("trigger \"GhostSpawn\" is taken by the ghost spawn".to_owned());
+        }
+        for spawn in &self.items {
+            if spawn.item.is_empty() {
+                problems.push(format!("spawn {:?} has no item name", spawn.mesh));
+            }
+        }
+        if let Some(planks) = &self.planks {
+            let available = self
+                .interactables
+                .iter()
+                .filter(|interactable| interactable.kind == InteractableKind::Plank)
+                .count();
+            if planks.count > available {
+                problems.push(format!(
+                    "{} planks are picked out of {available}",
+                    planks.count
+                ));
+            }
+            if let Some(item) = &planks.item {
+                if !self.items.iter().any(|spawn| spawn.item == *item) {
+                    problems.push(format!("plank item {item:?} has no spawns"));
+                }
+            }
+        }
+        for rule in &self.uv_rules {
+            if rule.scale.is_nan() || rule.scale <= 0.0 {
+                problems.push(format!(
+                    "uv rule for {:?} has a scale of {}",
+                    rule.prefix, rule.scale
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LevelManifestError { problems })
+        }
+    }
+
+    /// Manifest from the old mesh naming convention, like `TC_` for trigger cubes,
+    /// `D_`/`DL_`/`DR_` for doors, `I_` for drawers and `Spawn_` for items.
+    /// Closet doors always swing the same way and fence doors are slow,
+    /// half of the `I_LoosePlank`s are there with dolls under them
+    pub fn import(obj: &Obj) -> Self {
+        let mut result = Self {
+            skybox: "Skybox".to_owned(),
+            player_spawn: "PlayerSpawn".to_owned(),
+            ghost_spawn: "GhostSpawn".to_owned(),
+            triggers: BTreeMap::new(),
+            rooms: BTreeMap::new(),
+            interactables: Vec::new(),
+            items: Vec::new(),
+            planks: None,
+            removed: Vec::new(),
+            uv_rules: [
+                ("S_Grass", UvProjection::Top, 2.0),
+                ("S_Ceiling", UvProjection::Top, 2.0),
+                ("S_RefFloor", UvProjection::Top, 2.0),
+                ("S_Walls", UvProjection::Side, 2.0),
+                ("S_Fence", UvProjection::Side, 2.0),
+                ("S_Treeline", UvProjection::Side, 4.0),
+            ]
+            .into_iter()
+            .map(|(prefix, projection, scale)| UvRule {
+                prefix: prefix.to_owned(),
+                projection,
+                scale,
+            })
+            .collect(),
+        };
+        let mut seen = HashSet::new();
+        for mesh in &obj.meshes {
+            let name = mesh.name.clone();
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if name.starts_with("H_") {
+                result.removed.push(name);
+            } else if let Some(trigger) = name.strip_prefix("TC_") {
+                result.triggers.insert(trigger.to_owned(), name);
+            } else if let Some(room) = name.strip_prefix("RDC_") {
+                result.rooms.insert(room.to_owned(), name);
+            } else if name.starts_with("D_") || name.starts_with("DR_") || name.starts_with("DL_") {
+                let hinge = if name.starts_with("DL_") {
+                    DoorHinge::Left
+                } else {
+                    DoorHinge::Right
+                };
+                result.interactables.push(InteractableManifest {
+                    open_time: name.starts_with("DL_FenceDoor").then_some(2.0),
+                    kind: InteractableKind::Door {
+                        hinge,
+                        pivot: None,
+                        fixed_swing: name.contains("Closet"),
+                    },
+                    mesh: name,
+                });
+            } else if name.starts_with("I_")
+                || name.starts_with("B_SingingGirl")
+                || name.starts_with("B_Candle")
+            {
+                let kind = if name.starts_with("I_LoosePlank") {
+                    InteractableKind::Plank
+                } else if name.contains("BookshelfLibrary") {
+                    InteractableKind::Static
+                } else {
+                    InteractableKind::Drawer
+                };
+                result.interactables.push(InteractableManifest {
+                    mesh: name,
+                    kind,
+                    open_time: None,
+                });
+            } else if let Some(item) = name.strip_prefix("Spawn_") {
+                let item = if item.contains("StudyKey") {
+                    item
+                } else {
+                    item.split('.').next().unwrap()
+                };
+                result.items.push(ItemSpawnManifest {
+                    item: item.to_owned(),
+                    mesh: name,
+                });
+            }
+        }
+        let planks = result
+            .interactables
+            .iter()
+            .filter(|interactable| interactable.kind == InteractableKind::Plank)
+            .count();
+        if planks != 0 {
+            result.planks = Some(PlanksManifest {
+                count: planks / 2,
+                item: result
+                    .items
+                    .iter()
+                    .any(|spawn| spawn.item == "Doll")
+                    .then(|| "Doll".to_owned()),
+            });
+        }
+        result
+    }
+}
+
+/// Level obj together with its [LevelManifest]
+#[derive(Clone)]
+pub struct Level {
+    pub obj: Obj,
+    pub manifest: LevelManifest,
+}
+
+impl Level {
+    /// `level.obj` goes with `level.json`
+    pub fn manifest_path(obj_path: &std::path::Path) -> std::path::PathBuf {
+        obj_path.with_extension("json")
+    }
+
+    fn new(obj: Obj, manifest_path: &std::path::Path, manifest: &str) -> anyhow::Result<Self> {
+        let manifest: LevelManifest = serde_json::from_str(manifest)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", manifest_path.display()))?;
+        manifest
+            .validate(&obj)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", manifest_path.display()))?;
+        Ok(Self { obj, manifest })
+    }
+
+    /// Same as [Obj::load_headless]
+    pub fn load_headless(path: &std::path::Path) -> anyhow::Result<Self> {
+        let obj = Obj::load_headless(path)?;
+        let manifest_path = Self::manifest_path(path);
+        Self::new(
+            obj,
+            &manifest_path,
+            &std::fs::read_to_string(&manifest_path)?,
+        )
+    }
+}
+
+impl geng::LoadAsset for Level {
+    fn load(geng: &Geng, path: &std::path::Path) -> geng::AssetFuture<Self> {
+        let geng = geng.clone();
+        let path = path.to_owned();
+        async move {
+            let manifest_path = Self::manifest_path(&path);
+            let (obj, manifest) = future::try_join(
+                <Obj as geng::LoadAsset>::load(&geng, &path),
+                <String as geng::LoadAsset>::load(&geng, &manifest_path),
+            )
+            .await?;
+            Self::new(obj, &manifest_path, &manifest)
+        }
+        .boxed_local()
+    }
+    const DEFAULT_EXT: Option<&'static str> = Some("obj");
+}
@@ -1,12 +1,45 @@
 use super::*;
 
 mod load;
+mod manifest;
+mod registry;
 
 pub use load::*;
+pub use manifest::*;
+pub use registry::*;
 
 pub struct InteractableData {
     pub obj: Obj,
     pub typ: InteractableType,
+    /// What the [LevelManifest] says this is
+    pub kind: InteractableKind,
+    /// Seconds it takes to open or close
+    pub open_time: f32,
+    /// In the coordinates of [InteractableData::obj], see [InteractableState::matrix]
+    pub bvh: ObjBvh,
+}
+
+impl InteractableData {
+    pub fn new(
+        obj: Obj,
+        typ: InteractableType,
+        manifest: &InteractableManifest,
+        rules: &CollisionRules,
+    ) -> Self {
+        Self {
+            bvh: ObjBvh::new(&obj, rules),
+            obj,
+            typ,
+            kind: manifest.kind.clone(),
+            open_time: manifest
+                .open_time
+                .unwrap_or_else(|| manifest.kind.default_open_time()),
+        }
+    }
+
+    pub fn is_door(&self) -> bool {
+        matches!(self.kind, InteractableKind::Door { .. })
+    }
 }
 
 pub struct ItemSpawnData {
@@ -20,15 +53,46 @@ pub struct ItemData {
     pub texture_aabb: AABB<f32>,
 }
 
+/// Everything that is randomized when generating the level
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelRandomization {
+    pub combinations: HashMap<String, Vec<u8>>,
+    /// Also has the solution of every key puzzle
+    pub key_configs: HashMap<String, KeyConfiguration>,
+    pub planks: Vec<String>,
+}
+
 pub struct LevelData {
     pub obj: Obj,
+    /// Built once for [LevelData::obj], which never moves
+    pub bvh: ObjBvh,
     pub skybox: ObjMesh,
     pub items: HashMap<String, ItemData>,
+    /// Key items of the [PuzzleKind::KeyMatch] puzzles,
+    /// the solution of a puzzle has the configuration shown by its hint
     pub key_configs: HashMap<String, KeyConfiguration>,
-    pub hint_key_config: KeyConfiguration,
-    pub storage_lock_combination: [u8; 4],
+    /// Solutions of the combination lock puzzles by puzzle name
+    pub combinations: HashMap<String, Vec<u8>>,
+    pub planks: Vec<String>,
     pub interactables: Vec<Rc<InteractableData>>,
     pub spawn_point: Vec3<f32>,
     pub trigger_cubes: HashMap<String, TriggerCube>,
     pub room_data: HashMap<String, TriggerCube>,
 }
+
+impl LevelData {
+    /// Center of the `GhostSpawn` trigger cube, or the player spawn if the level has none
+    pub fn monster_spawn(&self) -> Vec3<f32> {
+        self.trigger_cubes
+            .get("GhostSpawn")
+            .map_or(self.spawn_point, |cube| cube.center())
+    }
+
+    pub fn randomization(&self) -> LevelRandomization {
+        LevelRandomization {
+            combinations: self.combinations.clone(),
+            key_configs: self.key_configs.clone(),
+            planks: self.planks.clone(),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::*;
+
+/// Entry of `levels.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelInfo {
+    pub id: String,
+    /// Shown in the level select
+    pub name: String,
+    /// Relative to the assets, the [LevelManifest] is next to it
+    pub obj: String,
+    pub navmesh: String,
+    /// Merged over `config.json` for this level, objects key by key,
+    /// so a level can have its own puzzles, interactables and events
+    #[serde(default)]
+    pub config: serde_json::Map<String, serde_json::Value>,
+    /// Level started after the ending of this one, instead of going back to the main menu
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+fn merge_json(base: &mut serde_json::Value, overrides: &serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_json(
+                    base.entry(key.clone()).or_insert(serde_json::Value::Null),
+                    value,
+                );
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+impl LevelInfo {
+    /// `base` is the contents of `config.json`, the sounds it plays are checked too
+    pub fn config(&self, base: &serde_json::Value) -> anyhow::Result<Config> {
+        let mut config = base.clone();
+        merge_json(&mut config, &serde_json::Value::Object(self.config.clone()));
+        let config: Config = serde_json::from_value(config)
+            .map_err(|e| anyhow::anyhow!("config of level {:?}: {e}", self.id))?;
+        config
+            .check_sfx()
+            .map_err(|e| anyhow::anyhow!("config of level {:?}: {e}", self.id))?;
+        Ok(config)
+    }
+}
+
+/// Everything a level needs to be played
+pub struct LevelAssets {
+    pub info: LevelInfo,
+    pub level: Level,
+    pub navmesh: NavMesh,
+    pub config: Rc<Config>,
+}
+
+/// Levels from `levels.json`, in the order they are listed in the level select
+pub struct LevelRegistry {
+    /// Never empty
+    pub levels: Vec<LevelAssets>,
+}
+
+impl LevelRegistry {
+    /// Checks that the ids are unique and the next levels exist
+    pub fn check(infos: &[LevelInfo]) -> anyhow::Result<()> {
+        if infos.is_empty() {
+            anyhow::bail!("there are no levels");
+        }
+        for (index, info) in infos.iter().enumerate() {
+            if infos[..index].iter().any(|other| other.id == info.id) {
+                anyhow::bail!("level {:?} is listed twice", info.id);
+            }
+            if let Some(next) = &info.next {
+                if !infos.iter().any(|other| other.id == *next) {
+                    anyhow::bail!("next level {next:?} of {:?} does not exist", info.id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LevelAssets> {
+        self.levels.iter().find(|level| level.info.id == id)
+    }
+
+    pub fn first(&self) -> &LevelAssets {
+        &self.levels[0]
+    }
+}
+
+impl geng::LoadAsset for LevelRegistry {
+    fn load(geng: &Geng, path: &std::path::Path) -> geng::AssetFuture<Self> {
+        let geng = geng.clone();
+        let path = path.to_owned();
+        async move {
+            let dir = path.parent().unwrap();
+            let infos: Vec<LevelInfo> =
+                serde_json::from_str(&<String as geng::LoadAsset>::load(&geng, &path).await?)?;
+            Self::check(&infos).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+            let base_config: serde_json::Value = serde_json::from_str(
+                &<String as geng::LoadAsset>::load(&geng, &dir.join("config.json")).await?,
+            )?;
+            let base_config = &base_config;
+            let levels = future::try_join_all(infos.into_iter().map(|info| {
+                let geng = geng.clone();
+                async move {
+                    let (level, navmesh) = future::try_join(
+                        <Level as geng::LoadAsset>::load(&geng, &dir.join(&info.obj)),
+                        <NavMesh as geng::LoadAsset>::load(&geng, &dir.join(&info.navmesh)),
+                    )
+                    .await?;
+                    let config = Rc::new(info.config(base_config)?);
+                    Ok::<_, anyhow::Error>(LevelAssets {
+                        info,
+                        level,
+                        navmesh,
+                        config,
+                    })
+                }
+            }))
+            .await?;
+            Ok(Self { levels })
+        }
+        .boxed_local()
+    }
+    const DEFAULT_EXT: Option<&'static str> = Some("json");
+}
@@ -1,10 +1,12 @@
 use super::*;
 
 mod config;
+mod cutscene;
 mod level;
 mod obj;
 
 pub use config::*;
+pub use cutscene::*;
 pub use level::*;
 pub use obj::*;
 
@@ -58,10 +60,10 @@ pub struct Assets {
     pub flashdark_dark: ugli::Texture,
     #[asset(path = "Music")]
     pub music: Music,
-    #[asset(path = "level/roomMVP.obj")]
-    pub level_obj: Obj,
+    #[asset(path = "levels.json")]
+    pub levels: LevelRegistry,
+    /// Same as the config of every level before its overrides
     pub config: Config,
-    pub navmesh: NavMesh,
     #[asset(path = "SFX")]
     pub sfx: SfxAssets,
     pub ui: UiAssets,
@@ -204,8 +206,24 @@ pub struct SfxAssets {
 }
 
 impl SfxAssets {
-    pub fn get_by_name(&self, name: &str) -> &geng::Sound {
-        match name {
+    /// Sound file names that [Sfx::Named] can play
+    pub const NAMED: &'static [&'static str] = &[
+        "placeObject.mp3",
+        "doorLocked.mp3",
+        "doorUnlocked.mp3",
+        "fusePlaced.mp3",
+        "curtainsOpen.mp3",
+        "curtainsClose.mp3",
+        "plankRemoval.mp3",
+        "girlGiggle.mp3",
+        "libraryClosetMove.mp3",
+        "SymbolsPuzzleButton.mp3",
+        "SymbolsPuzzleSolved.mp3",
+    ];
+
+    /// One of [SfxAssets::NAMED]
+    pub fn get_by_name(&self, name: &str) -> Option<&geng::Sound> {
+        Some(match name {
             "placeObject.mp3" => &self.place_object,
             "doorLocked.mp3" => &self.door_locked,
             "doorUnlocked.mp3" => &self.door_unlocked,
@@ -217,8 +235,11 @@ impl SfxAssets {
             "libraryClosetMove.mp3" => &self.library_closet_move,
             "SymbolsPuzzleButton.mp3" => &self.symbols_puzzle_button,
             "SymbolsPuzzleSolved.mp3" => &self.symbols_puzzle_solved,
-            _ => unreachable!(),
-        }
+            _ => {
+                warn!("There is no sound {name:?}");
+                return None;
+            }
+        })
     }
 }
 
@@ -5,8 +5,21 @@ pub struct Material {
     pub name: String,
     pub texture: Option<Rc<ugli::Texture>>,
     pub dark_texture: Option<Rc<ugli::Texture>>,
-    // pub ambient_color: Rgba<f32>,
-    // pub diffuse_color: Rgba<f32>,
+    pub ambient_color: Rgba<f32>,
+    pub diffuse_color: Rgba<f32>,
+}
+
+impl Material {
+    /// White, without any textures
+    pub fn untextured(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            texture: None,
+            dark_texture: None,
+            ambient_color: Rgba::WHITE,
+            diffuse_color: Rgba::WHITE,
+        }
+    }
 }
 
 #[derive(ugli::Vertex, Debug, Copy, Clone)]
@@ -18,12 +31,58 @@ pub struct Vertex {
     pub a_vn: Vec3<f32>,
 }
 
+/// Vertices of a mesh, only uploaded to the gpu once something draws them
+pub struct Geometry {
+    vertices: Vec<Vertex>,
+    buffer: RefCell<Option<ugli::VertexBuffer<Vertex>>>,
+}
+
+impl Geometry {
+    pub fn new(vertices: Vec<Vertex>) -> Self {
+        Self {
+            vertices,
+            buffer: RefCell::new(None),
+        }
+    }
+
+    pub fn buffer(&self, ugli: &ugli::Ugli) -> std::cell::Ref<ugli::VertexBuffer<Vertex>> {
+        if self.buffer.borrow().is_none() {
+            *self.buffer.borrow_mut() =
+                Some(ugli::VertexBuffer::new_static(ugli, self.vertices.clone()));
+        }
+        std::cell::Ref::map(self.buffer.borrow(), |buffer| buffer.as_ref().unwrap())
+    }
+}
+
+impl Clone for Geometry {
+    fn clone(&self) -> Self {
+        Self::new(self.vertices.clone())
+    }
+}
+
+impl std::ops::Deref for Geometry {
+    type Target = Vec<Vertex>;
+    fn deref(&self) -> &Vec<Vertex> {
+        &self.vertices
+    }
+}
+
+impl std::ops::DerefMut for Geometry {
+    fn deref_mut(&mut self) -> &mut Vec<Vertex> {
+        // Reupload on next draw
+        self.buffer.get_mut().take();
+        &mut self.vertices
+    }
+}
+
+#[derive(Clone)]
 pub struct ObjMesh {
     pub name: String,
-    pub geometry: ugli::VertexBuffer<Vertex>,
+    pub geometry: Geometry,
     pub material: Material,
 }
 
+#[derive(Clone)]
 pub struct Obj {
     pub meshes: Vec<ObjMesh>,
     // pub size: f32,
@@ -35,251 +94,442 @@ pub struct ObjInstance {
     pub i_color: Rgba<f32>,
 }
 
+/// Why an obj or mtl file could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjError {
+    pub file: String,
+    /// Starting from 1
+    pub line: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Path argument that may be quoted to have spaces in it, otherwise the last word,
+/// so that options like `-bm 1` in front are skipped
+fn path_argument(args: &str) -> &str {
+    let args = args.trim();
+    if let Some(quoted) = args.strip_suffix('"') {
+        if let Some((_, path)) = quoted.rsplit_once('"') {
+            return path;
+        }
+    }
+    args.split_whitespace().last().unwrap_or("")
+}
+
+/// First `N` numbers, anything after them (like `w` of a vertex) is ignored
+fn parse_floats<const N: usize>(args: &str) -> Result<[f32; N], String> {
+    let mut result = [0.0; N];
+    let mut parts = args.split_whitespace();
+    for x in &mut result {
+        let part = parts
+            .next()
+            .ok_or_else(|| format!("expected {N} numbers, got {args:?}"))?;
+        *x = part
+            .parse()
+            .map_err(|_| format!("{part:?} is not a number"))?;
+    }
+    Ok(result)
+}
+
+/// Indices start from 1, negative ones count back from the last one so far
+fn resolve_index<T: Copy>(items: &[T], index: &str, what: &str) -> Result<T, String> {
+    let i: isize = index
+        .parse()
+        .map_err(|_| format!("{index:?} is not a {what} index"))?;
+    let resolved = match i {
+        0 => None,
+        i if i > 0 => Some(i - 1),
+        i => Some(items.len() as isize + i),
+    };
+    resolved
+        .and_then(|i| usize::try_from(i).ok())
+        .and_then(|i| items.get(i))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "{what} index {i} is out of range, there are {} so far",
+                items.len()
+            )
+        })
+}
+
+struct ObjParser<'a> {
+    materials: &'a HashMap<String, Material>,
+    meshes: Vec<ObjMesh>,
+    current_name: String,
+    current_material: Material,
+    current_geometry: Vec<Vertex>,
+    v: Vec<Vec3<f32>>,
+    vn: Vec<Vec3<f32>>,
+    vt: Vec<Vec2<f32>>,
+}
+
+impl ObjParser<'_> {
+    fn finish_mesh(&mut self) {
+        if self.current_geometry.is_empty() {
+            return;
+        }
+        let mut material = self.current_material.clone();
+        if self.current_name.ends_with("_Dark") {
+            mem::swap(&mut material.texture, &mut material.dark_texture);
+        }
+        self.meshes.push(ObjMesh {
+            name: self.current_name.clone(),
+            geometry: Geometry::new(mem::take(&mut self.current_geometry)),
+            material,
+        });
+    }
+
+    /// Face vertex as `v`, `v/vt`, `v//vn` or `v/vt/vn`, without a normal if it has none
+    fn face_vertex(&self, s: &str) -> Result<(Vertex, bool), String> {
+        let mut parts = s.split('/');
+        let a_v = resolve_index(&self.v, parts.next().unwrap(), "vertex")?;
+        let a_vt = match parts.next() {
+            None | Some("") => vec2(0.0, 0.0),
+            Some(i) => resolve_index(&self.vt, i, "texture coordinate")?,
+        };
+        let a_vn = match parts.next() {
+            None | Some("") => None,
+            Some(i) => Some(resolve_index(&self.vn, i, "normal")?),
+        };
+        if parts.next().is_some() {
+            return Err(format!("{s:?} has too many indices"));
+        }
+        Ok((
+            Vertex {
+                a_b: 0.0,
+                a_v,
+                a_bv: Vec3::ZERO,
+                a_vt,
+                a_vn: a_vn.unwrap_or(Vec3::ZERO),
+            },
+            a_vn.is_some(),
+        ))
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim();
+        let (keyword, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args = args.trim();
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(args)?;
+                self.v.push(vec3(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(args)?;
+                self.vn.push(vec3(x, y, z));
+            }
+            "vt" => {
+                let [x, y] = parse_floats(args)?;
+                self.vt.push(vec2(x, y));
+            }
+            "f" => {
+                let vertices = args
+                    .split_whitespace()
+                    .map(|s| self.face_vertex(s))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if vertices.len() < 3 {
+                    return Err(format!(
+                        "face needs at least 3 vertices, got {}",
+                        vertices.len()
+                    ));
+                }
+                for i in 2..vertices.len() {
+                    let mut tri = [vertices[0], vertices[i - 1], vertices[i]];
+                    // Flat normals for vertices that come without them
+                    let normal =
+                        Vec3::cross(tri[1].0.a_v - tri[0].0.a_v, tri[2].0.a_v - tri[0].0.a_v)
+                            .normalize_or_zero();
+                    for (vertex, has_normal) in &mut tri {
+                        if !*has_normal {
+                            vertex.a_vn = normal;
+                        }
+                    }
+                    self.current_geometry.extend(tri.map(|(vertex, _)| vertex));
+                }
+            }
+            "o" | "g" => {
+                self.finish_mesh();
+                self.current_name = args.to_owned();
+            }
+            "usemtl" => {
+                self.finish_mesh();
+                self.current_material = self
+                    .materials
+                    .get(args)
+                    .ok_or_else(|| format!("unknown material {args:?}"))?
+                    .clone();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Obj {
+    pub fn mtl_libs(source: &str) -> impl Iterator<Item = &str> {
+        source
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("mtllib "))
+            .map(path_argument)
+    }
+
+    /// `file` is only used for error messages
+    pub fn parse(
+        file: &str,
+        source: &str,
+        materials: &HashMap<String, Material>,
+    ) -> Result<Self, ObjError> {
+        let mut parser = ObjParser {
+            materials,
+            meshes: Vec::new(),
+            current_name: String::from("__unnamed__"),
+            current_material: Material::untextured(""),
+            current_geometry: Vec::new(),
+            v: Vec::new(),
+            vn: Vec::new(),
+            vt: Vec::new(),
+        };
+        for (index, line) in source.lines().enumerate() {
+            parser.line(line).map_err(|reason| ObjError {
+                file: file.to_owned(),
+                line: index + 1,
+                reason,
+            })?;
+        }
+        parser.finish_mesh();
+        let mut meshes = parser.meshes;
+        for mesh in &mut meshes {
+            if mesh.name.starts_with("B_") {
+                let center = find_center(&mesh.geometry);
+                for v in mesh.geometry.iter_mut() {
+                    v.a_b = 1.0;
+                    v.a_bv = v.a_v - center;
+                    v.a_v = center;
+                }
+            }
+        }
+        for i in (0..meshes.len()).rev() {
+            for j in ((i + 1)..meshes.len()).rev() {
+                fn mergable(name: &str) -> bool {
+                    if name.starts_with("D")
+                        || name.starts_with("I")
+                        || name.starts_with("RDC")
+                        || name.starts_with("TC")
+                        || name.contains("Spawn")
+                        || name.contains("Light")
+                        || name.contains("Symbol")
+                        || name.contains("SwingingSwing")
+                        || name.contains("SingingGirl")
+                        || name.contains("S_PianoKeys")
+                        || name.starts_with("AF_")
+                        || name.starts_with("B_Candle")
+                        || name.starts_with("S_MusicBox")
+                        || name.starts_with("S_Bed")
+                    // TODO || name.starts_with("B_")
+                    {
+                        return false;
+                    }
+                    true
+                }
+                if meshes[i].material.name == meshes[j].material.name
+                    && mergable(&meshes[i].name)
+                    && mergable(&meshes[j].name)
+                {
+                    let append = meshes
+                        .remove(j)
+                        .geometry
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    meshes[i].geometry.extend(append);
+                }
+            }
+        }
+        info!(
+            "{:?}",
+            meshes
+                .iter()
+                .map(|mesh| mesh.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        Ok(Obj {
+            meshes,
+            // size,
+        })
+    }
+
+    /// Loads the geometry without any textures, so it works without a window
+    pub fn load_headless(path: &std::path::Path) -> anyhow::Result<Self> {
+        let dir = path.parent().unwrap();
+        let obj_source = std::fs::read_to_string(path)?;
+        let mut materials = HashMap::<String, Material>::new();
+        for mtl_path in Self::mtl_libs(&obj_source) {
+            let mtl_path = dir.join(mtl_path);
+            let mtl_source = std::fs::read_to_string(&mtl_path)?;
+            for mtl in parse_mtl(&mtl_path.to_string_lossy(), &mtl_source)? {
+                materials.insert(mtl.name.clone(), mtl.material(None, None));
+            }
+        }
+        Ok(Self::parse(
+            &path.to_string_lossy(),
+            &obj_source,
+            &materials,
+        )?)
+    }
+}
+
 impl geng::LoadAsset for Obj {
     fn load(geng: &Geng, path: &std::path::Path) -> geng::AssetFuture<Self> {
         let geng = geng.clone();
         let path = path.to_owned();
         async move {
             let dir = path.parent().unwrap();
-            let mut meshes = Vec::new();
             let obj_source = <String as geng::LoadAsset>::load(&geng, &path).await?;
-            let mut current_name = String::from("__unnamed__");
-            let mut v = Vec::new();
-            let mut vn = Vec::new();
-            let mut vt = Vec::new();
-            let mut current_material: Option<Material> = Some(Material {
-                name: "".to_owned(),
-                texture: None,
-                dark_texture: None,
-                // ambient_color: Rgba::WHITE,
-                // diffuse_color: Rgba::WHITE,
-            });
-            let mut current_geometry = Vec::new();
             let mut materials = HashMap::<String, Material>::new();
-            for line in obj_source.lines().chain(std::iter::once("o _")) {
-                if line.starts_with("v ") {
-                    let mut parts = line.split_whitespace();
-                    parts.next();
-                    let x: f32 = parts.next().unwrap().parse().unwrap();
-                    let y: f32 = parts.next().unwrap().parse().unwrap();
-                    let z: f32 = parts.next().unwrap().parse().unwrap();
-                    v.push(vec3(x, y, z));
-                } else if line.starts_with("vn ") {
-                    let mut parts = line.split_whitespace();
-                    parts.next();
-                    let x: f32 = parts.next().unwrap().parse().unwrap();
-                    let y: f32 = parts.next().unwrap().parse().unwrap();
-                    let z: f32 = parts.next().unwrap().parse().unwrap();
-                    vn.push(vec3(x, y, z));
-                } else if line.starts_with("vt ") {
-                    let mut parts = line.split_whitespace();
-                    parts.next();
-                    let x: f32 = parts.next().unwrap().parse().unwrap();
-                    let y: f32 = parts.next().unwrap().parse().unwrap();
-                    vt.push(vec2(x, y));
-                } else if line.starts_with("f ") {
-                    let mut parts = line.split_whitespace();
-                    parts.next();
-                    let to_vertex = |s: &str| {
-                        let mut parts = s.split('/');
-                        let i_v: usize = parts.next().unwrap().parse().unwrap();
-                        let i_vt: Option<usize> = match parts.next().unwrap() {
-                            "" => None,
-                            s => Some(s.parse().unwrap()),
-                        };
-                        let i_vn: usize = parts.next().unwrap().parse().unwrap();
-                        Vertex {
-                            a_b: 0.0,
-                            a_v: v[i_v - 1],
-                            a_bv: Vec3::ZERO,
-                            a_vn: vn[i_vn - 1],
-                            a_vt: match i_vt {
-                                Some(i_vt) => vt[i_vt - 1],
-                                None => vec2(0.0, 0.0),
-                            },
-                        }
-                    };
-                    let mut cur = Vec::new();
-                    for s in parts {
-                        cur.push(to_vertex(s));
-                    }
-                    for i in 2..cur.len() {
-                        current_geometry.push(cur[0]);
-                        current_geometry.push(cur[i - 1]);
-                        current_geometry.push(cur[i]);
-                    }
-                } else if line.starts_with("o ")
-                    || line.starts_with("g ")
-                    || line.starts_with("usemtl ")
-                {
-                    if !current_geometry.is_empty() {
-                        meshes.push(ObjMesh {
-                            name: current_name.clone(),
-                            geometry: ugli::VertexBuffer::new_static(geng.ugli(), current_geometry),
-                            material: {
-                                let mut result = current_material.clone().unwrap();
-                                if current_name.ends_with("_Dark") {
-                                    mem::swap(&mut result.texture, &mut result.dark_texture);
-                                }
-                                result
-                            },
-                        });
-                        current_geometry = Vec::new();
-                    }
-                    if line.starts_with("o ") || line.starts_with("g ") {
-                        current_name = String::from(&line[2..]);
-                    } else if let Some(name) = line.strip_prefix("usemtl ") {
-                        current_material = Some(materials[name].clone());
-                    }
-                } else if let Some(mtl_path) = line.strip_prefix("mtllib ") {
-                    for material in parse_mtl(&geng, dir, &dir.join(mtl_path)).await? {
-                        materials.insert(material.name.clone(), material);
-                    }
+            for mtl_path in Obj::mtl_libs(&obj_source) {
+                for material in load_mtl(&geng, dir, &dir.join(mtl_path)).await? {
+                    materials.insert(material.name.clone(), material);
                 }
             }
-            for mesh in &mut meshes {
-                if mesh.name.starts_with("B_") {
-                    let center = find_center(&mesh.geometry);
-                    for v in mesh.geometry.iter_mut() {
-                        v.a_b = 1.0;
-                        v.a_bv = v.a_v - center;
-                        v.a_v = center;
-                    }
+            Ok(Obj::parse(
+                &path.to_string_lossy(),
+                &obj_source,
+                &materials,
+            )?)
+        }
+        .boxed_local()
+    }
+    const DEFAULT_EXT: Option<&'static str> = Some("obj");
+}
+
+/// Material as described in an mtl file, before its textures are loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// Relative to the mtl file
+    pub texture: Option<String>,
+    pub ambient_color: Rgba<f32>,
+    pub diffuse_color: Rgba<f32>,
+}
+
+impl MtlMaterial {
+    pub fn material(
+        &self,
+        texture: Option<Rc<ugli::Texture>>,
+        dark_texture: Option<Rc<ugli::Texture>>,
+    ) -> Material {
+        Material {
+            name: self.name.clone(),
+            texture,
+            dark_texture,
+            ambient_color: self.ambient_color,
+            diffuse_color: self.diffuse_color,
+        }
+    }
+
+    /// `Texture_Dark.png` next to `Texture.png`
+    pub fn dark_texture(&self) -> Option<String> {
+        let texture = self.texture.as_ref()?;
+        Some(match texture.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() && !ext.contains(['/', '\\']) => {
+                format!("{stem}_Dark.{ext}")
+            }
+            _ => format!("{texture}_Dark"),
+        })
+    }
+}
+
+/// `file` is only used for error messages
+pub fn parse_mtl(file: &str, source: &str) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials = Vec::<MtlMaterial>::new();
+    for (index, line) in source.lines().enumerate() {
+        let error = |reason: String| ObjError {
+            file: file.to_owned(),
+            line: index + 1,
+            reason,
+        };
+        let line = line.trim();
+        let (keyword, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args = args.trim();
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial {
+                name: args.to_owned(),
+                texture: None,
+                ambient_color: Rgba::WHITE,
+                diffuse_color: Rgba::WHITE,
+            });
+            continue;
+        }
+        if !matches!(keyword, "map_Kd" | "Ka" | "Kd") {
+            continue;
+        }
+        let current = match materials.last_mut() {
+            Some(current) => current,
+            None => return Err(error(format!("{keyword} before any newmtl"))),
+        };
+        match keyword {
+            "map_Kd" => {
+                let texture = path_argument(args);
+                // WTF .
+                if texture.is_empty() || texture == "." {
+                    continue;
                 }
+                current.texture = Some(texture.to_owned());
             }
-            for i in (0..meshes.len()).rev() {
-                for j in ((i + 1)..meshes.len()).rev() {
-                    fn mergable(name: &str) -> bool {
-                        if name.starts_with("D")
-                            || name.starts_with("I")
-                            || name.starts_with("RDC")
-                            || name.starts_with("TC")
-                            || name.contains("Spawn")
-                            || name.contains("Light")
-                            || name.contains("Symbol")
-                            || name.contains("SwingingSwing")
-                            || name.contains("SingingGirl")
-                            || name.contains("S_PianoKeys")
-                            || name.starts_with("AF_")
-                            || name.starts_with("B_Candle")
-                            || name.starts_with("S_MusicBox")
-                        // TODO || name.starts_with("B_")
-                        {
-                            return false;
-                        }
-                        true
-                    }
-                    if meshes[i].material.name == meshes[j].material.name
-                        && mergable(&meshes[i].name)
-                        && mergable(&meshes[j].name)
-                    {
-                        let append = meshes
-                            .remove(j)
-                            .geometry
-                            .iter()
-                            .cloned()
-                            .collect::<Vec<_>>();
-                        meshes[i].geometry.extend(append);
-                    }
+            "Ka" | "Kd" => {
+                let [r, g, b] = parse_floats(args).map_err(error)?;
+                let color = Rgba::new(r, g, b, 1.0);
+                if keyword == "Ka" {
+                    current.ambient_color = color;
+                } else {
+                    current.diffuse_color = color;
                 }
             }
-            info!(
-                "{:?}",
-                meshes
-                    .iter()
-                    .map(|mesh| mesh.name.as_str())
-                    .collect::<Vec<_>>()
-            );
-            Ok(Obj {
-                meshes,
-                // size,
-            })
+            _ => unreachable!(),
         }
-        .boxed_local()
     }
-    const DEFAULT_EXT: Option<&'static str> = Some("obj");
+    Ok(materials)
 }
 
-async fn parse_mtl(
+async fn load_mtl(
     geng: &Geng,
     dir: &std::path::Path,
     path: &std::path::Path,
 ) -> anyhow::Result<Vec<Material>> {
-    struct MaterialFuture {
-        name: String,
-        texture: geng::AssetFuture<Option<ugli::Texture>>,
-        dark_texture: geng::AssetFuture<Option<ugli::Texture>>,
-    }
-
-    impl MaterialFuture {
-        async fn into_future(self) -> Material {
-            let (texture, dark_texture) = future::join(self.texture, self.dark_texture).await;
-            let texture = texture.unwrap().map(Rc::new);
-            let dark_texture = dark_texture.unwrap().map(Rc::new);
-            Material {
-                name: self.name,
-                texture,
-                dark_texture,
-            }
+    let load_texture = |texture: Option<String>| {
+        let geng = geng.clone();
+        let path = texture.map(|texture| dir.join(texture));
+        async move {
+            Ok::<_, anyhow::Error>(match path {
+                Some(path) => {
+                    let mut texture =
+                        <ugli::Texture as geng::LoadAsset>::load(&geng, &path).await?;
+                    make_repeated(&mut texture);
+                    Some(Rc::new(texture))
+                }
+                None => None,
+            })
         }
-    }
-
-    let mut materials = Vec::<MaterialFuture>::new();
+    };
     let mtl_source = <String as geng::LoadAsset>::load(geng, path).await?;
-    let mut current_texture = future::ready(Ok(None)).boxed_local();
-    let mut current_dark_texture = future::ready(Ok(None)).boxed_local();
-    let mut current_name = "__unnamed__";
-    let mut current_ambient_color = Rgba::WHITE;
-    let mut current_diffuse_color = Rgba::WHITE;
-    for line in mtl_source.lines().chain(std::iter::once("newmtl _")) {
-        if let Some(texture_path) = line.strip_prefix("map_Kd ") {
-            let texture_path = texture_path.split_whitespace().last().unwrap();
-            // WTF .
-            if texture_path != "." {
-                current_texture =
-                    <ugli::Texture as geng::LoadAsset>::load(&geng, &dir.join(texture_path))
-                        .map_ok(|mut texture| {
-                            make_repeated(&mut texture);
-                            Some(texture)
-                        })
-                        .boxed_local();
-                current_dark_texture = <ugli::Texture as geng::LoadAsset>::load(
-                    &geng,
-                    &dir.join(texture_path.strip_suffix(".png").unwrap().to_owned() + "_Dark.png"),
-                )
-                .map_ok(|mut texture| {
-                    make_repeated(&mut texture);
-                    Some(texture)
-                })
-                .map(|result| Ok(result.ok().flatten()))
-                .boxed_local();
-            }
-        } else if let Some(name) = line.strip_prefix("newmtl ") {
-            let name = name.trim();
-            materials.push(MaterialFuture {
-                name: current_name.to_owned(),
-                texture: mem::replace(&mut current_texture, future::ready(Ok(None)).boxed_local()),
-                dark_texture: mem::replace(
-                    &mut current_dark_texture,
-                    future::ready(Ok(None)).boxed_local(),
-                ),
-                // ambient_color: current_ambient_color,
-                // diffuse_color: current_diffuse_color,
-            });
-            current_name = name;
-        } else if let Some(color) = line.strip_prefix("Ka ") {
-            let mut parts = color.split_whitespace();
-            let r: f32 = parts.next().unwrap().parse().unwrap();
-            let g: f32 = parts.next().unwrap().parse().unwrap();
-            let b: f32 = parts.next().unwrap().parse().unwrap();
-            current_ambient_color = Rgba::new(r, g, b, 1.0);
-        } else if let Some(color) = line.strip_prefix("Kd ") {
-            let mut parts = color.split_whitespace();
-            let r: f32 = parts.next().unwrap().parse().unwrap();
-            let g: f32 = parts.next().unwrap().parse().unwrap();
-            let b: f32 = parts.next().unwrap().parse().unwrap();
-            current_diffuse_color = Rgba::new(r, g, b, 1.0);
+    let mtls = parse_mtl(&path.to_string_lossy(), &mtl_source)?;
+    future::try_join_all(mtls.into_iter().map(|mtl| {
+        let texture = load_texture(mtl.texture.clone());
+        // Dark versions are optional
+        let dark_texture = load_texture(mtl.dark_texture()).map(|result| Ok(result.ok().flatten()));
+        async move {
+            let (texture, dark_texture) = future::try_join(texture, dark_texture).await?;
+            Ok::<_, anyhow::Error>(mtl.material(texture, dark_texture))
         }
-    }
-    Ok(future::join_all(materials.into_iter().map(MaterialFuture::into_future)).await)
+    }))
+    .await
 }
@@ -0,0 +1,191 @@
+use super::*;
+
+impl SfxAssets {
+    pub fn get(&self, sfx: &Sfx) -> Option<&geng::Sound> {
+        Some(match sfx {
+            Sfx::Footstep => self.footsteps.choose(&mut global_rng()).unwrap(),
+            Sfx::FootstepCreak => self.footstep_creaks.choose(&mut global_rng()).unwrap(),
+            Sfx::FootstepGrass => self.footsteps_grass.choose(&mut global_rng()).unwrap(),
+            Sfx::GhostScream => &self.ghost_scream,
+            Sfx::GhostAlarmed => self.ghost_alarmed.choose(&mut global_rng()).unwrap(),
+            Sfx::FlashOn => &self.flash_on,
+            Sfx::FlashOff => &self.flash_off,
+            Sfx::BrokenFlashlight => &self.broken_flashlight,
+            Sfx::LightFlicker => &self.light_flicker,
+            Sfx::StudyLights => &self.study_lights,
+            Sfx::BlowCandle => &self.blow_candle,
+            Sfx::Ending => &self.ending,
+            Sfx::GenericPickup => &self.generic_pickup,
+            Sfx::Bat => &self.bat,
+            Sfx::GrandClock => &self.grand_clock,
+            Sfx::DoorOpen => &self.door_open,
+            Sfx::DoorClose => &self.door_close,
+            Sfx::DrawerOpen => &self.drawer_open,
+            Sfx::DrawerClose => &self.drawer_close,
+            Sfx::SymbolsPuzzleButton => &self.symbols_puzzle_button,
+            Sfx::SymbolsPuzzleSolved => &self.symbols_puzzle_solved,
+            Sfx::Named(name) => return self.get_by_name(name),
+        })
+    }
+}
+
+impl Game {
+    /// Plays everything the world asked for since the last call, and keeps the save up to date
+    pub fn handle_world_events(&mut self) {
+        for event in self.world.take_events() {
+            match event {
+                WorldEvent::Sfx {
+                    sfx,
+                    pos,
+                    max_distance,
+                    volume,
+                } => {
+                    let mut effect = match self.assets.sfx.get(&sfx) {
+                        Some(sound) => sound.effect(),
+                        None => continue,
+                    };
+                    if let Some(pos) = pos {
+                        effect.set_position(pos.map(|x| x as f64));
+                    }
+                    if let Some(max_distance) = max_distance {
+                        effect.set_max_distance(max_distance);
+                    }
+                    if let Some(volume) = volume {
+                        effect.set_volume(volume);
+                    }
+                    effect.play();
+                }
+                WorldEvent::Music(music) => {
+                    if let Some(mut music) = self.music.take() {
+                        music.stop();
+                    }
+                    self.music = Some(match music {
+                        BackgroundMusic::Outside => self.assets.music.outside.play(),
+                        BackgroundMusic::Ambient => {
+                            let mut music = self.assets.sfx.ambient.effect();
+                            music.set_volume(0.8);
+                            music.play();
+                            music
+                        }
+                    });
+                }
+                WorldEvent::StartTrack(track) => self.start_track(track),
+                WorldEvent::StopTrack(track) => {
+                    if let Some(mut sfx) = self.track_slot(track).take() {
+                        sfx.stop();
+                    }
+                }
+                WorldEvent::StopSounds => self.stop_sounds(),
+                WorldEvent::CheckpointReached => self.save(),
+                WorldEvent::GameOver => SaveData::clear(),
+            }
+        }
+    }
+
+    fn track_slot(&mut self, track: Track) -> &mut Option<geng::SoundEffect> {
+        match track {
+            Track::TvNoise => &mut self.tv_noise,
+            Track::Swing => &mut self.swing_sfx,
+            Track::CreepySinging => &mut self.creepy_sing_sfx,
+            Track::MusicBox => &mut self.music_box_sfx,
+            Track::Intro => &mut self.intro_sfx,
+            Track::GameOver => &mut self.game_over_sfx,
+        }
+    }
+
+    fn start_track(&mut self, track: Track) {
+        let level = &self.world.level;
+        let sfx = match track {
+            Track::TvNoise => {
+                let mut sfx = self.assets.sfx.tv_static.effect();
+                let pos = level.monster_spawn();
+                sfx.set_position(pos.map(|x| x as f64));
+                sfx.set_max_distance(2.0);
+                sfx.play();
+                sfx
+            }
+            Track::Swing => {
+                let swing = match level.trigger_cubes.get("SwingingSwing") {
+                    Some(swing) => swing,
+                    None => return,
+                };
+                let mut sfx = self.assets.sfx.swing_loop.effect();
+                sfx.set_position(
+                    swing
+                        .center()
+                        .xy()
+                        .extend(self.world.camera.pos.z)
+                        .map(|x| x as f64),
+                );
+                sfx.play();
+                sfx
+            }
+            Track::CreepySinging => {
+                let girl = match level
+                    .interactables
+                    .iter()
+                    .find(|i| i.obj.meshes[0].name == "B_SingingGirl")
+                {
+                    Some(girl) => girl,
+                    None => return,
+                };
+                let mut sfx = self.assets.music.creepy_singing.effect();
+                sfx.set_position(find_center(&girl.obj.meshes[0].geometry).map(|x| x as f64));
+                sfx.set_max_distance(self.assets.config.max_sound_distance);
+                sfx.play();
+                sfx
+            }
+            Track::MusicBox => {
+                let music_box = match level.obj.meshes.iter().find(|m| m.name == "S_MusicBox") {
+                    Some(music_box) => music_box,
+                    None => return,
+                };
+                let mut sfx = self.assets.music.music_box.effect();
+                sfx.set_position(find_center(&music_box.geometry).map(|x| x as f64));
+                sfx.set_max_distance(self.assets.config.max_sound_distance);
+                sfx.play();
+                sfx
+            }
+            Track::Intro => self.assets.sfx.intro_sequence.play(),
+            Track::GameOver => self.assets.sfx.jumpscare.play(),
+        };
+        if let Some(mut old) = self.track_slot(track).replace(sfx) {
+            old.stop();
+        }
+    }
+
+    pub fn update_audio(&mut self, delta_time: f32) {
+        self.handle_world_events();
+
+        let camera = &self.world.camera;
+        self.geng
+            .audio()
+            .set_listener_position(camera.pos.map(|x| x as f64));
+        self.geng.audio().set_listener_orientation(
+            { Mat4::rotate_z(camera.rot_h) * vec4(0.0, 1.0, 0.0, 1.0) }
+                .xyz()
+                .map(|x| x as f64),
+            vec3(0.0, 0.0, 1.0),
+        );
+
+        if self.world.main_menu || self.in_settings {
+            return;
+        }
+        if let (Some(sfx), Some(swing)) = (
+            &mut self.swing_sfx,
+            self.world.level.trigger_cubes.get("SwingingSwing"),
+        ) {
+            let pos = swing.center().xy().extend(camera.pos.z);
+            let ref_distance = (pos - camera.pos)
+                .len()
+                .max(1.0)
+                .min(self.current_swing_ref_distance);
+            self.current_swing_ref_distance = ref_distance;
+            sfx.set_ref_distance(ref_distance as f64);
+            sfx.set_max_distance(ref_distance as f64 + self.assets.config.max_sound_distance - 1.0);
+        }
+        if !self.world.game_over && !self.world.ending {
+            self.update_monster_sounds(delta_time);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Bakes the navmesh of every level in static/assets/levels.json without opening a window.
+//! Fails if the monster can not get to some of the rooms or trigger cubes from where it spawns.
+//! With `--check` nothing is saved, instead it fails if a fresh bake differs from the committed one
+
+use flashdark::*;
+use geng::prelude::*;
+
+/// How far apart the positions of the fresh and the committed bake can be
+const TOLERANCE: f32 = 1e-3;
+
+fn close(a: Vec3<f32>, b: Vec3<f32>) -> bool {
+    (a - b).len() < TOLERANCE
+}
+
+/// First thing that differs between the two navmeshes, if any
+fn difference(baked: &NavMesh, committed: &NavMesh) -> Option<String> {
+    if baked.waypoints.len() != committed.waypoints.len() {
+        return Some(format!(
+            "{} waypoints instead of {}",
+            baked.waypoints.len(),
+            committed.waypoints.len(),
+        ));
+    }
+    if baked.polygons.len() != committed.polygons.len() {
+        return Some(format!(
+            "{} polygons instead of {}",
+            baked.polygons.len(),
+            committed.polygons.len(),
+        ));
+    }
+    for (index, (a, b)) in baked.waypoints.iter().zip(&committed.waypoints).enumerate() {
+        if !close(*a, *b) {
+            return Some(format!("waypoint {index} is at {a:?} instead of {b:?}"));
+        }
+    }
+    for (index, (a, b)) in baked.edges.iter().zip(&committed.edges).enumerate() {
+        if a != b {
+            return Some(format!("waypoint {index} has edges {a:?} instead of {b:?}"));
+        }
+    }
+    for (index, (a, b)) in baked.polygons.iter().zip(&committed.polygons).enumerate() {
+        if a.vertices.len() != b.vertices.len()
+            || a.vertices
+                .iter()
+                .zip(&b.vertices)
+                .any(|(a, b)| !close(*a, *b))
+        {
+            return Some(format!(
+                "polygon {index} has vertices {:?} instead of {:?}",
+                a.vertices, b.vertices,
+            ));
+        }
+        if a.portals.len() != b.portals.len()
+            || a.portals
+                .iter()
+                .zip(&b.portals)
+                .any(|(a, b)| a.to != b.to || !close(a.a, b.a) || !close(a.b, b.b))
+        {
+            return Some(format!("polygon {index} has different portals"));
+        }
+    }
+    None
+}
+
+/// Returns whether the navmesh is fine
+fn bake(
+    assets: &std::path::Path,
+    info: &LevelInfo,
+    base_config: &serde_json::Value,
+    check: bool,
+) -> anyhow::Result<bool> {
+    let config = info.config(base_config)?;
+    let level = Level::load_headless(&assets.join(&info.obj))?;
+    // Randomization only moves items around, the geometry is always the same
+    let level = LevelData::generate(
+        &level,
+        &config.puzzles,
+        &config.collision_layers,
+        None,
+        &mut StdRng::seed_from_u64(0),
+    );
+    let navmesh = World::init_navmesh(&level, &config);
+    info!(
+        "Baked {} waypoints for level {:?}",
+        navmesh.waypoints.len(),
+        info.id,
+    );
+
+    let report = navmesh.report(
+        level.monster_spawn(),
+        &level.room_data,
+        &level.trigger_cubes,
+    );
+    for (pos, size) in &report.islands {
+        warn!("Island of {size} waypoints not connected to the monster spawn at {pos:?}");
+    }
+    for room in &report.empty_rooms {
+        error!("Room {room:?} has no waypoints reachable from the monster spawn");
+    }
+    for trigger in &report.unreachable_triggers {
+        error!("Trigger cube {trigger:?} is not reachable from the monster spawn");
+    }
+    if report.has_problems() {
+        return Ok(false);
+    }
+
+    let path = assets.join(&info.navmesh);
+    if check {
+        let committed: NavMesh =
+            serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(&path)?))?;
+        if let Some(difference) = difference(&navmesh, &committed) {
+            error!(
+                "{} is out of date, {difference}, run bake-navmesh to update it",
+                path.display(),
+            );
+            return Ok(false);
+        }
+    } else {
+        serde_json::to_writer(std::fs::File::create(&path)?, &navmesh)?;
+        info!("Saved to {}", path.display());
+    }
+    Ok(true)
+}
+
+fn main() -> anyhow::Result<()> {
+    logger::init().unwrap();
+    let check = std::env::args().skip(1).any(|arg| arg == "--check");
+    let assets = static_path().join("assets");
+
+    let infos: Vec<LevelInfo> = serde_json::from_reader(std::io::BufReader::new(
+        std::fs::File::open(assets.join("levels.json"))?,
+    ))?;
+    LevelRegistry::check(&infos)?;
+    let base_config: serde_json::Value = serde_json::from_reader(std::io::BufReader::new(
+        std::fs::File::open(assets.join("config.json"))?,
+    ))?;
+
+    let mut fine = true;
+    for info in &infos {
+        // Keep going so that every broken level is reported at once
+        fine &= bake(&assets, info, &base_config, check)?;
+    }
+    if !fine {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Writes the manifest of every level in static/assets/levels.json from the mesh names
+//! of its obj, see [LevelManifest::import]. Only the levels with the ids given as arguments
+//! are imported if there are any. With `--check` nothing is saved,
+//! instead it fails if a saved manifest is different from what would be imported
+
+use flashdark::*;
+use geng::prelude::*;
+
+/// Returns whether the saved manifest is up to date when checking
+fn import(assets: &std::path::Path, info: &LevelInfo, check: bool) -> anyhow::Result<bool> {
+    let obj_path = assets.join(&info.obj);
+    let obj = Obj::load_headless(&obj_path)?;
+    let manifest = LevelManifest::import(&obj);
+    manifest.validate(&obj)?;
+    info!(
+        "Imported {} triggers, {} rooms, {} interactables and {} item spawns for level {:?}",
+        manifest.triggers.len(),
+        manifest.rooms.len(),
+        manifest.interactables.len(),
+        manifest.items.len(),
+        info.id,
+    );
+
+    let path = Level::manifest_path(&obj_path);
+    if check {
+        let saved: LevelManifest =
+            serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(&path)?))?;
+        if saved != manifest {
+            error!("{} is different from the imported manifest", path.display());
+            return Ok(false);
+        }
+    } else {
+        serde_json::to_writer_pretty(std::fs::File::create(&path)?, &manifest)?;
+        info!("Saved to {}", path.display());
+    }
+    Ok(true)
+}
+
+fn main() -> anyhow::Result<()> {
+    logger::init().unwrap();
+    let (flags, ids): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let check = flags.iter().any(|arg| arg == "--check");
+    let assets = static_path().join("assets");
+
+    let infos: Vec<LevelInfo> = serde_json::from_reader(std::io::BufReader::new(
+        std::fs::File::open(assets.join("levels.json"))?,
+    ))?;
+    LevelRegistry::check(&infos)?;
+    for id in &ids {
+        if !infos.iter().any(|info| info.id == *id) {
+            anyhow::bail!("there is no level {id:?}");
+        }
+    }
+
+    let mut fine = true;
+    for info in &infos {
+        if ids.is_empty() || ids.contains(&info.id) {
+            fine &= import(&assets, info, check)?;
+        }
+    }
+    if !fine {
+        std::process::exit(1);
+    }
+    Ok(())
+}
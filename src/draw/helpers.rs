@@ -51,7 +51,7 @@ impl Game {
                     u_dissolve: dissolve,
                     u_color: color,
                 },
-                geng::camera3d_uniforms(&self.camera, self.framebuffer_size),
+                geng::camera3d_uniforms(&self.world.camera, self.framebuffer_size),
             ),
             ugli::DrawParameters {
                 blend_mode: Some(ugli::BlendMode::default()),
@@ -148,13 +148,13 @@ impl Game {
             framebuffer,
             &self.assets.shaders.skybox,
             ugli::DrawMode::Triangles,
-            &mesh.geometry,
+            &*mesh.geometry.buffer(self.geng.ugli()),
             (
                 ugli::uniforms! {
                     u_model_matrix: matrix,
                     u_texture: texture,
                 },
-                geng::camera3d_uniforms(&self.camera, self.framebuffer_size),
+                geng::camera3d_uniforms(&self.world.camera, self.framebuffer_size),
             ),
             ugli::DrawParameters {
                 write_depth: false,
@@ -177,50 +177,53 @@ impl Game {
             return;
         }
         if mesh.name == "S_Bat" {
-            if !self.bat_go || self.bat_t > 1.0 {
+            if !self.world.bat_go || self.world.bat_t > 1.0 {
                 return;
             }
-            matrix = Mat4::translate(vec3(self.bat_t * 4.0, 0.0, self.bat_t * 0.3));
-            if (self.time * 10.0) as i64 % 2 == 0 {
+            matrix = Mat4::translate(vec3(self.world.bat_t * 4.0, 0.0, self.world.bat_t * 0.3));
+            if (self.world.time * 10.0) as i64 % 2 == 0 {
                 texture_matrix = Mat3::translate(vec2(0.5, 0.0));
             }
         }
         if let Some(i) = mesh.name.strip_prefix("S_PianoKeys") {
             let i = i.chars().next().unwrap().to_digit(10).unwrap();
-            if i != ((self.time * 4.0) as u32) % 4 {
+            if i != ((self.world.time * 4.0) as u32) % 4 {
                 return;
             }
         }
         if let Some(i) = mesh.name.strip_prefix("AF_TV_Static") {
-            if self.key_puzzle_state == KeyPuzzleState::Finish {
+            if self.world.key_puzzle_state == KeyPuzzleState::Finish {
                 return;
             }
             let i = i.chars().next().unwrap().to_digit(10).unwrap();
-            if i != ((self.time * 10.0) as u32) % 2 + 1 {
+            if i != ((self.world.time * 10.0) as u32) % 2 + 1 {
                 return;
             }
         }
-        if self.fuse_spawned && mesh.name.contains("SwingingSwing") {
-            let center = self.level.trigger_cubes["SwingingSwing"].center();
+        if let Some(swing) = self
+            .world
+            .level
+            .trigger_cubes
+            .get("SwingingSwing")
+            .filter(|_| self.world.fuse_spawned && mesh.name.contains("SwingingSwing"))
+        {
+            let center = swing.center();
             matrix = matrix
                 * Mat4::translate(center)
-                * Mat4::rotate_x(self.time.sin() * 0.5)
+                * Mat4::rotate_x(self.world.time.sin() * 0.5)
                 * Mat4::translate(-center);
         }
-        if mesh.name == "S_GrandfatherClock" {
-            let shake_t = (60.0 - self.gf_clock_timer).clamp(0.0, 1.0);
-            if shake_t < 1.0 {
-                let center = find_center(&mesh.geometry).xy().extend(0.0);
-                matrix = Mat4::translate(center)
-                    * Mat4::rotate_y((self.rng.get(10.0) * 0.5 + 0.5) * 0.02)
-                    * Mat4::translate(-center)
-                    * matrix;
-            }
+        if self.world.shaking.contains_key(&mesh.name) {
+            let center = find_center(&mesh.geometry).xy().extend(0.0);
+            matrix = Mat4::translate(center)
+                * Mat4::rotate_y((self.world.rng.get(10.0) * 0.5 + 0.5) * 0.02)
+                * Mat4::translate(-center)
+                * matrix;
         }
         if mesh.name.starts_with("S_Piano") {
             let center = find_center(&mesh.geometry).xy().extend(0.0);
             matrix = Mat4::translate(center)
-                * Mat4::rotate_x((self.rng.get(10.0) * 0.5 + 0.5) * 0.02)
+                * Mat4::rotate_x((self.world.rng.get(10.0) * 0.5 + 0.5) * 0.02)
                 * Mat4::translate(-center)
                 * matrix;
         }
@@ -234,7 +237,7 @@ impl Game {
             let center = sum / mesh.geometry.len() as f32;
             matrix = matrix
                 * Mat4::translate(center)
-                * Mat4::rotate_z(self.camera.rot_h)
+                * Mat4::rotate_z(self.world.camera.rot_h)
                 * Mat4::translate(-center);
         }
         // if mesh.name.starts_with("HB_") {
@@ -246,10 +249,10 @@ impl Game {
         //     let center = sum / mesh.geometry.len() as f32;
         //     matrix = matrix
         //         * Mat4::translate(center)
-        //         * Mat4::rotate_x(if center.y > self.camera.pos.y {
-        //             self.camera.rot_v
+        //         * Mat4::rotate_x(if center.y > self.world.camera.pos.y {
+        //             self.world.camera.rot_v
         //         } else {
-        //             -self.camera.rot_v
+        //             -self.world.camera.rot_v
         //         })
         //         * Mat4::translate(-center);
         // }
@@ -274,34 +277,34 @@ impl Game {
         {
             Rgba::WHITE
         } else {
-            self.ambient_light
+            self.world.ambient_light
         };
 
         ugli::draw(
             framebuffer,
             &self.assets.shaders.obj,
             ugli::DrawMode::Triangles,
-            &mesh.geometry,
+            &*mesh.geometry.buffer(self.geng.ugli()),
             (
                 ugli::uniforms! {
-                    u_flashdark_pos: self.player.flashdark.pos,
-                    u_flashdark_dir: self.player.flashdark.dir,
+                    u_flashdark_pos: self.world.player.flashdark.pos,
+                    u_flashdark_dir: self.world.player.flashdark.dir,
                     u_flashdark_angle: f32::PI / 4.0,
-                    u_flashdark_strength: self.player.flashdark.strength,
-                    u_flashdark_dark: self.player.flashdark.dark,
+                    u_flashdark_strength: self.world.player.flashdark.strength,
+                    u_flashdark_dark: self.world.player.flashdark.dark,
                     u_ambient_light_color: ambient_light,
                     u_model_matrix: matrix,
                     u_color: color,
                     u_noise: &self.noise,
-                    u_camera_rot: self.camera.rot_h,
+                    u_camera_rot: self.world.camera.rot_h,
                     u_texture: texture,
                     u_texture_matrix: texture_matrix,
                     u_dark_texture: mesh.material.dark_texture.as_deref().unwrap_or(texture),
-                    u_darkness: if self.fuse_placed || self.main_menu { 1000.0 } else { -6.0 },
-                    u_time: self.time,
+                    u_darkness: if self.world.fuse_placed || self.world.main_menu { 1000.0 } else { -6.0 },
+                    u_time: self.world.time,
                     u_should_shine: if shine { 1.0 } else { 0.0 },
                 },
-                geng::camera3d_uniforms(&self.camera, self.framebuffer_size),
+                geng::camera3d_uniforms(&self.world.camera, self.framebuffer_size),
                 lights,
             ),
             ugli::DrawParameters {
@@ -339,11 +342,17 @@ impl Game {
             if mesh.name == "PlayerSpawn" {
                 continue;
             }
-            if self.fuse_spawned && mesh.name.contains("SwingingSwing") {
-                let center = self.level.trigger_cubes["SwingingSwing"].center();
+            if let Some(swing) = self
+                .world
+                .level
+                .trigger_cubes
+                .get("SwingingSwing")
+                .filter(|_| self.world.fuse_spawned && mesh.name.contains("SwingingSwing"))
+            {
+                let center = swing.center();
                 matrix = matrix
                     * Mat4::translate(center)
-                    * Mat4::rotate_x(self.time.sin() * 0.5)
+                    * Mat4::rotate_x(self.world.time.sin() * 0.5)
                     * Mat4::translate(-center);
             }
             // TODO
@@ -356,7 +365,7 @@ impl Game {
                 let center = sum / mesh.geometry.len() as f32;
                 matrix = matrix
                     * Mat4::translate(center)
-                    * Mat4::rotate_z(self.camera.rot_h)
+                    * Mat4::rotate_z(self.world.camera.rot_h)
                     * Mat4::translate(-center);
                 // continue; // Ignore billboards for lighting for now
             }
@@ -370,14 +379,14 @@ impl Game {
                 framebuffer,
                 &self.assets.shaders.shadow,
                 ugli::DrawMode::Triangles,
-                &mesh.geometry,
+                &*mesh.geometry.buffer(self.geng.ugli()),
                 (
                     ugli::uniforms! {
                         u_model_matrix: matrix,
                         u_shadow_size: framebuffer.size(),
                         u_texture: texture,
                         u_texture_matrix: Mat3::identity(),
-                        u_camera_rot: self.camera.rot_h,
+                        u_camera_rot: self.world.camera.rot_h,
                     },
                     geng::camera3d_uniforms(light, framebuffer.size().map(|x| x as f32)),
                 ),
@@ -393,14 +402,14 @@ impl Game {
     }
 
     pub fn light_uniforms(&self) -> LightsUniform {
-        let mut lights: Vec<&Light> = self.lights.iter().collect();
-        lights.sort_by_key(|light| r32((light.pos - self.camera.pos).len()));
+        let mut lights: Vec<&Light> = self.world.lights.iter().collect();
+        lights.sort_by_key(|light| r32((light.pos - self.world.camera.pos).len()));
         let shadow_maps = &self.shadow_calc.as_ref().unwrap().shadow_maps;
         LightsUniform {
             u_lights: lights
                 .into_iter()
                 .filter(|light| {
-                    if self.key_puzzle_state == KeyPuzzleState::LightOut && light.id.0 != 0 {
+                    if self.world.key_puzzle_state == KeyPuzzleState::LightOut && light.id.0 != 0 {
                         return false;
                     }
                     true
@@ -414,7 +423,7 @@ impl Game {
                             * if light.flicker_time <= 0.0 {
                                 1.0
                             } else {
-                                self.rng.get(10.0) * 0.5 + 0.5
+                                self.world.rng.get(10.0) * 0.5 + 0.5
                             },
                         shadow_map,
                     }
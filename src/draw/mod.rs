@@ -34,17 +34,22 @@ impl Game {
         );
         self.draw_skybox_mesh(
             framebuffer,
-            &self.level.skybox,
-            Mat4::translate(self.camera.pos),
+            &self.world.level.skybox,
+            Mat4::translate(self.world.camera.pos),
         );
 
         self.update_shadows();
 
-        let look = self.look();
+        let look = self.world.look();
 
-        self.draw_obj(framebuffer, &self.level.obj, Mat4::identity(), Rgba::WHITE);
+        self.draw_obj(
+            framebuffer,
+            &self.world.level.obj,
+            Mat4::identity(),
+            Rgba::WHITE,
+        );
 
-        for (id, interactable) in self.interactables.iter().enumerate() {
+        for (id, interactable) in self.world.interactables.iter().enumerate() {
             if interactable.config.transparent {
                 continue;
             }
@@ -60,7 +65,7 @@ impl Game {
                         Mat3::identity()
                     } else {
                         Mat3::translate(vec2(
-                            0.25 * ((self.time / 0.1).floor() as i32 % 3 + 1) as f32,
+                            0.25 * ((self.world.time / 0.1).floor() as i32 % 3 + 1) as f32,
                             0.0,
                         ))
                     }
@@ -72,12 +77,8 @@ impl Game {
             );
         }
 
-        for (id, item) in self.items.iter().enumerate() {
-            let data = if item.name == "Doll" && item.mesh_index == 1000 {
-                &self.level.items["Doll_Floor"].spawns[0]
-            } else {
-                &self.level.items[&item.name].spawns[item.mesh_index]
-            };
+        for (id, item) in self.world.items.iter().enumerate() {
+            let data = self.world.item_spawn(item);
             let texture = &*data.mesh.material.texture.as_deref().unwrap();
             let dark_texture = data
                 .mesh
@@ -95,7 +96,7 @@ impl Game {
             self.draw_mesh(
                 framebuffer,
                 &data.mesh,
-                self.item_matrix(item),
+                self.world.item_matrix(item),
                 Mat3::identity(),
                 Rgba::WHITE,
                 true,
@@ -104,41 +105,33 @@ impl Game {
 
         self.draw_monster(framebuffer);
 
-        // TV cutscene
-        if self.fuse_placed && self.cutscene_t < 5.0 && self.lock_controls {
-            let t = self.cutscene_t / 5.0;
-            self.draw_billboard(
-                framebuffer,
-                &self.assets.ghost.normal.back,
-                self.monster.pos, // self.level.trigger_cubes["GhostSpawn"].center(),
-                0.5 + t,
-                0.0,
-                1.0 - t * t,
-            );
-            self.draw_texture(
-                framebuffer,
-                &self.assets.shaders.shine,
-                &self.assets.glow_particle,
-                vec3(-8.0, -2.6, 0.65),
-                2.0 + (self.time * 10.0).sin() * 0.2,
-                0.0,
-                0.0,
-                Rgba::new(1.0, 1.0, 1.0, (self.time * 20.0).cos() * 0.25 + 0.75),
-            );
-            // self.draw_billboard(
-            //     framebuffer,
-            //     &self.assets.ghost.crawling,
-            //     self.level.trigger_cubes["GhostSpawn"].center() + vec3(1.0 - t, 0.0, 0.0) * 0.5,
-            //     t,
-            //     0.0,
-            //     0.0,
-            // );
+        for (action, _progress) in self.world.cutscene_cues() {
+            if let CueAction::Glow { spot, size } = action {
+                let pos = match self.world.spot(spot) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                self.draw_texture(
+                    framebuffer,
+                    &self.assets.shaders.shine,
+                    &self.assets.glow_particle,
+                    pos,
+                    size + (self.world.time * 10.0).sin() * 0.2,
+                    0.0,
+                    0.0,
+                    Rgba::new(1.0, 1.0, 1.0, (self.world.time * 20.0).cos() * 0.25 + 0.75),
+                );
+            }
         }
 
         self.draw_debug_navmesh(framebuffer);
 
         self.draw_particles(framebuffer);
 
+        if self.world.player.inventory.inspecting {
+            self.draw_inspected_item(framebuffer);
+        }
+
         // UI ---
 
         let camera2d = geng::Camera2d {
@@ -146,8 +139,8 @@ impl Game {
             rotation: 0.0,
             fov: 10.0,
         };
-        if !self.main_menu && !self.ending {
-            if self.player.flashdark.dark < 1.0 {
+        if !self.world.main_menu && !self.world.ending {
+            if self.world.player.flashdark.dark < 1.0 {
                 self.geng.draw_2d(
                     framebuffer,
                     &camera2d,
@@ -163,150 +156,46 @@ impl Game {
                 &draw_2d::TexturedQuad::colored(
                     AABB::point(vec2(-5.0, -3.5)).extend_uniform(1.5),
                     &self.assets.flashdark_dark,
-                    Rgba::new(1.0, 1.0, 1.0, self.player.flashdark.dark),
+                    Rgba::new(1.0, 1.0, 1.0, self.world.player.flashdark.dark),
                 ),
             );
         }
-        if !self.ending {
-            if let Some(name) = &self.player.item {
-                let data = &self.level.items[name];
-                if name.contains("StudyKey") {
-                    let texture = if self.player.flashdark.on {
-                        data.spawns[0]
-                            .mesh
-                            .material
-                            .dark_texture
-                            .as_deref()
-                            .unwrap()
-                    } else {
-                        data.spawns[0].mesh.material.texture.as_deref().unwrap()
-                    };
-                    let key_config = &self.level.key_configs[name];
-
-                    let transform = Mat3::translate(vec2(5.0, -4.2))
-                        * Mat3::scale_uniform(2.0)
-                        * Mat3::rotate(-f32::PI * 0.7);
-
-                    let texture_aabb = AABB::point(Vec2::ZERO)
-                        .extend_positive(vec2(0.25, 0.25 / 2.0))
-                        .translate(vec2(
-                            0.25 * key_config.top_color as f32,
-                            0.75 + 0.25 * key_config.top_shape as f32 + 0.25 / 2.0,
-                        ));
-                    self.geng.draw_2d(
-                        framebuffer,
-                        &camera2d,
-                        &draw_2d::TexturedPolygon::new(
-                            vec![
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(-1.0, 0.0),
-                                    a_vt: texture_aabb.bottom_left(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(1.0, 0.0),
-                                    a_vt: texture_aabb.bottom_right(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(1.0, 1.0),
-                                    a_vt: texture_aabb.top_right(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(-1.0, 1.0),
-                                    a_vt: texture_aabb.top_left(),
-                                    a_color: Rgba::WHITE,
-                                },
-                            ],
-                            texture,
-                        )
-                        .transform(transform),
-                    );
-                    let texture_aabb = AABB::point(Vec2::ZERO)
-                        .extend_positive(vec2(0.25, 0.25 / 2.0))
-                        .translate(vec2(
-                            0.25 * key_config.bottom_color as f32,
-                            0.75 + 0.25 * key_config.bottom_shape as f32,
-                        ));
-                    self.geng.draw_2d(
-                        framebuffer,
-                        &camera2d,
-                        &draw_2d::TexturedPolygon::new(
-                            vec![
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(-1.0, -1.0),
-                                    a_vt: texture_aabb.bottom_left(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(1.0, -1.0),
-                                    a_vt: texture_aabb.bottom_right(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(1.0, 0.0),
-                                    a_vt: texture_aabb.top_right(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(-1.0, 0.0),
-                                    a_vt: texture_aabb.top_left(),
-                                    a_color: Rgba::WHITE,
-                                },
-                            ],
-                            texture,
-                        )
-                        .transform(transform),
-                    );
-                } else {
-                    let texture_aabb = data.texture_aabb;
-                    self.geng.draw_2d(
+        if !self.world.ending && !self.world.main_menu {
+            let inventory = &self.world.player.inventory;
+            let slots = inventory.slots.len();
+            for (index, item) in inventory.slots.iter().enumerate() {
+                let active = index == inventory.active;
+                let pos = vec2(5.0 - 1.5 * (slots - 1 - index) as f32, -3.0);
+                self.geng.draw_2d(
+                    framebuffer,
+                    &camera2d,
+                    &draw_2d::Quad::new(
+                        AABB::point(pos).extend_uniform(0.7),
+                        Rgba::new(1.0, 1.0, 1.0, if active { 0.2 } else { 0.05 }),
+                    ),
+                );
+                if let Some(name) = item {
+                    self.draw_inventory_item(
                         framebuffer,
                         &camera2d,
-                        &draw_2d::TexturedPolygon::new(
-                            vec![
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(-1.0, -1.0),
-                                    a_vt: texture_aabb.bottom_left(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(1.0, -1.0),
-                                    a_vt: texture_aabb.bottom_right(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(1.0, 1.0),
-                                    a_vt: texture_aabb.top_right(),
-                                    a_color: Rgba::WHITE,
-                                },
-                                draw_2d::TexturedVertex {
-                                    a_pos: vec2(-1.0, 1.0),
-                                    a_vt: texture_aabb.top_left(),
-                                    a_color: Rgba::WHITE,
-                                },
-                            ],
-                            data.spawns[0].mesh.material.texture.as_deref().unwrap(),
-                        )
-                        .translate(vec2(0.0, -0.5))
-                        .scale(vec2(2.0, 2.0 * texture_aabb.height() / texture_aabb.width()) * 0.6)
-                        .translate(vec2(5.0, -3.0)),
+                        name,
+                        Mat3::translate(pos) * Mat3::scale_uniform(if active { 0.8 } else { 0.5 }),
+                        Rgba::new(1.0, 1.0, 1.0, if active { 1.0 } else { 0.5 }),
                     );
                 }
             }
         }
 
-        if !self.main_menu && !self.in_settings {
+        if !self.world.main_menu && !self.in_settings {
             // PawnMan20 is like totally hot
 
             let tutorial_texture = {
                 let mut texture = None;
-                if self.show_crouch_tutorial && self.player_inside_house {
-                    self.show_flashlight_tutorial = false;
+                if self.world.show_crouch_tutorial && self.world.player_inside_house {
+                    self.world.show_flashlight_tutorial = false;
                     texture = Some(&self.assets.tutorial.crouch);
                 }
-                if self.show_flashlight_tutorial && !self.player.flashdark.on {
+                if self.world.show_flashlight_tutorial && !self.world.player.flashdark.on {
                     texture = Some(&self.assets.tutorial.flashlight);
                 }
                 texture
@@ -322,33 +211,21 @@ impl Game {
                         texture,
                     ),
                 );
-            } else if !self.ending {
+            } else if !self.world.ending {
                 let reticle_texture = (|| {
                     match look.target {
                         None => &self.assets.reticle,
                         Some(target) => match target.object {
                             Object::StaticLevel => &self.assets.reticle,
                             Object::Interactable(id) => {
-                                let interactable = &self.interactables[id];
-
-                                // Copypasta mmmm
-                                let mut requirement = interactable.config.require_item.as_deref();
-                                if interactable.data.obj.meshes[0]
-                                    .name
-                                    .starts_with("I_LoosePlank")
-                                {
-                                    requirement = Some("Crowbar");
-                                }
-
-                                if let Some(requirement) = requirement {
-                                    if self.player.item.as_deref() != Some(requirement) {
-                                        return &self.assets.require_item;
-                                        // self.level.items[requirement].spawns[0]
-                                        //     .mesh
-                                        //     .material
-                                        //     .texture
-                                        //     .unwrap();
-                                    }
+                                let interactable = &self.world.interactables[id];
+                                if interactable.requirement_met(&self.world) == Some(false) {
+                                    return &self.assets.require_item;
+                                    // self.world.level.items[requirement].spawns[0]
+                                    //     .mesh
+                                    //     .material
+                                    //     .texture
+                                    //     .unwrap();
                                 }
                                 &self.assets.hand
                             }
@@ -367,34 +244,64 @@ impl Game {
             }
         }
 
-        if !self.main_menu && self.intro_t > 0.0 {
-            let alpha = ((self.intro_t - 2.0) / 3.0).clamp(0.0, 1.0);
-            // self.camera.fov
-            self.geng.draw_2d(
-                framebuffer,
-                &camera2d,
-                &draw_2d::Quad::new(
-                    AABB::point(Vec2::ZERO).extend_uniform(100.0),
-                    Rgba::new(0.0, 0.0, 0.0, alpha),
-                ),
-            );
-            {
-                let texture = &self.assets.tutorial.intro;
-                self.geng.draw_2d(
-                    framebuffer,
-                    &camera2d,
-                    &draw_2d::TexturedQuad::colored(
-                        AABB::point(Vec2::ZERO).extend_symmetric(
-                            vec2(texture.size().x as f32 / texture.size().y as f32, 1.0) * 5.0,
+        for (action, progress) in self.world.cutscene_cues() {
+            let lerp = |[from, to]: [f32; 2]| from + (to - from) * progress;
+            match action {
+                CueAction::Fade { color, alpha } => {
+                    self.geng.draw_2d(
+                        framebuffer,
+                        &camera2d,
+                        &draw_2d::Quad::new(
+                            AABB::point(Vec2::ZERO).extend_uniform(100.0),
+                            Rgba {
+                                a: color.a * lerp(*alpha),
+                                ..*color
+                            },
                         ),
-                        texture,
-                        Rgba::new(1.0, 1.0, 1.0, alpha),
-                    ),
-                );
+                    );
+                }
+                CueAction::Image {
+                    image,
+                    from,
+                    to,
+                    size,
+                    width,
+                    alpha,
+                    flicker,
+                } => {
+                    let texture = match image {
+                        CutsceneImage::Intro => &self.assets.tutorial.intro,
+                        CutsceneImage::Title => &self.assets.ui.title,
+                        CutsceneImage::PentagramFire => &self.assets.pentagram_fire,
+                        CutsceneImage::ToBeContinued => &self.assets.tobecontinued,
+                    };
+                    let pos = match to {
+                        Some(to) => *from + (*to - *from) * progress,
+                        None => *from,
+                    };
+                    let width =
+                        width.unwrap_or(texture.size().x as f32 / texture.size().y as f32 * size);
+                    let mut alpha = lerp(*alpha);
+                    if *flicker {
+                        alpha *= self.world.rng.get(10.0) * 0.2 + 0.8;
+                    }
+                    self.geng.draw_2d(
+                        framebuffer,
+                        &camera2d,
+                        &draw_2d::TexturedQuad::colored(
+                            AABB::point(pos).extend_symmetric(vec2(width, *size)),
+                            texture,
+                            Rgba::new(1.0, 1.0, 1.0, alpha),
+                        ),
+                    );
+                }
+                _ => {}
             }
+        }
 
+        if !self.world.main_menu && self.world.skippable_cutscene_playing() {
             let aabb =
-                AABB::point(vec2(0.0, -4.0)).extend_symmetric(vec2(self.intro_skip_t, 0.1) * 3.0);
+                AABB::point(vec2(0.0, -4.0)).extend_symmetric(vec2(self.world.skip_t, 0.1) * 3.0);
             self.geng.draw_2d(
                 framebuffer,
                 &camera2d,
@@ -403,22 +310,22 @@ impl Game {
                         draw_2d::TexturedVertex {
                             a_pos: aabb.bottom_left(),
                             a_color: Rgba::WHITE,
-                            a_vt: vec2(0.5 - 0.5 * self.intro_skip_t, 0.0),
+                            a_vt: vec2(0.5 - 0.5 * self.world.skip_t, 0.0),
                         },
                         draw_2d::TexturedVertex {
                             a_pos: aabb.bottom_right(),
                             a_color: Rgba::WHITE,
-                            a_vt: vec2(0.5 + 0.5 * self.intro_skip_t, 0.0),
+                            a_vt: vec2(0.5 + 0.5 * self.world.skip_t, 0.0),
                         },
                         draw_2d::TexturedVertex {
                             a_pos: aabb.top_right(),
                             a_color: Rgba::WHITE,
-                            a_vt: vec2(0.5 + 0.5 * self.intro_skip_t, 1.0),
+                            a_vt: vec2(0.5 + 0.5 * self.world.skip_t, 1.0),
                         },
                         draw_2d::TexturedVertex {
                             a_pos: aabb.top_left(),
                             a_color: Rgba::WHITE,
-                            a_vt: vec2(0.5 - 0.5 * self.intro_skip_t, 1.0),
+                            a_vt: vec2(0.5 - 0.5 * self.world.skip_t, 1.0),
                         },
                     ],
                     &self.assets.goo_bar,
@@ -426,62 +333,6 @@ impl Game {
             );
         }
 
-        if self.game_over {
-            let alpha = ((self.game_over_t - 1.5) / 4.0).clamp(0.0, 1.0);
-            self.geng.draw_2d(
-                framebuffer,
-                &camera2d,
-                &draw_2d::Quad::new(
-                    AABB::point(Vec2::ZERO).extend_uniform(100.0),
-                    Rgba::new(0.0, 0.0, 0.0, alpha),
-                ),
-            );
-        }
-
-        if self.ending {
-            if self.ending_t > 3.0 && self.ending_t < 15.0 {
-                self.geng.draw_2d(
-                    framebuffer,
-                    &camera2d,
-                    &draw_2d::TexturedQuad::colored(
-                        AABB::point(vec2(0.0, self.ending_t * 2.0 - 10.0))
-                            .extend_symmetric(vec2(3.0, 20.0)),
-                        &self.assets.pentagram_fire,
-                        Rgba::new(1.0, 1.0, 1.0, self.rng.get(10.0) * 0.2 + 0.8),
-                    ),
-                );
-            }
-            if self.ending_t > 8.0 && self.ending_t < 15.0 {
-                let alpha = ((self.ending_t - 8.0) / 2.0).clamp(0.0, 1.0);
-                self.geng.draw_2d(
-                    framebuffer,
-                    &camera2d,
-                    &draw_2d::Quad::new(
-                        AABB::point(Vec2::ZERO).extend_uniform(100.0),
-                        Rgba::new(0.0, 0.0, 0.0, alpha),
-                    ),
-                );
-            }
-            let mut draw_texture = |pos: Vec2<f32>, size: f32, texture: &ugli::Texture| {
-                self.geng.draw_2d(
-                    framebuffer,
-                    &camera2d,
-                    &draw_2d::TexturedQuad::new(
-                        AABB::point(pos).extend_symmetric(
-                            vec2(texture.size().x as f32 / texture.size().y as f32, 1.0) * size,
-                        ),
-                        texture,
-                    ),
-                );
-            };
-            if self.ending_t > 8.0 {
-                draw_texture(vec2(0.0, 3.0), 1.0, &self.assets.ui.title);
-            }
-            if self.ending_t > 15.0 {
-                draw_texture(vec2(0.0, 1.0), 0.5, &self.assets.tobecontinued);
-            }
-        }
-
         // self.geng.draw_2d(
         //     framebuffer,
         //     &camera2d,
@@ -496,14 +347,14 @@ impl Game {
         //     ),
         // );
 
-        if self.main_menu || self.in_settings {
-            if !self.main_menu {
+        if self.world.main_menu || self.in_settings || self.game_over_menu {
+            if !self.world.main_menu {
                 self.geng.draw_2d(
                     framebuffer,
                     &camera2d,
                     &draw_2d::Quad::new(
                         AABB::point(Vec2::ZERO).extend_uniform(100.0),
-                        Rgba::new(0.0, 0.0, 0.0, 0.5),
+                        Rgba::new(0.0, 0.0, 0.0, if self.game_over_menu { 1.0 } else { 0.5 }),
                     ),
                 );
             }
@@ -626,13 +477,25 @@ impl Game {
                     self.settings.volume =
                         ((mouse_pos.x - (-slider_width)) / (slider_width * 2.0)).clamp(0.0, 1.0);
                 }
-                if !self.main_menu {
+                if !self.world.main_menu {
                     draw_icon(
                         vec2(-5.0, -4.0),
                         0.7,
                         &self.assets.ui.icon_home,
                         Some(UiAction::Home),
                     );
+                    // So that a bug report can be replayed with --seed
+                    self.geng.draw_2d(
+                        framebuffer,
+                        &camera2d,
+                        &draw_2d::Text::unit(
+                            &**self.geng.default_font(),
+                            format!("Seed: {}", self.seed),
+                            Rgba::new(1.0, 1.0, 1.0, 0.5),
+                        )
+                        .scale_uniform(0.2)
+                        .translate(vec2(0.0, -4.0)),
+                    );
                 }
                 draw_icon(
                     vec2(5.0, -4.0),
@@ -649,7 +512,7 @@ impl Game {
                     to_draw.pop();
                     draw_controls = true;
                 }
-            } else if self.main_menu {
+            } else if self.world.main_menu {
                 draw_icon(vec2(0.0, 3.0), 1.5, &self.assets.ui.title, None);
                 draw_icon(
                     vec2(0.0, 0.1),
@@ -670,6 +533,107 @@ impl Game {
                     &self.assets.ui.icon_door,
                     Some(UiAction::Exit),
                 );
+                if self.has_save {
+                    // Play continues the saved run, this one starts over
+                    let pos = vec2(0.0, -1.2);
+                    let text = "New game";
+                    let rect = AABB::point(pos)
+                        .extend_symmetric(vec2(text.len() as f32 * 0.25, 0.5) * 0.6);
+                    let mut color = Rgba::WHITE;
+                    if rect.contains(mouse_pos)
+                        || (self
+                            .geng
+                            .window()
+                            .is_button_pressed(geng::MouseButton::Left)
+                            && self.hover_ui_action == Some(UiAction::NewGame))
+                    {
+                        color = Rgba::BLACK;
+                        hovered = Some(pos);
+                        new_hover_ui_action = Some(UiAction::NewGame);
+                    }
+                    self.geng.draw_2d(
+                        framebuffer,
+                        &camera2d,
+                        &draw_2d::Text::unit(&**self.geng.default_font(), text, color)
+                            .scale_uniform(0.3)
+                            .translate(pos),
+                    );
+                }
+                if self.assets.levels.levels.len() > 1 {
+                    for (index, level) in self.assets.levels.levels.iter().enumerate() {
+                        let action = UiAction::SelectLevel(index);
+                        let pos = vec2(0.0, -2.2 - index as f32 * 0.7);
+                        let text = &level.info.name;
+                        let rect = AABB::point(pos)
+                            .extend_symmetric(vec2(text.len() as f32 * 0.25, 0.5) * 0.6);
+                        let mut color = if level.info.id == self.level_id {
+                            Rgba::WHITE
+                        } else {
+                            Rgba::new(0.6, 0.6, 0.6, 1.0)
+                        };
+                        if rect.contains(mouse_pos)
+                            || (self
+                                .geng
+                                .window()
+                                .is_button_pressed(geng::MouseButton::Left)
+                                && self.hover_ui_action == Some(action))
+                        {
+                            color = Rgba::BLACK;
+                            hovered = Some(pos);
+                            new_hover_ui_action = Some(action);
+                        }
+                        self.geng.draw_2d(
+                            framebuffer,
+                            &camera2d,
+                            &draw_2d::Text::unit(&**self.geng.default_font(), text, color)
+                                .scale_uniform(0.3)
+                                .translate(pos),
+                        );
+                    }
+                }
+            } else if self.game_over_menu {
+                draw_icon(vec2(0.0, 3.0), 1.5, &self.assets.ui.title, None);
+                draw_icon(
+                    vec2(-5.0, -4.0),
+                    0.7,
+                    &self.assets.ui.icon_home,
+                    Some(UiAction::Home),
+                );
+                let options =
+                    std::iter::once((UiAction::Restart(None), "Restart")).chain(
+                        self.world.session.checkpoints.iter().enumerate().map(
+                            |(index, reached)| {
+                                (
+                                    UiAction::Restart(Some(index)),
+                                    self.world.checkpoint_title(&reached.checkpoint),
+                                )
+                            },
+                        ),
+                    );
+                for (index, (action, text)) in options.enumerate() {
+                    let pos = vec2(0.0, 0.5 - index as f32 * 0.7);
+                    let rect = AABB::point(pos)
+                        .extend_symmetric(vec2(text.len() as f32 * 0.25, 0.5) * 0.6);
+                    let mut color = Rgba::WHITE;
+                    if rect.contains(mouse_pos)
+                        || (self
+                            .geng
+                            .window()
+                            .is_button_pressed(geng::MouseButton::Left)
+                            && self.hover_ui_action == Some(action))
+                    {
+                        color = Rgba::BLACK;
+                        hovered = Some(pos);
+                        new_hover_ui_action = Some(action);
+                    }
+                    self.geng.draw_2d(
+                        framebuffer,
+                        &camera2d,
+                        &draw_2d::Text::unit(&**self.geng.default_font(), text, color)
+                            .scale_uniform(0.3)
+                            .translate(pos),
+                    );
+                }
             }
 
             if !self
@@ -752,14 +716,174 @@ impl Game {
         }
     }
 
+    /// The active item held up in front of the camera, drawn over everything else
+    fn draw_inspected_item(&self, framebuffer: &mut ugli::Framebuffer) {
+        let name = match self.world.player.inventory.active_item() {
+            Some(name) => name,
+            None => return,
+        };
+        let camera = &self.world.camera;
+        let matrix = Mat4::translate(camera.pos)
+            * Mat4::rotate_z(camera.rot_h)
+            * Mat4::rotate_x(camera.rot_v)
+            * Mat4::translate(vec3(0.0, 0.35, 0.0))
+            * Mat4::rotate_x(0.3)
+            * Mat4::rotate_z(self.world.time);
+        ugli::clear(framebuffer, None, Some(1.0), None);
+        self.draw_mesh(
+            framebuffer,
+            &self.world.level.items[name].spawns[0].mesh,
+            matrix,
+            Mat3::identity(),
+            Rgba::WHITE,
+            true,
+        );
+    }
+
+    /// Draws an inventory item icon, `slot_transform` maps to the center of the slot
+    fn draw_inventory_item(
+        &self,
+        framebuffer: &mut ugli::Framebuffer,
+        camera2d: &geng::Camera2d,
+        name: &str,
+        slot_transform: Mat3<f32>,
+        color: Rgba<f32>,
+    ) {
+        let data = &self.world.level.items[name];
+        if let Some(key_config) = self.world.level.key_configs.get(name) {
+            let texture = if self.world.player.flashdark.on {
+                data.spawns[0]
+                    .mesh
+                    .material
+                    .dark_texture
+                    .as_deref()
+                    .unwrap()
+            } else {
+                data.spawns[0].mesh.material.texture.as_deref().unwrap()
+            };
+
+            let transform = slot_transform
+                * Mat3::translate(vec2(0.0, -1.2))
+                * Mat3::scale_uniform(2.0)
+                * Mat3::rotate(-f32::PI * 0.7);
+
+            let texture_aabb = AABB::point(Vec2::ZERO)
+                .extend_positive(vec2(0.25, 0.25 / 2.0))
+                .translate(vec2(
+                    0.25 * key_config.top_color as f32,
+                    0.75 + 0.25 * key_config.top_shape as f32 + 0.25 / 2.0,
+                ));
+            self.geng.draw_2d(
+                framebuffer,
+                camera2d,
+                &draw_2d::TexturedPolygon::new(
+                    vec![
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(-1.0, 0.0),
+                            a_vt: texture_aabb.bottom_left(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(1.0, 0.0),
+                            a_vt: texture_aabb.bottom_right(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(1.0, 1.0),
+                            a_vt: texture_aabb.top_right(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(-1.0, 1.0),
+                            a_vt: texture_aabb.top_left(),
+                            a_color: color,
+                        },
+                    ],
+                    texture,
+                )
+                .transform(transform),
+            );
+            let texture_aabb = AABB::point(Vec2::ZERO)
+                .extend_positive(vec2(0.25, 0.25 / 2.0))
+                .translate(vec2(
+                    0.25 * key_config.bottom_color as f32,
+                    0.75 + 0.25 * key_config.bottom_shape as f32,
+                ));
+            self.geng.draw_2d(
+                framebuffer,
+                camera2d,
+                &draw_2d::TexturedPolygon::new(
+                    vec![
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(-1.0, -1.0),
+                            a_vt: texture_aabb.bottom_left(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(1.0, -1.0),
+                            a_vt: texture_aabb.bottom_right(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(1.0, 0.0),
+                            a_vt: texture_aabb.top_right(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(-1.0, 0.0),
+                            a_vt: texture_aabb.top_left(),
+                            a_color: color,
+                        },
+                    ],
+                    texture,
+                )
+                .transform(transform),
+            );
+        } else {
+            let texture_aabb = data.texture_aabb;
+            self.geng.draw_2d(
+                framebuffer,
+                camera2d,
+                &draw_2d::TexturedPolygon::new(
+                    vec![
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(-1.0, -1.0),
+                            a_vt: texture_aabb.bottom_left(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(1.0, -1.0),
+                            a_vt: texture_aabb.bottom_right(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(1.0, 1.0),
+                            a_vt: texture_aabb.top_right(),
+                            a_color: color,
+                        },
+                        draw_2d::TexturedVertex {
+                            a_pos: vec2(-1.0, 1.0),
+                            a_vt: texture_aabb.top_left(),
+                            a_color: color,
+                        },
+                    ],
+                    data.spawns[0].mesh.material.texture.as_deref().unwrap(),
+                )
+                .translate(vec2(0.0, -0.5))
+                .scale(vec2(2.0, 2.0 * texture_aabb.height() / texture_aabb.width()) * 0.6)
+                .transform(slot_transform),
+            );
+        }
+    }
+
     fn update_shadows(&mut self) {
         let mut shadow_calc = self.shadow_calc.take().unwrap();
-        for light in &self.lights {
+        for light in &self.world.lights {
             if light.id.0 != 0 && shadow_calc.shadow_maps.contains_key(&light.id) {
                 continue;
             }
             if light.id.0 == 0
-                && self.player.flashdark.strength == 0.0
+                && self.world.player.flashdark.strength == 0.0
                 && shadow_calc.shadow_maps.contains_key(&light.id)
             {
                 continue;
@@ -788,13 +912,13 @@ impl Game {
             self.obj_shadow(
                 &light,
                 &mut shadow_framebuffer,
-                &self.level.obj,
+                &self.world.level.obj,
                 Mat4::identity(),
                 Some(ugli::CullFace::Back),
             );
 
             // Interactables
-            for interactable in &self.interactables {
+            for interactable in &self.world.interactables {
                 if interactable.config.transparent {
                     continue;
                 }
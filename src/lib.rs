@@ -0,0 +1,696 @@
+use geng::prelude::*;
+
+mod assets;
+mod audio;
+mod camera;
+mod draw;
+mod id;
+mod loading_screen;
+mod logic;
+mod particles;
+mod save;
+#[cfg(test)]
+mod tests;
+mod util;
+
+pub use assets::*;
+pub use audio::*;
+pub use camera::*;
+pub use draw::*;
+pub use id::*;
+pub use loading_screen::*;
+pub use logic::*;
+pub use particles::*;
+pub use save::*;
+pub use util::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum KeyPuzzleState {
+    Begin,
+    Entered,
+    LightOut,
+    Ready,
+    Finish,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct KeyConfiguration {
+    top_color: usize,
+    bottom_color: usize,
+    top_shape: usize,
+    bottom_shape: usize,
+}
+
+impl KeyConfiguration {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            top_color: rng.gen_range(0..4),
+            bottom_color: rng.gen_range(0..4),
+            top_shape: rng.gen_range(0..4),
+            bottom_shape: rng.gen_range(0..4),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum UiAction {
+    None,
+    Settings,
+    Exit,
+    Play,
+    Back,
+    ChangeVolume,
+    ChangeMouseSens,
+    IncDifficulty,
+    DecDifficulty,
+    Home,
+    NewGame,
+    /// Index in the level registry
+    SelectLevel(usize),
+    /// From the game over screen, index in [Session::checkpoints], `None` is the very start
+    Restart(Option<usize>),
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Settings {
+    pub mouse_sens: f32,
+    pub volume: f32,
+    pub difficulty: usize,
+}
+
+const DEFAULT_DIFF: usize = 0;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mouse_sens: 0.5,
+            volume: 0.7,
+            difficulty: 0,
+        }
+    }
+}
+
+pub struct Game {
+    pressed_play: bool,
+    pressed_play_t: f32,
+    has_save: bool,
+    creepy_sing_sfx: Option<geng::SoundEffect>,
+    music_box_sfx: Option<geng::SoundEffect>,
+    draw_calls: Cell<usize>,
+    in_settings: bool,
+    /// Choosing a checkpoint to restart from after dying
+    game_over_menu: bool,
+    settings: Settings,
+    main_menu_next_camera: f32,
+    main_menu_next_camera_index: usize,
+    hover_ui_action: Option<UiAction>,
+    start_drag: Vec2<f32>,
+    ui_mouse_pos: Vec2<f32>,
+    /// Seed of the current run, all gameplay randomness comes from [World::run_rng]
+    seed: u64,
+    /// Seed passed on the command line, reused by every run
+    cli_seed: Option<u64>,
+    /// [LevelInfo::id] of the level being played, or picked in the main menu
+    level_id: String,
+    game_over_sfx: Option<geng::SoundEffect>,
+    chase_music: Option<(f64, geng::SoundEffect)>,
+    /// None if the level has no piano
+    piano_music: Option<geng::SoundEffect>,
+    monster_sounds: MonsterSounds,
+    framebuffer_size: Vec2<f32>,
+    quad_geometry: ugli::VertexBuffer<geng::obj::Vertex>,
+    geng: Geng,
+    assets: Rc<Assets>,
+    sens: f32,
+    white_texture: ugli::Texture,
+    black_texture: ugli::Texture,
+    transparent_black_texture: ugli::Texture,
+    shadow_calc: Option<ShadowCalculation>,
+    tv_noise: Option<geng::SoundEffect>,
+    swing_sfx: Option<geng::SoundEffect>,
+    current_swing_ref_distance: f32,
+    transition: Option<geng::Transition>,
+    music: Option<geng::SoundEffect>,
+    noise: ugli::Texture,
+    intro_sfx: Option<geng::SoundEffect>,
+    particles: Particles,
+    world: World,
+}
+
+impl Drop for Game {
+    fn drop(&mut self) {
+        self.stop_sounds();
+        batbox::preferences::save("flashdark.json", &self.settings);
+    }
+}
+
+impl Game {
+    fn stop_sounds(&mut self) {
+        if let Some(sfx) = &mut self.music {
+            sfx.stop();
+        }
+        if let Some(sfx) = &mut self.tv_noise {
+            sfx.stop();
+        }
+        if let Some((_, sfx)) = &mut self.chase_music {
+            sfx.stop();
+        }
+        if let Some(sfx) = &mut self.swing_sfx {
+            sfx.stop();
+        }
+        if let Some(sfx) = &mut self.intro_sfx {
+            sfx.stop();
+        }
+        // if you're reading this, there's one very important thing you should know:
+        if let Some(sfx) = &mut self.game_over_sfx {
+            sfx.stop();
+        }
+        if let Some(sfx) = &mut self.creepy_sing_sfx {
+            sfx.stop();
+        }
+        if let Some(sfx) = &mut self.music_box_sfx {
+            sfx.stop();
+        }
+        if let Some(sfx) = &mut self.piano_music {
+            sfx.stop();
+        }
+        self.monster_sounds.stop();
+    }
+    /// Falls back to the first level if there is no level with that id
+    pub fn new(
+        geng: &Geng,
+        assets: &Rc<Assets>,
+        level_id: &str,
+        session: Session,
+        main_menu: bool,
+        cli_seed: Option<u64>,
+    ) -> Self {
+        let seed = cli_seed.unwrap_or_else(|| global_rng().gen());
+        Self::new_with_level(
+            geng, assets, level_id, session, main_menu, None, seed, cli_seed,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_with_level(
+        geng: &Geng,
+        assets: &Rc<Assets>,
+        level_id: &str,
+        session: Session,
+        main_menu: bool,
+        saved: Option<&LevelRandomization>,
+        seed: u64,
+        cli_seed: Option<u64>,
+    ) -> Self {
+        let level_assets = assets.levels.get(level_id).unwrap_or_else(|| {
+            warn!("There is no level {level_id:?}, starting the first one");
+            assets.levels.first()
+        });
+        let config = &level_assets.config;
+        let mut run_rng = StdRng::seed_from_u64(seed);
+        let level = LevelData::generate(
+            &level_assets.level,
+            &config.puzzles,
+            &config.collision_layers,
+            saved,
+            &mut run_rng,
+        );
+        if !main_menu {
+            info!("Run seed: {seed}");
+        }
+        if main_menu {
+            geng.window().unlock_cursor();
+        } else {
+            geng.window().lock_cursor();
+        }
+        geng.window().set_cursor_type(geng::CursorType::None);
+
+        let mut navmesh = if config.create_navmesh && !main_menu {
+            World::init_navmesh(&level, config)
+        } else {
+            level_assets.navmesh.clone()
+        };
+        navmesh.remove_unreachable_from(level.monster_spawn());
+
+        let mut res = Self {
+            pressed_play: false,
+            game_over_menu: false,
+            pressed_play_t: 0.0,
+            has_save: main_menu && SaveData::exists(),
+            creepy_sing_sfx: None,
+            music_box_sfx: None,
+            start_drag: Vec2::ZERO,
+            ui_mouse_pos: Vec2::ZERO,
+            settings: batbox::preferences::load("flashdark.json").unwrap_or_default(),
+            main_menu_next_camera: 0.0,
+            in_settings: false,
+            hover_ui_action: None,
+            main_menu_next_camera_index: 0,
+            seed,
+            cli_seed,
+            game_over_sfx: None,
+            piano_music: level
+                .obj
+                .meshes
+                .iter()
+                .find(|mesh| mesh.name == "S_Piano")
+                .map(|piano| {
+                    let mut sfx = assets.music.piano.effect();
+                    sfx.set_position(find_center(&piano.geometry).map(|x| x as f64));
+                    sfx.set_max_distance(4.0);
+                    sfx.play();
+                    sfx
+                }),
+            chase_music: None,
+            music: main_menu.then(|| assets.music.anxiety.play()),
+            draw_calls: Cell::new(0),
+            tv_noise: main_menu.then(|| {
+                let mut tv_noise = assets.sfx.tv_static.effect();
+                let pos = level.monster_spawn();
+                tv_noise.set_position(pos.map(|x| x as f64));
+                // tv_noise.set_ref_distance((pos - self.camera.pos).len() as f64);
+                tv_noise.set_max_distance(2.0);
+                tv_noise.play();
+                tv_noise
+            }),
+            swing_sfx: level
+                .trigger_cubes
+                .get("SwingingSwing")
+                .filter(|_| main_menu)
+                .map(|swing| {
+                    let mut swing_sfx = assets.sfx.swing_loop.effect();
+                    swing_sfx.set_position(swing.center().map(|x| x as f64));
+                    swing_sfx.set_max_distance(2.0);
+                    swing_sfx.play();
+                    swing_sfx
+                }),
+            current_swing_ref_distance: 10000.0,
+            quad_geometry: ugli::VertexBuffer::new_static(
+                geng.ugli(),
+                vec![
+                    geng::obj::Vertex {
+                        a_v: vec3(0.0, 0.0, 0.0),
+                        a_vt: vec2(0.0, 0.0),
+                        a_vn: vec3(0.0, 1.0, 0.0),
+                    },
+                    geng::obj::Vertex {
+                        a_v: vec3(1.0, 0.0, 0.0),
+                        a_vt: vec2(1.0, 0.0),
+                        a_vn: vec3(0.0, 1.0, 0.0),
+                    },
+                    geng::obj::Vertex {
+                        a_v: vec3(1.0, 1.0, 0.0),
+                        a_vt: vec2(1.0, 1.0),
+                        a_vn: vec3(0.0, 1.0, 0.0),
+                    },
+                    geng::obj::Vertex {
+                        a_v: vec3(0.0, 1.0, 0.0),
+                        a_vt: vec2(0.0, 1.0),
+                        a_vn: vec3(0.0, 1.0, 0.0),
+                    },
+                ],
+            ),
+            framebuffer_size: vec2(1.0, 1.0),
+            geng: geng.clone(),
+            assets: assets.clone(),
+            sens: 0.001,
+            white_texture: ugli::Texture::new_with(geng.ugli(), vec2(1, 1), |_| Rgba::WHITE),
+            black_texture: ugli::Texture::new_with(geng.ugli(), vec2(1, 1), |_| Rgba::BLACK),
+            transparent_black_texture: ugli::Texture::new_with(geng.ugli(), vec2(1, 1), |_| {
+                Rgba::TRANSPARENT_BLACK
+            }),
+            shadow_calc: Some(ShadowCalculation::new()),
+            transition: None,
+            noise: {
+                let mut texture = ugli::Texture::new_with(geng.ugli(), vec2(1024, 1024), |_| {
+                    Rgba::new(
+                        global_rng().gen(),
+                        global_rng().gen(),
+                        global_rng().gen(),
+                        global_rng().gen(),
+                    )
+                });
+                texture.set_wrap_mode(ugli::WrapMode::Repeat);
+                texture
+            },
+            intro_sfx: (!session.intro_seen && !main_menu)
+                .then(|| assets.sfx.intro_sequence.play()),
+            particles: Particles::new(geng),
+            monster_sounds: MonsterSounds::new(assets),
+            level_id: level_assets.info.id.clone(),
+            world: World::new(
+                config.clone(),
+                assets.difficulties[DEFAULT_DIFF].clone(),
+                level,
+                navmesh,
+                run_rng,
+                session,
+                main_menu,
+            ),
+        };
+        res.handle_world_events();
+        res
+    }
+
+    fn player_input(&self) -> PlayerInput {
+        let controls = &self.assets.config.controls;
+        let pressed =
+            |buttons: &Vec<Button>| buttons.iter().any(|button| button.is_pressed(&self.geng));
+        let mut movement = Vec2::ZERO;
+        if pressed(&controls.move_forward) {
+            movement.y += 1.0;
+        }
+        if pressed(&controls.move_backward) {
+            movement.y -= 1.0;
+        }
+        if pressed(&controls.move_right) {
+            movement.x += 1.0;
+        }
+        if pressed(&controls.move_left) {
+            movement.x -= 1.0;
+        }
+        let window = self.geng.window();
+        PlayerInput {
+            movement,
+            skip: !window.pressed_keys().is_empty(),
+            sprint: window.is_key_pressed(geng::Key::LShift),
+            fly_up: window.is_key_pressed(geng::Key::Space),
+            fly_down: window.is_key_pressed(geng::Key::LCtrl),
+        }
+    }
+
+    /// Starts a new run from the start
+    pub fn reset(&mut self) {
+        self.restart(Session::default());
+    }
+
+    pub fn restart(&mut self, session: Session) {
+        self.transition = Some(geng::Transition::Switch(Box::new(Game::new(
+            &self.geng,
+            &self.assets,
+            &self.level_id,
+            session,
+            false,
+            self.cli_seed,
+        ))));
+    }
+
+    /// Restarts the run from the reached checkpoint with this index, carrying the session over
+    pub fn restart_from(&mut self, index: Option<usize>) {
+        let checkpoint = index
+            .and_then(|index| self.world.session.checkpoints.get(index))
+            .map(|reached| reached.checkpoint.clone());
+        let session = Session {
+            restart_from: checkpoint,
+            ..self.world.session.clone()
+        };
+        self.restart(session);
+    }
+}
+
+impl geng::State for Game {
+    fn update(&mut self, delta_time: f64) {
+        self.sens = 0.0002 + self.settings.mouse_sens * 0.01;
+        self.geng.audio().set_volume(
+            self.settings.volume as f64 * (1.0 - self.pressed_play_t as f64).clamp(0.0, 1.0),
+        );
+        self.world.difficulty = self.assets.difficulties[self.settings.difficulty].clone();
+        self.world.paused = self.in_settings;
+        let delta_time = delta_time as f32;
+        if self.pressed_play {
+            self.pressed_play_t += delta_time;
+            if self.pressed_play_t > 1.0 {
+                self.continue_or_reset();
+            }
+        }
+        self.update_particles(delta_time);
+        let input = self.player_input();
+        self.world.update(delta_time, &input);
+        self.update_audio(delta_time);
+        if self.world.main_menu {
+            self.main_menu_next_camera -= delta_time;
+            if self.main_menu_next_camera < 0.0 {
+                self.main_menu_next_camera += 6.0;
+                self.world.camera =
+                    self.world.config.main_menu_cameras[self.main_menu_next_camera_index].clone();
+                self.main_menu_next_camera_index += 1;
+                self.main_menu_next_camera_index %= self.world.config.main_menu_cameras.len();
+            }
+        }
+        if self.world.game_over && !self.world.cutscene_playing("game_over") && !self.game_over_menu
+        {
+            if self.world.session.checkpoints.is_empty() {
+                self.restart_from(None);
+            } else {
+                self.game_over_menu = true;
+                self.geng.window().unlock_cursor();
+            }
+        }
+        if self.world.ending && !self.world.cutscene_playing("ending") {
+            SaveData::clear();
+            let next = self
+                .assets
+                .levels
+                .get(&self.level_id)
+                .and_then(|level| level.info.next.clone());
+            self.transition = Some(geng::Transition::Switch(Box::new(match next {
+                Some(next) => Game::new(
+                    &self.geng,
+                    &self.assets,
+                    &next,
+                    Session::default(),
+                    false,
+                    self.cli_seed,
+                ),
+                None => Game::new(
+                    &self.geng,
+                    &self.assets,
+                    &self.level_id,
+                    Session::default(),
+                    true,
+                    self.cli_seed,
+                ),
+            })));
+        }
+    }
+
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        self.draw_calls.set(0);
+        self.draw_impl(framebuffer);
+        // info!("Draw calls: {}", self.draw_calls.get());
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if !self.world.lock_controls && !self.world.main_menu && !self.in_settings {
+            self.handle_event_camera(&event);
+            self.handle_clicks(&event);
+            if self
+                .assets
+                .config
+                .controls
+                .crouch
+                .iter()
+                .any(|button| button.matches(&event))
+            {
+                self.world.player.crouch = !self.world.player.crouch;
+            }
+        }
+
+        for button in &self.assets.config.controls.god_mode {
+            if button.matches(&event) {
+                self.world.toggle_god_mode();
+            }
+        }
+        for button in &self.assets.config.controls.toggle_fullscreen {
+            if button.matches(&event) {
+                self.geng.window().toggle_fullscreen();
+            }
+        }
+        if !self.world.main_menu
+            && !self.world.cutscene_playing("intro")
+            && !self.world.ending
+            && !self.game_over_menu
+        {
+            let assets = self.assets.clone();
+            for button in &assets.config.controls.pause {
+                if button.matches(&event) {
+                    self.in_settings = !self.in_settings;
+                    if self.in_settings {
+                        self.save();
+                    } else {
+                        batbox::preferences::save("flashdark.json", &self.settings);
+                    }
+                    if !self.world.main_menu && !self.in_settings {
+                        self.geng.window().lock_cursor();
+                    } else {
+                        self.geng.window().unlock_cursor();
+                    }
+                }
+            }
+        }
+        // TODO: remove
+        match event {
+            geng::Event::KeyDown { key: geng::Key::R }
+                if self.geng.window().is_key_pressed(geng::Key::LCtrl) =>
+            {
+                self.reset();
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            geng::Event::KeyDown { key: geng::Key::I } => {
+                serde_json::to_writer_pretty(
+                    std::fs::File::create("saved_pos.json").unwrap(),
+                    &self.world.camera,
+                )
+                .unwrap();
+            }
+            geng::Event::MouseDown {
+                button: geng::MouseButton::Left,
+                ..
+            } => {
+                if let Some(action) = self.hover_ui_action {
+                    match action {
+                        UiAction::Settings => self.in_settings = true,
+                        UiAction::Exit => {
+                            self.save();
+                            self.transition = Some(geng::Transition::Pop);
+                        }
+                        UiAction::Play => {
+                            self.pressed_play = true;
+                        }
+                        UiAction::Back => {
+                            self.in_settings = false;
+                            batbox::preferences::save("flashdark.json", &self.settings);
+                            if !self.world.main_menu {
+                                self.geng.window().lock_cursor();
+                            } else {
+                                self.geng.window().unlock_cursor();
+                            }
+                        }
+                        UiAction::ChangeVolume => {
+                            self.start_drag = self.ui_mouse_pos;
+                        }
+                        UiAction::ChangeMouseSens => {
+                            self.start_drag = self.ui_mouse_pos;
+                        }
+                        UiAction::None => {}
+                        UiAction::NewGame => {
+                            SaveData::clear();
+                            self.pressed_play = true;
+                        }
+                        UiAction::Home => {
+                            self.save();
+                            self.transition = Some(geng::Transition::Switch(Box::new(Game::new(
+                                &self.geng,
+                                &self.assets,
+                                &self.level_id,
+                                Session::default(),
+                                true,
+                                self.cli_seed,
+                            ))));
+                        }
+                        UiAction::SelectLevel(index) => {
+                            let id = &self.assets.levels.levels[index].info.id;
+                            if *id != self.level_id {
+                                self.transition =
+                                    Some(geng::Transition::Switch(Box::new(Game::new(
+                                        &self.geng,
+                                        &self.assets,
+                                        id,
+                                        Session::default(),
+                                        true,
+                                        self.cli_seed,
+                                    ))));
+                            }
+                        }
+                        UiAction::Restart(checkpoint) => self.restart_from(checkpoint),
+                        UiAction::IncDifficulty => {
+                            self.settings.difficulty =
+                                (self.settings.difficulty + 1) % self.assets.difficulties.len()
+                        }
+                        UiAction::DecDifficulty => {
+                            self.settings.difficulty =
+                                (self.settings.difficulty + self.assets.difficulties.len() - 1)
+                                    % self.assets.difficulties.len()
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
+
+/// Parses `--seed <seed>` (or `--seed=<seed>`) so that a run can be replayed
+fn seed_from_args() -> Option<u64> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = if arg == "--seed" {
+            args.next()
+        } else if let Some(value) = arg.strip_prefix("--seed=") {
+            Some(value.to_owned())
+        } else {
+            continue;
+        };
+        match value.as_deref().map(str::parse) {
+            Some(Ok(seed)) => return Some(seed),
+            _ => error!("Invalid --seed argument: {value:?}"),
+        }
+    }
+    None
+}
+
+/// Runs the game in a window
+pub fn run() {
+    logger::init().unwrap();
+    let cli_seed = seed_from_args();
+    geng::setup_panic_handler();
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(not(feature = "console"))]
+    std::panic::set_hook(Box::new({
+        fn hook(info: &std::panic::PanicInfo) {
+            let mut f = std::fs::File::create(
+                std::env::current_exe()
+                    .unwrap()
+                    .parent()
+                    .unwrap_or(".".as_ref())
+                    .join("panic.txt"),
+            )
+            .unwrap();
+            let _ = writeln!(f, "{info}");
+        }
+        hook
+    }));
+
+    let geng = Geng::new_with(geng::ContextOptions {
+        title: "FlashDark".to_string(),
+        vsync: false,
+        ..default()
+    });
+    #[cfg(not(target_arch = "wasm32"))]
+    geng.set_icon(&static_path().join("assets").join("icon.png"))
+        .unwrap();
+    geng.audio().set_volume(0.0);
+    geng::run(
+        &geng,
+        geng::LoadingScreen::new(
+            &geng,
+            LoadingScreen::new(&geng),
+            <Assets as geng::LoadAsset>::load(&geng, &static_path().join("assets")),
+            {
+                let geng = geng.clone();
+                move |assets| {
+                    let assets = Rc::new(assets.unwrap());
+                    // The main menu is in the level that Play would continue
+                    let level_id = match SaveData::load() {
+                        Ok(Some(save)) => save.level_id,
+                        _ => assets.levels.first().info.id.clone(),
+                    };
+                    Game::new(&geng, &assets, &level_id, default(), true, cli_seed)
+                }
+            },
+        ),
+    );
+}
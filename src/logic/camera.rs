@@ -34,7 +34,7 @@ impl LookAt {
 }
 
 // Rust Pog
-impl Game {
+impl World {
     pub fn update_camera(&mut self, delta_time: f32) {
         if self.game_over {
             self.camera.pos += (self.player.pos
@@ -53,9 +53,8 @@ impl Game {
         self.camera.rot_v = self.player.rot_v;
     }
     pub fn look(&self) -> LookAt {
-        let mut ray = self
-            .camera
-            .pixel_ray(self.framebuffer_size, self.framebuffer_size / 2.0);
+        // The center of the screen does not depend on the aspect ratio
+        let mut ray = self.camera.pixel_ray(vec2(1.0, 1.0), vec2(0.5, 0.5));
         ray.dir = ray.dir.normalize_or_zero();
 
         let mut target: Option<LookAtTarget> = None;
@@ -63,10 +62,10 @@ impl Game {
             if let Some(t) = t {
                 if t < target.as_ref().map_or(f32::INFINITY, |target| target.t) {
                     let pos = ray.from + ray.dir * t;
-                    if (pos - ray.from).xy().len() > self.assets.config.arms_horizontal_length {
+                    if (pos - ray.from).xy().len() > self.config.arms_horizontal_length {
                         return;
                     }
-                    if (pos - ray.from).z.abs() > self.assets.config.arms_vertical_length {
+                    if (pos - ray.from).z.abs() > self.config.arms_vertical_length {
                         return;
                     }
                     target = Some(LookAtTarget { object, t });
@@ -74,31 +73,20 @@ impl Game {
             }
         };
         update_target(
-            intersect_ray_with_obj(&self.level.obj, Mat4::identity(), 0.0, ray),
+            self.level
+                .bvh
+                .raycast(Mat4::identity(), 0.0, ray, CollisionLayer::Raycast),
             Object::StaticLevel,
         );
         for (id, interactable) in self.interactables.iter().enumerate() {
             update_target(
-                intersect_ray_with_obj(&interactable.data.obj, interactable.matrix(), 0.0, ray),
-                if (interactable.progress != 0.0 && interactable.progress != 1.0)
-                    || interactable.config.disabled
-                    || (interactable.data.obj.meshes[0].name == "D_DoorStorage"
-                        && !self.storage_unlocked)
-                    || (interactable.data.obj.meshes[0]
-                        .name
-                        .ends_with("S_StudyCloset")
-                        && self.key_puzzle_state != KeyPuzzleState::Finish)
-                    || (interactable.data.obj.meshes[0].name == "D_DoorStudy"
-                        && (self.key_puzzle_state != KeyPuzzleState::Begin
-                            && self.key_puzzle_state != KeyPuzzleState::Entered
-                            && self.key_puzzle_state != KeyPuzzleState::Finish))
-                    || (interactable.data.obj.meshes[0].name.starts_with("B_Candle")
-                        && interactable.open)
-                    || (interactable.data.obj.meshes[0]
-                        .name
-                        .starts_with("I_BookshelfLibrary")
-                        && interactable.open)
-                {
+                interactable.data.bvh.raycast(
+                    interactable.matrix(),
+                    0.0,
+                    ray,
+                    CollisionLayer::Raycast,
+                ),
+                if !interactable.can_player_use(self) {
                     Object::StaticLevel
                 } else {
                     Object::Interactable(id)
@@ -108,12 +96,7 @@ impl Game {
         for (id, item) in self.items.iter().enumerate() {
             update_target(
                 intersect_ray_with_mesh(
-                    &if item.name == "Doll" && item.mesh_index == 1000 {
-                        &self.level.items["Doll_Floor"].spawns[0]
-                    } else {
-                        &self.level.items[&item.name].spawns[item.mesh_index]
-                    }
-                    .mesh,
+                    &self.item_spawn(item).mesh,
                     self.item_matrix(item),
                     0.0,
                     ray,
@@ -123,7 +106,9 @@ impl Game {
         }
         LookAt { ray, target }
     }
+}
 
+impl Game {
     pub fn handle_event_camera(&mut self, event: &geng::Event) {
         if !self.geng.window().cursor_locked() {
             return;
@@ -131,9 +116,10 @@ impl Game {
         if let geng::Event::MouseMove { delta, .. } = *event {
             // info!("{delta:?}");
             let delta = delta.map(|x| x as f32);
-            self.player.rot_h -= delta.x * self.sens;
-            self.player.rot_v = (self.player.rot_v + delta.y * self.sens)
-                .clamp(Camera::MIN_ROT_V, Camera::MAX_ROT_V);
+            let player = &mut self.world.player;
+            player.rot_h -= delta.x * self.sens;
+            player.rot_v =
+                (player.rot_v + delta.y * self.sens).clamp(Camera::MIN_ROT_V, Camera::MAX_ROT_V);
         }
     }
 }
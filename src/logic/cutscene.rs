@@ -0,0 +1,239 @@
+use super::*;
+
+/// A cutscene that is playing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CutscenePlayback {
+    pub name: String,
+    /// Seconds since the start
+    pub time: f32,
+}
+
+impl World {
+    /// Starts `start` seconds in, the cues before that do not happen.
+    /// Nothing plays in the main menu
+    pub fn play_cutscene(&mut self, name: &str, start: f32) {
+        if self.main_menu {
+            return;
+        }
+        let cutscene = match self.config.cutscenes.get(name) {
+            Some(cutscene) => cutscene,
+            None => {
+                warn!("There is no cutscene {name:?}");
+                return;
+            }
+        };
+        self.lock_controls |= cutscene.lock_controls;
+        self.cutscenes.retain(|playback| playback.name != name);
+        self.cutscenes.push(CutscenePlayback {
+            name: name.to_owned(),
+            time: start,
+        });
+    }
+
+    /// Starts where skipping it would jump to
+    pub fn play_cutscene_skipped(&mut self, name: &str) {
+        let start = self.config.cutscenes.get(name).map_or(0.0, |cutscene| {
+            cutscene.skip_to.unwrap_or(cutscene.duration)
+        });
+        self.play_cutscene(name, start);
+    }
+
+    pub fn cutscene_playing(&self, name: &str) -> bool {
+        self.cutscenes.iter().any(|playback| playback.name == name)
+    }
+
+    pub fn cutscene_locks_controls(&self) -> bool {
+        self.cutscenes.iter().any(|playback| {
+            self.config
+                .cutscenes
+                .get(&playback.name)
+                .map_or(false, |cutscene| cutscene.lock_controls)
+        })
+    }
+
+    pub fn skippable_cutscene_playing(&self) -> bool {
+        self.cutscenes.iter().any(|playback| {
+            self.config
+                .cutscenes
+                .get(&playback.name)
+                .map_or(false, |cutscene| cutscene.skippable)
+        })
+    }
+
+    /// `None` if the place is not in the level, the cues using it are skipped then
+    pub fn spot(&self, spot: &Spot) -> Option<Vec3<f32>> {
+        let anchor = match &spot.anchor {
+            Anchor::Origin => Vec3::ZERO,
+            Anchor::PlayerSpawn => self.level.spawn_point,
+            Anchor::Eyes => self.player.pos + vec3(0.0, 0.0, self.player.height),
+            Anchor::Monster => self.monster.pos,
+            Anchor::Place(name) => self.place(name)?,
+        };
+        Some(anchor + spot.offset)
+    }
+
+    /// Where the cue moving from `from` to `to` is at
+    fn spot_along(&self, from: &Option<Spot>, to: &Spot, progress: f32) -> Option<Vec3<f32>> {
+        let to = self.spot(to)?;
+        Some(match from {
+            Some(from) => {
+                let from = self.spot(from)?;
+                from + (to - from) * progress
+            }
+            None => to,
+        })
+    }
+
+    /// Continuous cues of the playing cutscenes with how far along they are, in order
+    pub fn cutscene_cues(&self) -> Vec<(&CueAction, f32)> {
+        self.cutscenes
+            .iter()
+            .filter_map(|playback| {
+                self.config
+                    .cutscenes
+                    .get(&playback.name)
+                    .map(|cutscene| (cutscene, playback.time))
+            })
+            .flat_map(|(cutscene, time)| {
+                cutscene
+                    .cues
+                    .iter()
+                    .filter_map(move |cue| Some((&cue.action, cue.progress(time)?)))
+            })
+            .collect()
+    }
+
+    pub fn update_cutscenes(&mut self, delta_time: f32, input: &PlayerInput) {
+        if input.skip && self.skippable_cutscene_playing() {
+            self.skip_t += delta_time;
+        } else {
+            self.skip_t -= delta_time;
+        }
+        self.skip_t = self.skip_t.clamp(0.0, 1.0);
+
+        let config = self.config.clone();
+        let mut playing = Vec::new();
+        let mut finished = Vec::new();
+        for mut playback in mem::take(&mut self.cutscenes) {
+            let cutscene = match config.cutscenes.get(&playback.name) {
+                Some(cutscene) => cutscene,
+                None => continue,
+            };
+            let prev = playback.time;
+            playback.time += delta_time;
+            if cutscene.skippable && self.skip_t >= 1.0 {
+                playback.time = playback
+                    .time
+                    .max(cutscene.skip_to.unwrap_or(cutscene.duration));
+            }
+            let done = playback.time >= cutscene.duration;
+            playback.time = playback.time.min(cutscene.duration);
+            for cue in &cutscene.cues {
+                match cue.until {
+                    None => {
+                        if prev <= cue.at
+                            && (cue.at < playback.time || done && cue.at <= playback.time)
+                        {
+                            self.apply_cue(&cue.action, 1.0, None);
+                        }
+                    }
+                    Some(_) => {
+                        if let Some(progress) = cue.progress(playback.time) {
+                            self.apply_cue(&cue.action, progress, Some(delta_time));
+                        }
+                    }
+                }
+            }
+            if done {
+                finished.push(playback.name);
+            } else {
+                playing.push(playback);
+            }
+        }
+        // Cues might have started other cutscenes
+        let started = mem::take(&mut self.cutscenes);
+        playing.retain(|playback| !started.iter().any(|other| other.name == playback.name));
+        playing.extend(started);
+        self.cutscenes = playing;
+        // Nothing is left to control after dying
+        self.lock_controls = self.game_over || self.cutscene_locks_controls();
+
+        for name in finished {
+            if name == "intro" {
+                self.session.intro_seen = true;
+            }
+            self.fire_cutscene_end_events(&name);
+        }
+    }
+
+    /// `delta_time` is only there for continuous cues
+    fn apply_cue(&mut self, action: &CueAction, progress: f32, delta_time: Option<f32>) {
+        let approach = |smoothing: f32| match delta_time {
+            Some(delta_time) if smoothing > 0.0 => (delta_time / smoothing).min(1.0),
+            _ => 1.0,
+        };
+        match action {
+            CueAction::Effect { effect } => self.apply_effect(effect),
+            CueAction::MovePlayer {
+                from,
+                to,
+                horizontal,
+                smoothing,
+            } => {
+                let mut target = match self.spot_along(from, to, progress) {
+                    Some(target) => target,
+                    None => return,
+                };
+                if *horizontal {
+                    target.z = self.player.pos.z;
+                }
+                self.player.pos += (target - self.player.pos) * approach(*smoothing);
+                self.player.vel = Vec3::ZERO;
+            }
+            CueAction::Look { target, smoothing } => {
+                let eyes = self.player.pos + vec3(0.0, 0.0, self.player.height);
+                let dv = match self.spot(target) {
+                    Some(target) => target - eyes,
+                    None => return,
+                };
+                let target_rot_h = dv.xy().arg() - f32::PI / 2.0;
+                let target_rot_v = vec2(dv.xy().len(), dv.z).arg();
+                let t = approach(*smoothing);
+                self.player.rot_h += normalize_angle(target_rot_h - self.player.rot_h) * t;
+                self.player.rot_v += (target_rot_v - self.player.rot_v) * t;
+            }
+            CueAction::PlayerHeight { height, smoothing } => {
+                self.player.height += (height - self.player.height) * approach(*smoothing);
+            }
+            CueAction::Flashdark { on, dark } => {
+                self.player.flashdark.on = *on;
+                if let Some([from, to]) = dark {
+                    self.player.flashdark.dark = from + (to - from) * progress;
+                }
+                self.monster.next_flashdark_flicker_time = 10.0;
+            }
+            CueAction::MoveMonster {
+                from,
+                to,
+                horizontal,
+                shake,
+            } => {
+                let mut target = match self.spot_along(from, to, progress) {
+                    Some(target) => target,
+                    None => return,
+                };
+                if *horizontal {
+                    target.z = self.monster.pos.z;
+                }
+                self.monster.pos =
+                    target + vec3(self.rng.get(20.0), 0.0, self.rng.get(20.0)) * *shake;
+            }
+            CueAction::MonsterState { state } => self.set_monster_state(*state),
+            CueAction::TurnMonster { dir } => self.monster.dir = *dir,
+            CueAction::MonsterSprite { .. }
+            | CueAction::Fade { .. }
+            | CueAction::Image { .. }
+            | CueAction::Glow { .. } => {}
+        }
+    }
+}
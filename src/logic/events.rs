@@ -0,0 +1,353 @@
+use super::*;
+
+impl World {
+    /// Trigger cube or room, trigger cubes first
+    pub fn area(&self, name: &str) -> Option<&TriggerCube> {
+        self.level
+            .trigger_cubes
+            .get(name)
+            .or_else(|| self.level.room_data.get(name))
+    }
+
+    /// False if the level has no such area
+    pub fn player_in_area(&self, name: &str) -> bool {
+        self.area(name).map_or(false, |area| {
+            area.horizontal_aabb().contains(self.player.pos.xy())
+        })
+    }
+
+    pub fn interactable_id(&self, name: &str) -> Option<Id> {
+        self.interactables
+            .iter()
+            .position(|interactable| interactable.name() == name)
+    }
+
+    /// Center of an area, level mesh or interactable
+    pub fn place(&self, name: &str) -> Option<Vec3<f32>> {
+        if let Some(area) = self.area(name) {
+            return Some(area.center());
+        }
+        if let Some(mesh) = self.level.obj.meshes.iter().find(|mesh| mesh.name == name) {
+            return Some(find_center(&mesh.geometry));
+        }
+        self.interactables
+            .iter()
+            .find(|interactable| interactable.name() == name)
+            .map(|interactable| find_center(&interactable.data.obj.meshes[0].geometry))
+    }
+
+    fn event_ready(&self, event: &EventConfig) -> bool {
+        if event.once && self.fired_events.contains(&event.name) {
+            return false;
+        }
+        if self
+            .event_cooldowns
+            .get(&event.name)
+            .map_or(false, |&time| time > 0.0)
+        {
+            return false;
+        }
+        if let Some(item) = &event.require_item {
+            if !self
+                .player
+                .inventory
+                .slots
+                .iter()
+                .flatten()
+                .any(|i| i == item)
+            {
+                return false;
+            }
+        }
+        if let Some(puzzle) = &event.require_solved {
+            if !self.is_solved(puzzle) {
+                return false;
+            }
+        }
+        if let Some(name) = &event.require_open {
+            match self.interactable_id(name) {
+                Some(id) if self.interactables[id].open => {}
+                _ => return false,
+            }
+        }
+        if let Some(name) = &event.require_interactable {
+            if self.interactable_id(name).is_none() {
+                return false;
+            }
+        }
+        if !event.key_puzzle_states.is_empty()
+            && !event.key_puzzle_states.contains(&self.key_puzzle_state)
+        {
+            return false;
+        }
+        if let Some(checkpoint) = &event.require_seen {
+            if self.session.reached(checkpoint).is_none() {
+                return false;
+            }
+        }
+        if let Some(facing) = &event.facing {
+            let look = self.player.rot_h + f32::PI / 2.0;
+            if normalize_angle(look - facing.direction.to_radians()).abs()
+                > facing.within.to_radians()
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn fire_event(&mut self, event: &EventConfig) {
+        if !self.event_ready(event) {
+            return;
+        }
+        if event.once {
+            self.fired_events.insert(event.name.clone());
+        }
+        if event.cooldown > 0.0 {
+            self.event_cooldowns
+                .insert(event.name.clone(), event.cooldown);
+        }
+        for effect in &event.actions {
+            self.apply_effect(effect);
+        }
+    }
+
+    pub fn update_events(&mut self, delta_time: f32) {
+        for time in self.event_cooldowns.values_mut() {
+            *time -= delta_time;
+        }
+        self.event_cooldowns.retain(|_, time| *time > 0.0);
+        for time in self.shaking.values_mut() {
+            *time -= delta_time;
+        }
+        self.shaking.retain(|_, time| *time > 0.0);
+
+        let config = self.config.clone();
+        // Looking around is a raycast, so it is only done when some event needs it
+        let looking_at = config
+            .events
+            .iter()
+            .any(|event| {
+                matches!(event.when, EventTrigger::LookAt { .. }) && self.event_ready(event)
+            })
+            .then(|| self.look().target)
+            .flatten()
+            .and_then(|target| match target.object {
+                Object::Interactable(id) => Some(self.interactables[id].name().to_owned()),
+                _ => None,
+            });
+        for event in &config.events {
+            let happening = match &event.when {
+                EventTrigger::Inside { area } => self.player_in_area(area),
+                EventTrigger::Outside { area } => !self.player_in_area(area),
+                EventTrigger::LookAt { interactable } => {
+                    looking_at.as_deref() == Some(interactable.as_str())
+                }
+                EventTrigger::CutsceneEnd { .. } | EventTrigger::FlashdarkToggled => false,
+            };
+            if happening {
+                self.fire_event(event);
+            }
+        }
+    }
+
+    pub fn fire_cutscene_end_events(&mut self, name: &str) {
+        let config = self.config.clone();
+        for event in &config.events {
+            if matches!(&event.when, EventTrigger::CutsceneEnd { cutscene } if cutscene == name) {
+                self.fire_event(event);
+            }
+        }
+    }
+
+    pub fn fire_flashdark_events(&mut self) {
+        let config = self.config.clone();
+        for event in &config.events {
+            if matches!(event.when, EventTrigger::FlashdarkToggled) {
+                self.fire_event(event);
+            }
+        }
+    }
+
+    pub fn apply_effect(&mut self, effect: &Effect) {
+        match effect {
+            Effect::PlaySfx { sfx } => self.play_sfx(sfx.clone()),
+            Effect::PlaySfxAt { sfx, at } => match self.place(at) {
+                Some(pos) => self.play_sfx_at(sfx.clone(), pos),
+                None => warn!("No {at:?} to play {sfx:?} at"),
+            },
+            Effect::StartTrack { track } => self.events.push(WorldEvent::StartTrack(*track)),
+            Effect::StopTrack { track } => self.events.push(WorldEvent::StopTrack(*track)),
+            Effect::StopSounds => self.events.push(WorldEvent::StopSounds),
+            Effect::SetAmbientLight { color } => self.ambient_light = *color,
+            Effect::RemoveInteractables { containing } => {
+                while let Some(id) = self
+                    .interactables
+                    .iter()
+                    .position(|i| i.name().contains(containing.as_str()))
+                {
+                    self.remove_interactable(id);
+                }
+            }
+            Effect::RemoveItems { containing } => {
+                self.items
+                    .retain(|item| !item.name.contains(containing.as_str()));
+                self.player.inventory.remove_matching(containing);
+            }
+            Effect::Click { interactable } => match self.interactable_id(interactable) {
+                Some(id) => self.click_interactable(id, false, Vec3::ZERO),
+                None => warn!("No interactable {interactable:?} to click"),
+            },
+            Effect::Open { interactable, from } => match self.interactable_id(interactable) {
+                Some(id) => {
+                    if !self.interactables[id].open {
+                        let from = from
+                            .as_deref()
+                            .and_then(|from| self.place(from))
+                            .unwrap_or(Vec3::ZERO);
+                        self.click_interactable(id, false, from);
+                    }
+                }
+                None => warn!("No interactable {interactable:?} to open"),
+            },
+            Effect::Close { interactable } => match self.interactable_id(interactable) {
+                Some(id) => {
+                    if self.interactables[id].open {
+                        self.click_interactable(id, false, Vec3::ZERO);
+                    }
+                }
+                None => warn!("No interactable {interactable:?} to close"),
+            },
+            Effect::SpawnItem { item } => {
+                let data = match self.level.items.get(item) {
+                    Some(data) if !data.spawns.is_empty() => data,
+                    _ => {
+                        warn!("No spawns for {item:?}");
+                        return;
+                    }
+                };
+                let spawn_index = self.run_rng.gen_range(0..data.spawns.len());
+                let pos = data.spawns[spawn_index].pos;
+                self.items.push(Item {
+                    name: item.clone(),
+                    matrix: Mat4::translate(pos),
+                    mesh_index: spawn_index,
+                    dropped_mesh: None,
+                    parent_interactable: None,
+                });
+            }
+            Effect::MoveMonster { to } => match self.place(to) {
+                Some(pos) => {
+                    self.monster.pos = pos.xy().extend(self.monster.pos.z);
+                    self.monster.next_scan_pos = self.monster.pos;
+                    self.monster.next_pathfind_pos = self.monster.pos;
+                    self.monster.next_target_pos = self.monster.pos;
+                    self.monster.path = None;
+                }
+                None => warn!("No {to:?} to move the monster to"),
+            },
+            Effect::AlertMonster { to } => match self.place(to) {
+                Some(pos) => self.monster_investigate(
+                    self.navmesh.waypoints[self.navmesh.closest_waypoint(pos)],
+                ),
+                None => warn!("No {to:?} to alert the monster to"),
+            },
+            Effect::Shake { mesh, duration } => {
+                self.shaking.insert(mesh.clone(), *duration);
+            }
+            Effect::SetKeyPuzzleState { state } => self.key_puzzle_state = *state,
+            Effect::SetFlag { flag, value } => self.set_flag(*flag, *value),
+            Effect::SetMonsterState { state } => self.set_monster_state(*state),
+            Effect::SolvePuzzle {
+                puzzle,
+                without_effects,
+            } => {
+                if *without_effects {
+                    self.solved_puzzles.insert(puzzle.clone());
+                } else {
+                    self.solve_named_puzzle(puzzle);
+                }
+            }
+            Effect::UseItem { interactable, item } => match self.interactable_id(interactable) {
+                Some(id) => {
+                    self.player.inventory.replace_active(item.clone());
+                    self.click_interactable(id, true, Vec3::ZERO);
+                }
+                None => warn!("No interactable {interactable:?} to use {item:?} on"),
+            },
+            Effect::PlayCutscene { cutscene, start } => self.play_cutscene(cutscene, *start),
+            Effect::SetOpen {
+                containing,
+                open,
+                instantly,
+            } => {
+                for interactable in &mut self.interactables {
+                    if interactable.name().contains(containing.as_str()) {
+                        interactable.open = *open;
+                        if *instantly {
+                            interactable.progress = if *open { 1.0 } else { 0.0 };
+                        }
+                    }
+                }
+            }
+            Effect::PlayMusic { music } => self.events.push(WorldEvent::Music(*music)),
+            Effect::ReachCheckpoint { checkpoint } => self.reach_checkpoint(checkpoint),
+            Effect::FlickerLights { duration } => {
+                for light in &mut self.lights {
+                    light.flicker_time = *duration;
+                }
+            }
+            Effect::RevealKeys { puzzle } => {
+                let (key, keys, hint) = match self
+                    .config
+                    .puzzles
+                    .iter()
+                    .find(|config| config.name == *puzzle)
+                    .map(|config| &config.kind)
+                {
+                    Some(PuzzleKind::KeyMatch {
+                        key, keys, hint, ..
+                    }) => (key.clone(), keys.clone(), hint.clone()),
+                    _ => {
+                        warn!("No key puzzle {puzzle:?} to reveal");
+                        return;
+                    }
+                };
+                let mut names: Vec<&String> = self
+                    .level
+                    .items
+                    .keys()
+                    .filter(|name| **name == key || name.contains(keys.as_str()))
+                    .collect();
+                names.sort();
+                for name in names {
+                    for (mesh_index, spawn) in self.level.items[name].spawns.iter().enumerate() {
+                        self.items.push(Item {
+                            name: name.clone(),
+                            matrix: Mat4::translate(spawn.pos),
+                            mesh_index,
+                            dropped_mesh: None,
+                            parent_interactable: spawn.parent_interactable.clone(),
+                        });
+                    }
+                }
+                if !self.spawn_interactable(&hint) {
+                    warn!("No hint {hint:?} to show");
+                }
+            }
+        }
+    }
+
+    pub fn set_flag(&mut self, flag: WorldFlag, value: bool) {
+        match flag {
+            WorldFlag::FuseSpawned => self.fuse_spawned = value,
+            WorldFlag::FusePlaced => self.fuse_placed = value,
+            WorldFlag::BatReleased => self.bat_go = value,
+            WorldFlag::PlayerInsideHouse => self.player_inside_house = value,
+            WorldFlag::MonsterSpawned => self.monster_spawned = value,
+            WorldFlag::CrouchTutorial => self.show_crouch_tutorial = value,
+            WorldFlag::Flashdark => self.player.flashdark.on = value,
+            WorldFlag::Ending => self.ending = value,
+        }
+    }
+}
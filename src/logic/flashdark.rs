@@ -1,6 +1,6 @@
 use super::*;
 
-impl Game {
+impl World {
     pub fn update_flashdark(&mut self, delta_time: f32) {
         self.player.flashdark.strength = (self.player.flashdark.strength
             + if self.player.flashdark.on { 1.0 } else { -1.0 } * delta_time / 0.3)
@@ -30,18 +30,18 @@ impl Game {
         light.intensity = self.player.flashdark.strength;
 
         // actually flicker LUL
-        if self.player.flashdark.on && self.intro_t < 0.0 {
+        if self.player.flashdark.on && !self.cutscene_playing("intro") {
             self.monster.next_flashdark_flicker_time -= delta_time;
             if self.monster.next_flashdark_flicker_time < 0.5 {
                 self.lights.get_mut(&LightId(0)).unwrap().flicker_time =
                     self.monster.next_flashdark_flicker_time;
             }
             if self.monster.next_flashdark_flicker_time < 0.0 {
-                self.monster.next_flashdark_flicker_time =
-                    self.assets.config.flashdark_flicker_interval;
+                self.monster.next_flashdark_flicker_time = self.config.flashdark_flicker_interval;
                 if self.show_flashlight_tutorial
-                    || global_rng()
-                        .gen_bool(self.assets.config.flashdark_turn_off_probability as f64)
+                    || self
+                        .run_rng
+                        .gen_bool(self.config.flashdark_turn_off_probability as f64)
                 {
                     self.toggle_flashdark(true);
                 }
@@ -53,55 +53,23 @@ impl Game {
         self.player.flashdark.on = !self.player.flashdark.on;
         if self.player.flashdark.on {
             self.show_flashlight_tutorial = false;
-            self.assets.sfx.flash_on.play();
+            self.play_sfx(Sfx::FlashOn);
         } else {
             if broke {
-                self.assets.sfx.broken_flashlight.play().set_volume(0.7);
+                self.events.push(WorldEvent::Sfx {
+                    sfx: Sfx::BrokenFlashlight,
+                    pos: None,
+                    max_distance: None,
+                    volume: Some(0.7),
+                });
             } else {
-                self.assets.sfx.flash_off.play();
+                self.play_sfx(Sfx::FlashOff);
             }
         }
 
-        self.monster.next_flashdark_flicker_time = self.assets.config.flashdark_flicker_interval;
-        self.check_monster_sfx(self.player.pos);
+        self.monster.next_flashdark_flicker_time = self.config.flashdark_flicker_interval;
+        self.make_noise(Noise::Flashlight, self.player.pos);
 
-        // Key puzzle
-        if self.key_puzzle_state == KeyPuzzleState::LightOut {
-            self.key_puzzle_state = KeyPuzzleState::Ready;
-            self.ambient_light = self.assets.config.ambient_light_inside_house;
-
-            self.assets.sfx.light_flicker.play();
-            for light in &mut self.lights {
-                light.flicker_time = 0.5;
-            }
-
-            for (name, data) in &self.level.items {
-                if !name.contains("StudyKey") {
-                    continue;
-                }
-                for (mesh_index, spawn) in data.spawns.iter().enumerate() {
-                    self.items.push(Item {
-                        name: name.clone(),
-                        matrix: Mat4::translate(spawn.pos),
-                        mesh_index,
-                        parent_interactable: spawn.parent_interactable.clone(),
-                    });
-                }
-            }
-            let data = self
-                .level
-                .interactables
-                .iter()
-                .find(|i| i.obj.meshes[0].name == "I_HintKey")
-                .unwrap();
-            self.interactables.push(InteractableState {
-                open_other_way: false,
-                open: false,
-                extra_hacky_library_moving_closet_progress: 0.0,
-                progress: 0.0,
-                data: data.clone(),
-                config: self.assets.config.interactables["I_HintKey"].clone(),
-            });
-        }
+        self.fire_flashdark_events();
     }
 }
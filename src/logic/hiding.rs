@@ -0,0 +1,196 @@
+use super::*;
+
+/// The player is hiding in or under an interactable or a level mesh and can only look around
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Hiding {
+    /// Name of the interactable or the level mesh
+    pub spot: String,
+    /// Where the player goes back to when getting out
+    pub exit_pos: Vec3<f32>,
+    /// Height the player gets up to when getting out
+    pub exit_height: f32,
+    /// Looking out of the hiding spot
+    pub rot_h: f32,
+    /// How far to the sides the player can peek, in radians
+    pub peek_angle: f32,
+    /// The monster saw the player getting in
+    pub seen: bool,
+}
+
+/// Where the player coming from `from` would hide and which way they would look out.
+/// The hiding spot is behind the middle of the mesh, along its thinnest side
+fn hide_pos(geometry: &[Vertex], hide: &HideConfig, from: Vec3<f32>) -> (Vec3<f32>, f32) {
+    let center = find_center(geometry);
+    let (min, max) = geometry.iter().fold(
+        (
+            vec2(f32::INFINITY, f32::INFINITY),
+            vec2(-f32::INFINITY, -f32::INFINITY),
+        ),
+        |(min, max), v| {
+            (
+                vec2(min.x.min(v.a_v.x), min.y.min(v.a_v.y)),
+                vec2(max.x.max(v.a_v.x), max.y.max(v.a_v.y)),
+            )
+        },
+    );
+    let size = max - min;
+    let normal = if size.x < size.y {
+        vec2(1.0, 0.0)
+    } else {
+        vec2(0.0, 1.0)
+    };
+    let normal = if Vec2::dot(normal, from.xy() - center.xy()) > 0.0 {
+        -normal
+    } else {
+        normal
+    };
+    let pos = (center.xy() + normal * hide.depth).extend(from.z);
+    (pos, (-normal).arg() - f32::PI / 2.0)
+}
+
+impl InteractableState {
+    pub fn hide_pos(&self, from: Vec3<f32>) -> Option<(Vec3<f32>, f32)> {
+        let hide = self.config.hide.as_ref()?;
+        Some(hide_pos(&self.data.obj.meshes[0].geometry, hide, from))
+    }
+}
+
+impl World {
+    /// Level mesh from [Config::hiding_meshes] that contains `pos` horizontally
+    pub fn hiding_mesh_at(&self, pos: Vec3<f32>) -> Option<&ObjMesh> {
+        self.level.obj.meshes.iter().find(|mesh| {
+            self.config.hiding_meshes.contains_key(&mesh.name)
+                && AABB::points_bounding_box(mesh.geometry.iter().map(|v| v.a_v.xy()))
+                    .contains(pos.xy())
+        })
+    }
+
+    fn start_hiding(&mut self, spot: String, hide: &HideConfig, pos: Vec3<f32>, rot_h: f32) {
+        let seen = self.monster_sees_player();
+        info!("Hiding in {spot:?}, seen: {seen}");
+        if seen {
+            self.monster_investigate(self.player.pos);
+        }
+        self.player.hiding = Some(Hiding {
+            spot,
+            exit_pos: self.player.pos,
+            exit_height: self.player.height,
+            rot_h,
+            peek_angle: hide.peek_angle * f32::PI / 180.0,
+            seen,
+        });
+        self.player.pos = pos;
+        self.player.vel = Vec3::ZERO;
+        self.player.rot_h = rot_h;
+        if let Some(height) = hide.lying_height {
+            self.player.height = height;
+        }
+        self.player.inventory.inspecting = false;
+        self.update_camera(0.0);
+    }
+
+    pub fn hide(&mut self, id: Id) {
+        if self.player.hiding.is_some() {
+            return;
+        }
+        let interactable = &self.interactables[id];
+        let (pos, rot_h) = match interactable.hide_pos(self.player.pos) {
+            Some(spot) => spot,
+            None => return,
+        };
+        let hide = interactable.config.hide.clone().unwrap();
+        let name = interactable.name().to_owned();
+        if interactable.open {
+            self.click_interactable(id, false, self.player.pos);
+        }
+        self.start_hiding(name, &hide, pos, rot_h);
+    }
+
+    /// Hides in or under the level mesh, like a bed
+    pub fn hide_in_mesh(&mut self, name: &str) {
+        if self.player.hiding.is_some() {
+            return;
+        }
+        let hide = match self.config.hiding_meshes.get(name) {
+            Some(hide) => hide.clone(),
+            None => return,
+        };
+        let mesh = match self.level.obj.meshes.iter().find(|mesh| mesh.name == name) {
+            Some(mesh) => mesh,
+            None => return,
+        };
+        let (mut pos, rot_h) = hide_pos(&mesh.geometry, &hide, self.player.pos);
+        // Lie on the floor under the mesh even if the player is standing on it
+        let bottom = mesh
+            .geometry
+            .iter()
+            .map(|v| v.a_v.z)
+            .fold(f32::INFINITY, f32::min);
+        let from = pos.xy().extend(bottom - 0.01);
+        if let Some(t) = self.level.bvh.raycast(
+            Mat4::identity(),
+            0.0,
+            geng::CameraRay {
+                from,
+                dir: vec3(0.0, 0.0, -1.0),
+            },
+            CollisionLayer::Player,
+        ) {
+            pos.z = from.z - t;
+        }
+        self.start_hiding(name.to_owned(), &hide, pos, rot_h);
+    }
+
+    pub fn stop_hiding(&mut self) {
+        if let Some(hiding) = self.player.hiding.take() {
+            info!("Stopped hiding in {:?}", hiding.spot);
+            self.player.pos = hiding.exit_pos;
+            self.player.height = hiding.exit_height;
+            self.player.vel = Vec3::ZERO;
+            self.update_camera(0.0);
+        }
+    }
+
+    /// Hides behind what the player is looking at, or gets out of the hiding spot
+    pub fn toggle_hiding(&mut self) {
+        if self.player.hiding.is_some() {
+            self.stop_hiding();
+            return;
+        }
+        let look = self.look();
+        match &look.target {
+            Some(LookAtTarget {
+                object: Object::Interactable(id),
+                ..
+            }) => self.hide(*id),
+            Some(LookAtTarget {
+                object: Object::StaticLevel,
+                ..
+            }) => {
+                if let Some(mesh) = self.hiding_mesh_at(look.position().unwrap()) {
+                    let name = mesh.name.clone();
+                    self.hide_in_mesh(&name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn update_hiding(&mut self) {
+        let hiding = match &self.player.hiding {
+            Some(hiding) => hiding,
+            None => return,
+        };
+        self.player.rot_h = hiding.rot_h
+            + normalize_angle(self.player.rot_h - hiding.rot_h)
+                .clamp(-hiding.peek_angle, hiding.peek_angle);
+        if hiding.seen
+            && (self.monster.pos - self.player.pos).xy().len()
+                < self.difficulty.monster_discover_distance
+        {
+            info!("The monster found the player hiding");
+            self.stop_hiding();
+            self.monster_chase(self.player.pos);
+        }
+    }
+}
@@ -9,6 +9,20 @@ pub struct InteractableState {
     pub config: Rc<InteractableConfig>,
 }
 
+/// Why an interactable can not be used right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockedReason {
+    /// Still opening or closing
+    Moving,
+    Disabled,
+    /// Waiting for a puzzle to be solved
+    Puzzle,
+    /// Not usable in the current key puzzle state
+    KeyPuzzle,
+    /// Can not be closed again
+    OneWay,
+}
+
 pub enum InteractableType {
     LDoor { pivot: Vec3<f32> },
     RDoor { pivot: Vec3<f32> },
@@ -17,6 +31,76 @@ pub enum InteractableType {
 }
 
 impl InteractableState {
+    pub fn name(&self) -> &str {
+        &self.data.obj.meshes[0].name
+    }
+
+    pub fn locked_reason(&self, world: &World) -> Option<LockedReason> {
+        if self.progress != 0.0 && self.progress != 1.0 {
+            return Some(LockedReason::Moving);
+        }
+        if self.config.disabled {
+            return Some(LockedReason::Disabled);
+        }
+        if let Some(puzzle) = &self.config.locked_until {
+            if !world.is_solved(puzzle) {
+                return Some(LockedReason::Puzzle);
+            }
+        }
+        if self.config.locked_during.contains(&world.key_puzzle_state) {
+            return Some(LockedReason::KeyPuzzle);
+        }
+        if world.is_one_way(self) {
+            return Some(LockedReason::OneWay);
+        }
+        None
+    }
+
+    pub fn can_player_use(&self, world: &World) -> bool {
+        self.locked_reason(world).is_none()
+    }
+
+    /// The monster does not wait for doors to stop moving
+    pub fn can_monster_open(&self, world: &World) -> bool {
+        if !matches!(self.locked_reason(world), None | Some(LockedReason::Moving)) {
+            return false;
+        }
+        if self
+            .config
+            .monster_locked_during
+            .contains(&world.key_puzzle_state)
+        {
+            return false;
+        }
+        if self.config.monster_waits_for_player_inside && !world.player_inside_house {
+            return false;
+        }
+        true
+    }
+
+    pub fn required_item(&self) -> Option<&str> {
+        self.config.require_item.as_deref()
+    }
+
+    /// Whether the player holds what this needs, `None` if it needs nothing
+    pub fn requirement_met(&self, world: &World) -> Option<bool> {
+        if let Some(fits) = world.key_fits(self.name()) {
+            return Some(fits);
+        }
+        self.required_item()
+            .map(|item| world.player.inventory.active_item() == Some(item))
+    }
+
+    /// Dropped items stay inside open drawers and move with them,
+    /// unless the drawer gets replaced when used
+    pub fn can_hold_items(&self, world: &World) -> bool {
+        matches!(self.data.typ, InteractableType::Drawer { .. })
+            && self.open
+            && self.config.transform_on_use.is_none()
+            && !self.config.dissapear_on_use
+            && world.puzzle_transform(self.name()).is_none()
+    }
+
     pub fn matrix(&self) -> Mat4<f32> {
         let mut matrix = match self.data.typ {
             InteractableType::LDoor { pivot } => {
@@ -47,36 +131,46 @@ impl InteractableState {
     }
 }
 
-impl Game {
-    pub fn initialize_interactables(assets: &Assets, level: &LevelData) -> Vec<InteractableState> {
-        let initial_storage_lock_config: [u8; 4] = loop {
-            let config = std::array::from_fn(|_| global_rng().gen_range(0..4));
-            if config != level.storage_lock_combination {
-                break config;
-            }
-        };
+impl World {
+    pub fn initialize_interactables(
+        config: &Config,
+        level: &LevelData,
+        rng: &mut impl Rng,
+    ) -> Vec<InteractableState> {
+        let initial_combinations: Vec<Vec<u8>> = config
+            .puzzles
+            .iter()
+            .map(|puzzle| match &puzzle.kind {
+                PuzzleKind::CombinationLock {
+                    buttons, symbols, ..
+                } => loop {
+                    let combination: Vec<u8> =
+                        buttons.iter().map(|_| rng.gen_range(0..*symbols)).collect();
+                    if combination != level.combinations[&puzzle.name] {
+                        break combination;
+                    }
+                },
+                _ => Vec::new(),
+            })
+            .collect();
         level
             .interactables
             .iter()
             .filter_map(|data| {
                 let name = &data.obj.meshes[0].name;
-                if name == "I_HintKey" {
-                    return None;
-                }
-                if let Some(numbers) = name.strip_prefix("I_StorageButtonIcon") {
-                    let mut numbers = numbers.chars().map(|c| c.to_digit(10).unwrap());
-                    let index = numbers.next().unwrap() as usize - 1;
-                    let value = numbers.next().unwrap() as u8;
-                    if initial_storage_lock_config[index] != value {
-                        return None;
+                for (puzzle, initial) in config.puzzles.iter().zip(&initial_combinations) {
+                    if let Some((index, value)) = puzzle.kind.combination_button(name) {
+                        if initial[index] != value {
+                            return None;
+                        }
                     }
                 }
-                let config = assets.config.interactables.get(name);
+                let open = config.open_interactables.contains(name);
+                let config = config.interactables.get(name);
                 if config.map_or(false, |config| config.hidden) {
                     return None;
                 }
-                let open = assets.config.open_interactables.contains(name)
-                    || config.map_or(false, |config| config.open);
+                let open = open || config.map_or(false, |config| config.open);
                 Some(InteractableState {
                     open_other_way: config.map_or(false, |config| config.open_inverse),
                     open,
@@ -89,36 +183,10 @@ impl Game {
             .collect()
     }
 
-    pub fn light_out(&mut self) {
-        self.key_puzzle_state = KeyPuzzleState::LightOut;
-        self.assets.sfx.study_lights.play();
-        self.ambient_light = Rgba::BLACK;
-        self.player.flashdark.on = false;
-        self.monster.pos = self.level.room_data["Kitchen"]
-            .center()
-            .xy()
-            .extend(self.monster.pos.z);
-        self.monster.scream_time = 0.0;
-        self.monster.scan_timer_going = true;
-        self.monster.next_pathfind_pos = self.monster.pos;
-        self.monster.next_target_pos = self.monster.pos;
-        unsafe {
-            SEEN_LIGHT_OUT = true;
-        }
-    }
-
     pub fn update_interactables(&mut self, delta_time: f32) {
+        let mut closed = Vec::new();
         for interactable in &mut self.interactables {
-            let inter_time = if interactable.data.obj.meshes[0]
-                .name
-                .starts_with("DL_FenceDoor")
-            {
-                2.0
-            } else if interactable.data.obj.meshes[0].name.starts_with("D") {
-                0.6
-            } else {
-                0.3
-            };
+            let inter_time = interactable.data.open_time;
             let was_zero = interactable.progress == 0.0;
             if interactable.open {
                 interactable.progress += delta_time / inter_time;
@@ -127,51 +195,61 @@ impl Game {
             }
             interactable.progress = interactable.progress.clamp(0.0, 1.0);
             if !was_zero && interactable.progress == 0.0 {
-                if interactable.data.obj.meshes[0].name == "D_DoorMain" {
-                    if let Some(mut music) = self.music.take() {
-                        music.stop();
-                    }
-                    self.music = Some({
-                        let mut music = self.assets.sfx.ambient.effect();
-                        music.set_volume(0.8);
-                        music.play();
-                        music
-                    });
-                }
+                closed.push(interactable.config.clone());
+            }
+        }
+        for config in closed {
+            for effect in &config.on_closed {
+                self.apply_effect(effect);
             }
         }
     }
 
-    pub fn click_interactable(&mut self, id: Id, player: bool, from: Vec3<f32>) {
-        let interactable = &mut self.interactables[id];
-
-        if interactable.data.obj.meshes[0].name.starts_with("B_Candle") {
-            interactable.open = true;
-
-            // TODO: sfx
-            let all_candles = self
-                .interactables
-                .iter()
-                .filter(|i| i.data.obj.meshes[0].name.starts_with("B_Candle"))
-                .count();
-            let lit_candles = self
+    /// Adds a closed interactable of the level, like one hidden at the start.
+    /// False if the level has no such interactable
+    pub fn spawn_interactable(&mut self, name: &str) -> bool {
+        let data = match self
+            .level
+            .interactables
+            .iter()
+            .find(|data| data.obj.meshes[0].name == name)
+        {
+            Some(data) => data.clone(),
+            None => return false,
+        };
+        self.interactables.push(InteractableState {
+            open_other_way: false,
+            open: false,
+            progress: 0.0,
+            extra_hacky_library_moving_closet_progress: 0.0,
+            data,
+            config: self
+                .config
                 .interactables
-                .iter()
-                .filter(|i| i.data.obj.meshes[0].name.starts_with("B_Candle"))
-                .filter(|i| !i.open)
-                .count();
-            self.ambient_light = Rgba::lerp(
-                Rgba::BLACK,
-                self.assets.config.ambient_light_inside_house,
-                lit_candles as f32 / all_candles as f32,
-            );
-            if lit_candles == 0 {
-                self.lock_controls = true;
-                self.stop_sounds();
-                self.ending = true;
-                self.assets.sfx.ending.play();
+                .get(name)
+                .cloned()
+                .unwrap_or_default(),
+        });
+        true
+    }
+
+    /// Items inside are left where they are
+    pub fn remove_interactable(&mut self, id: Id) {
+        let interactable = self.interactables.remove(id);
+        let matrix = interactable.matrix();
+        for item in &mut self.items {
+            if item.parent_interactable.as_deref() == Some(interactable.name()) {
+                item.matrix = matrix * item.matrix;
+                item.parent_interactable = None;
             }
-            self.assets.sfx.blow_candle.play();
+        }
+    }
+
+    pub fn click_interactable(&mut self, id: Id, player: bool, from: Vec3<f32>) {
+        if player && !self.interactables[id].can_player_use(self) {
+            return;
+        }
+        if self.extinguish(id) {
             return;
         }
 
@@ -179,103 +257,67 @@ impl Game {
             return;
         }
 
-        let mut requirement = interactable.config.require_item.as_deref();
-        if interactable.data.obj.meshes[0]
-            .name
-            .starts_with("I_LoosePlank")
-        {
-            requirement = Some("Crowbar");
-        }
-        if let Some(requirement) = requirement {
-            if !player {
+        let name = self.interactables[id].name().to_owned();
+        let is_door = self.interactables[id].data.is_door();
+        if let Some(met) = self.interactables[id].requirement_met(self) {
+            if !player || !met {
                 return;
             }
-            if self.player.item.as_deref() != Some(requirement) {
-                return;
-            }
-        }
-
-        // Fix the fuse
-        if interactable.data.obj.meshes[0].name == "I_FusePlaceholder" {
-            self.fuse_placed = true;
-            let mut tv_noise = self.assets.sfx.tv_static.effect();
-            let pos = self.level.trigger_cubes["GhostSpawn"].center();
-            tv_noise.set_position(pos.map(|x| x as f64));
-            // tv_noise.set_ref_distance((pos - self.camera.pos).len() as f64);
-            tv_noise.set_max_distance(2.0);
-            tv_noise.play();
-            // self.swing_sfx.take().unwrap().stop();
-            self.tv_noise = Some(tv_noise);
-            self.ambient_light = self.assets.config.ambient_light_after_fuse;
         }
+        let puzzle_sfx = self.puzzle_sfx(&name);
+        let puzzle_transform = self.puzzle_transform(&name);
 
-        if interactable.data.obj.meshes[0].name == "B_SingingGirl" {
-            if let Some(mut sfx) = self.creepy_sing_sfx.take() {
-                sfx.stop();
-            }
+        let config = self.interactables[id].config.clone();
+        if let Some(track) = config.stop_track {
+            self.events.push(WorldEvent::StopTrack(track));
         }
-
-        // Key puzzle
-        if interactable.data.obj.meshes[0].name == "D_DoorStudy" {
-            if self.key_puzzle_state == KeyPuzzleState::Entered {
-                self.light_out();
+        if let Some(on_use) = &config.on_use {
+            if on_use.key_puzzle_states.is_empty()
+                || on_use.key_puzzle_states.contains(&self.key_puzzle_state)
+            {
+                for effect in &on_use.actions {
+                    self.apply_effect(effect);
+                }
                 return;
             }
         }
-        let mut clear_keys = false;
-        if interactable.data.obj.meshes[0].name == "I_StudyClosetLock" {
-            self.key_puzzle_state = KeyPuzzleState::Finish;
-            if let Some(mut sfx) = self.tv_noise.take() {
-                sfx.stop();
-            }
-            clear_keys = true;
-        }
 
+        let interactable = &mut self.interactables[id];
         let sfx_position = find_center(&interactable.data.obj.meshes[0].geometry);
 
         if self.time != 0.0 {
             let sfx = if let Some(sfx) = interactable.config.sfx.as_deref() {
-                self.assets.sfx.get_by_name(sfx)
-            } else if interactable.data.obj.meshes[0]
-                .name
-                .starts_with("I_LoosePlank")
-            {
-                &self.assets.sfx.plank_removal
-            } else if interactable.data.obj.meshes[0]
-                .name
-                .starts_with("I_StorageButton")
-            {
-                &self.assets.sfx.symbols_puzzle_button
-            } else if interactable.data.obj.meshes[0].name.starts_with("D") {
-                if interactable.open {
-                    &self.assets.sfx.door_close
-                } else {
-                    &self.assets.sfx.door_open
-                }
-            } else if interactable.data.obj.meshes[0].name.starts_with("I_") {
+                Sfx::Named(sfx.to_owned())
+            } else if let Some(sfx) = puzzle_sfx {
+                sfx
+            } else if interactable.data.is_door() {
                 if interactable.open {
-                    &self.assets.sfx.drawer_close
+                    Sfx::DoorClose
                 } else {
-                    &self.assets.sfx.drawer_open
+                    Sfx::DoorOpen
                 }
+            } else if interactable.open {
+                Sfx::DrawerClose
             } else {
-                &self.assets.sfx.drawer_open // Girl sound?
+                Sfx::DrawerOpen
             };
-            let mut effect = sfx.effect();
-            if let Some(volume) = interactable.config.sfx_volume {
-                effect.set_volume(volume);
-            }
-            if interactable.data.obj.meshes[0].name != "I_FusePlaceholder" {
-                effect.set_position(sfx_position.map(|x| x as f64));
-                effect.set_max_distance(self.assets.config.max_sound_distance);
-            }
-            effect.play();
+            let positioned = interactable.config.positioned_sfx.unwrap_or(true);
+            self.events.push(WorldEvent::Sfx {
+                sfx,
+                pos: positioned.then_some(sfx_position),
+                max_distance: positioned.then_some(self.config.max_sound_distance),
+                volume: interactable.config.sfx_volume,
+            });
         }
 
-        if !interactable.open
-            && interactable.progress == 0.0
-            && !interactable.data.obj.meshes[0].name.contains("Closet")
-        {
+        let fixed_swing = matches!(
+            interactable.data.kind,
+            InteractableKind::Door {
+                fixed_swing: true,
+                ..
+            }
+        );
+        if !interactable.open && interactable.progress == 0.0 && !fixed_swing {
             if let InteractableType::LDoor { pivot } = interactable.data.typ {
                 interactable.open_other_way =
                     Vec2::skew((sfx_position - from).xy(), (pivot - from).xy()) > 0.0;
@@ -287,122 +329,32 @@ impl Game {
         }
         interactable.open = !interactable.open;
         if interactable.config.use_item {
-            self.player.item = None;
-        }
-        if let Some(give) = &interactable.config.give_item {
-            self.player.item = Some(give.clone());
+            self.player.inventory.take_active();
         }
+        let swapped_out = interactable
+            .config
+            .give_item
+            .as_ref()
+            .and_then(|give| self.player.inventory.add(give.clone()));
         // TODO: clone not needed
-        let mut transform = interactable.config.transform_on_use.clone();
-        // Storage lock puzzle
-        let mut check_storage_lock = false;
-        if let Some(numbers) = interactable.data.obj.meshes[0]
-            .name
-            .strip_prefix("I_StorageButtonIcon")
-        {
-            let mut numbers = numbers.chars().map(|c| c.to_digit(10).unwrap());
-            let _index = numbers.next().unwrap() as usize;
-            let value = numbers.next().unwrap() as u8;
-            let value = (value + 1) % 4;
-            let mut new_name = interactable.data.obj.meshes[0].name.clone();
-            new_name.pop();
-            new_name += &value.to_string();
-            transform = Some(new_name);
-            check_storage_lock = true;
-        }
+        let transform = puzzle_transform.or_else(|| interactable.config.transform_on_use.clone());
         if let Some(transform) = transform {
-            self.interactables.remove(id);
-            self.interactables.push(InteractableState {
-                open_other_way: false,
-                open: false,
-                progress: 0.0,
-                extra_hacky_library_moving_closet_progress: 0.0,
-                data: self
-                    .level
-                    .interactables
-                    .iter()
-                    .find(|data| data.obj.meshes[0].name == transform)
-                    .unwrap()
-                    .clone(),
-                config: self
-                    .assets
-                    .config
-                    .interactables
-                    .get(&transform)
-                    .cloned()
-                    .unwrap_or_default(),
-            });
-        } else if interactable.config.dissapear_on_use
-            || interactable.data.obj.meshes[0]
-                .name
-                .starts_with("I_LoosePlank")
-        {
-            self.interactables.remove(id);
-        }
-
-        if player {
-            self.check_monster_sfx(sfx_position);
+            self.remove_interactable(id);
+            if !self.spawn_interactable(&transform) {
+                warn!("No interactable {transform:?} to turn {name:?} into");
+            }
+        } else if interactable.config.dissapear_on_use {
+            self.remove_interactable(id);
         }
 
-        if clear_keys {
-            self.items.retain(|item| !item.name.contains("StudyKey"));
-            self.interactables
-                .retain(|i| !i.data.obj.meshes[0].name.contains("I_HintKey"));
-
-            self.click_interactable(
-                self.interactables
-                    .iter()
-                    .position(|i| i.data.obj.meshes[0].name == "D_DoorStudy")
-                    .unwrap(),
-                false,
-                Vec3::ZERO,
-            );
+        if let Some(item) = swapped_out {
+            self.create_dropped(item);
         }
-
-        // Library puzzle
-        {
-            let current_library_puzzle_progress = self
-                .interactables
-                .iter()
-                .filter(|i| i.data.obj.meshes[0].name.starts_with("I_BookshelfLibrary"))
-                .filter(|i| i.open)
-                .count();
-            let mut p = current_library_puzzle_progress as f32 / 10.0;
-            if current_library_puzzle_progress == 5 {
-                p = 1.0;
-            }
-            for i in self
-                .interactables
-                .iter_mut()
-                .filter(|i| i.data.obj.meshes[0].name.contains("LibraryMovingCloset"))
-            {
-                i.extra_hacky_library_moving_closet_progress = p;
-            }
+        if player {
+            let noise = if is_door { Noise::Door } else { Noise::Drawer };
+            self.make_noise(noise, sfx_position);
         }
 
-        if check_storage_lock {
-            let current_lock_combination = std::array::from_fn(|i| {
-                self.interactables
-                    .iter()
-                    .filter_map(|interactable| {
-                        interactable.data.obj.meshes[0]
-                            .name
-                            .strip_prefix(&format!("I_StorageButtonIcon{}", i + 1))
-                    })
-                    .next()
-                    .unwrap()
-                    .chars()
-                    .next()
-                    .unwrap()
-                    .to_digit(10)
-                    .unwrap() as u8
-            });
-            if current_lock_combination == self.level.storage_lock_combination {
-                self.interactables
-                    .retain(|i| !i.data.obj.meshes[0].name.contains("StorageButton"));
-                self.assets.sfx.symbols_puzzle_solved.play();
-                self.storage_unlocked = true;
-            }
-        }
+        self.update_puzzles(&name);
     }
 }
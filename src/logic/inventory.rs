@@ -0,0 +1,73 @@
+use super::*;
+
+pub struct Inventory {
+    pub slots: Vec<Option<String>>,
+    pub active: usize,
+    /// Showing the active item up close
+    pub inspecting: bool,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size.max(1)],
+            active: 0,
+            inspecting: false,
+        }
+    }
+
+    pub fn active_item(&self) -> Option<&str> {
+        self.slots[self.active].as_deref()
+    }
+
+    pub fn take_active(&mut self) -> Option<String> {
+        self.inspecting = false;
+        self.slots[self.active].take()
+    }
+
+    /// Puts the item into the active slot, returning what was there before
+    pub fn replace_active(&mut self, item: String) -> Option<String> {
+        self.slots[self.active].replace(item)
+    }
+
+    /// Puts the item into the active slot, or selects a free one first if that is taken.
+    /// When every slot is full, the active item is swapped out and returned
+    pub fn add(&mut self, item: String) -> Option<String> {
+        if self.slots[self.active].is_some() {
+            if let Some(free) = self.slots.iter().position(|slot| slot.is_none()) {
+                self.select(free);
+            }
+        }
+        self.replace_active(item)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.slots.len() && index != self.active {
+            self.active = index;
+            self.inspecting = false;
+        }
+    }
+
+    pub fn scroll(&mut self, delta: i32) {
+        let len = self.slots.len() as i32;
+        self.select((self.active as i32 + delta).rem_euclid(len) as usize);
+    }
+
+    pub fn remove_matching(&mut self, containing: &str) {
+        for slot in &mut self.slots {
+            if slot
+                .as_ref()
+                .map_or(false, |item| item.contains(containing))
+            {
+                *slot = None;
+            }
+        }
+        if self.active_item().is_none() {
+            self.inspecting = false;
+        }
+    }
+
+    pub fn toggle_inspect(&mut self) {
+        self.inspecting = !self.inspecting && self.active_item().is_some();
+    }
+}
@@ -4,17 +4,30 @@ pub struct Item {
     pub name: String,
     pub matrix: Mat4<f32>,
     pub mesh_index: usize,
+    /// Drawn with the first spawn of this item instead, see [Config::dropped_meshes]
+    pub dropped_mesh: Option<String>,
     pub parent_interactable: Option<String>,
 }
 
-impl Game {
-    pub fn initialize_items(assets: &Assets, level: &LevelData) -> Vec<Item> {
+impl World {
+    /// Keys only show up once their puzzle reveals them, see [Effect::RevealKeys]
+    pub fn initialize_items(config: &Config, level: &LevelData, rng: &mut impl Rng) -> Vec<Item> {
+        let is_key = |name: &str| {
+            config.puzzles.iter().any(|puzzle| match &puzzle.kind {
+                PuzzleKind::KeyMatch { key, keys, .. } => {
+                    name == key || name.contains(keys.as_str())
+                }
+                _ => false,
+            })
+        };
         let mut items = Vec::new();
-        for (name, data) in level.items.iter().filter(|(name, _data)| {
+        let mut level_items: Vec<(&String, &ItemData)> = level.items.iter().collect();
+        level_items.sort_by_key(|(name, _data)| *name);
+        for (name, data) in level_items.into_iter().filter(|(name, _data)| {
             if name.contains("Fuse") {
                 return false;
             }
-            if name.contains("StudyKey") {
+            if is_key(name) {
                 return false;
             }
             if *name == "Book5" {
@@ -22,13 +35,14 @@ impl Game {
             }
             true
         }) {
-            let index = global_rng().gen_range(0..data.spawns.len());
+            let index = rng.gen_range(0..data.spawns.len());
             let spawn = &data.spawns[index];
             items.push(Item {
                 matrix: Mat4::translate(spawn.pos),
                 name: name.clone(),
                 parent_interactable: spawn.parent_interactable.clone(),
                 mesh_index: index,
+                dropped_mesh: None,
             });
         }
         items
@@ -36,39 +50,169 @@ impl Game {
 
     pub fn item_matrix(&self, item: &Item) -> Mat4<f32> {
         let mut matrix = item.matrix; // Mat4::translate(item.pos);
-        if let Some(parent) = &item.parent_interactable {
-            let parent = self
-                .interactables
-                .iter()
-                .find(|inter| inter.data.obj.meshes[0].name == *parent) // TODO: this is slow
-                .unwrap();
-            matrix = parent.matrix() * matrix;
+        if let Some(parent) = item
+            .parent_interactable
+            .as_deref()
+            .and_then(|parent| self.interactable_id(parent))
+        {
+            matrix = self.interactables[parent].matrix() * matrix;
         }
         matrix
     }
 
+    /// The spawn whose mesh the item is drawn with
+    pub fn item_spawn(&self, item: &Item) -> &ItemSpawnData {
+        match &item.dropped_mesh {
+            Some(mesh) => &self.level.items[mesh].spawns[0],
+            None => &self.level.items[&item.name].spawns[item.mesh_index],
+        }
+    }
+
+    /// Closest hit with the level or a visible interactable
+    pub fn cast_ray(&self, ray: geng::CameraRay) -> Option<(f32, Option<Id>)> {
+        let mut hit = self
+            .level
+            .bvh
+            .raycast(Mat4::identity(), 0.0, ray, CollisionLayer::Raycast)
+            .map(|t| (t, None));
+        for (id, interactable) in self.interactables.iter().enumerate() {
+            if interactable.config.transparent {
+                continue;
+            }
+            if let Some(t) = interactable.data.bvh.raycast(
+                interactable.matrix(),
+                0.0,
+                ray,
+                CollisionLayer::Raycast,
+            ) {
+                if hit.map_or(true, |(closest, _)| t < closest) {
+                    hit = Some((t, Some(id)));
+                }
+            }
+        }
+        hit
+    }
+
+    /// Which way the surface closest to `pos` faces, up if there is none
+    fn surface_normal(&self, pos: Vec3<f32>, id: Option<Id>) -> Vec3<f32> {
+        let v = match id {
+            Some(id) => {
+                let interactable = &self.interactables[id];
+                interactable.data.bvh.vector_from(
+                    interactable.matrix(),
+                    pos,
+                    CollisionLayer::Raycast,
+                )
+            }
+            None => self
+                .level
+                .bvh
+                .vector_from(Mat4::identity(), pos, CollisionLayer::Raycast),
+        };
+        match v {
+            Some(v) if v.len() > EPS => v.normalize(),
+            _ => vec3(0.0, 0.0, 1.0),
+        }
+    }
+
+    /// Where a dropped item comes to rest and the normal of the surface it lies on:
+    /// below what the player is looking at, or on the floor under the player if that is out of reach
+    fn drop_position(&self, radius: f32) -> Option<(Vec3<f32>, Vec3<f32>, Option<Id>)> {
+        let mut ray = self.camera.pixel_ray(vec2(1.0, 1.0), vec2(0.5, 0.5));
+        ray.dir = ray.dir.normalize_or_zero();
+        let from = match self.cast_ray(ray) {
+            Some((t, id)) if t < self.config.arms_horizontal_length => {
+                // Step away from the surface so that the item does not stick into walls
+                let pos = ray.from + ray.dir * (t - 0.05);
+                pos + self.surface_normal(pos, id) * radius
+            }
+            _ => self.camera.pos,
+        };
+        let down = geng::CameraRay {
+            from,
+            dir: vec3(0.0, 0.0, -1.0),
+        };
+        let (t, id) = self.cast_ray(down)?;
+        let pos = from - vec3(0.0, 0.0, t);
+        Some((pos, self.surface_normal(pos + vec3(0.0, 0.0, 0.05), id), id))
+    }
+
     pub fn create_dropped(&mut self, name: String) {
-        let matrix = Mat4::translate(self.player.pos + vec3(0.0, 0.0, -0.24));
-        self.items.push(Item {
-            mesh_index: if name == "Doll" { 1000 } else { 0 },
+        let dropped_mesh = self
+            .config
+            .dropped_meshes
+            .get(&name)
+            .filter(|mesh| {
+                self.level
+                    .items
+                    .get(*mesh)
+                    .map_or(false, |data| !data.spawns.is_empty())
+            })
+            .cloned();
+        let mut item = Item {
             name,
-            matrix,
+            matrix: Mat4::identity(),
+            mesh_index: 0,
+            dropped_mesh,
             parent_interactable: None,
-        })
+        };
+        let mesh = &self.item_spawn(&item).mesh;
+        let bottom = mesh
+            .geometry
+            .iter()
+            .map(|v| r32(v.a_v.z))
+            .min()
+            .map_or(0.0, |z| z.raw());
+        let radius = mesh
+            .geometry
+            .iter()
+            .map(|v| r32(v.a_v.xy().len()))
+            .max()
+            .map_or(0.0, |r| r.raw());
+        (item.matrix, item.parent_interactable) = match self.drop_position(radius) {
+            Some((pos, normal, id)) => {
+                // Tilt towards the slope without turning around
+                let slope = normal.xy().arg() + f32::PI / 2.0;
+                let matrix = Mat4::translate(pos)
+                    * Mat4::rotate_z(slope)
+                    * Mat4::rotate_x(normal.xy().len().atan2(normal.z))
+                    * Mat4::rotate_z(-slope)
+                    * Mat4::translate(vec3(0.0, 0.0, -bottom));
+                match id
+                    .map(|id| &self.interactables[id])
+                    .filter(|parent| parent.can_hold_items(self))
+                {
+                    Some(parent) => (
+                        parent.matrix().inverse() * matrix,
+                        Some(parent.name().to_owned()),
+                    ),
+                    None => (matrix, None),
+                }
+            }
+            None => (
+                Mat4::translate(self.player.pos + vec3(0.0, 0.0, -0.24)),
+                None,
+            ),
+        };
+        self.items.push(item);
     }
 
     pub fn click_item(&mut self, id: Id) {
         let item = self.items.remove(id);
-        self.assets.sfx.generic_pickup.play();
-        if let Some(prev) = self.player.item.replace(item.name) {
+        self.play_sfx(Sfx::GenericPickup);
+        if let Some(prev) = self.player.inventory.add(item.name) {
             self.create_dropped(prev);
         }
     }
 
     pub fn drop_item(&mut self) {
-        if let Some(item) = self.player.item.take() {
-            self.assets.sfx.generic_pickup.play();
+        if self.player.hiding.is_some() {
+            return;
+        }
+        if let Some(item) = self.player.inventory.take_active() {
+            self.play_sfx(Sfx::GenericPickup);
             self.create_dropped(item);
+            self.make_noise(Noise::ItemDrop, self.player.pos);
         }
     }
 }
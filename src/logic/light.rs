@@ -35,8 +35,8 @@ impl geng::AbstractCamera3d for Light {
     }
 }
 
-impl Game {
-    pub fn initialize_lights(assets: &Rc<Assets>) -> Collection<Light> {
+impl World {
+    pub fn initialize_lights(level: &LevelData) -> Collection<Light> {
         let mut id = 0;
         let mut id = || {
             let i = LightId(id);
@@ -53,7 +53,7 @@ impl Game {
             rot_v: 0.0,
             intensity: 1.0,
         });
-        lights.extend(assets.level_obj.meshes.iter().filter_map(|mesh| {
+        lights.extend(level.obj.meshes.iter().filter_map(|mesh| {
             if mesh.name == "B_CeilingLight.008_B_CeilingLight.011" {
                 return None;
             }
@@ -87,10 +87,7 @@ impl Game {
                 .choose(&mut global_rng())
                 .unwrap();
             light.flicker_time = 0.5;
-            // let mut sfx = self.assets.sfx.light_flicker.effect();
-            // sfx.set_position(light.pos.map(|x| x as f64));
-            // sfx.set_max_distance(self.assets.config.max_sound_distance);
-            // sfx.play();
+            // self.play_sfx_at(Sfx::LightFlicker, light.pos);
         }
         for light in &mut self.lights {
             light.flicker_time -= delta_time;
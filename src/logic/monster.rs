@@ -1,64 +1,76 @@
 use super::*;
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
-pub enum TargetType {
-    Player,
-    Noise,
-    Flashdark,
-    Rng,
+/// Rooms [World::monster_wander] tries before giving up on finding one to go to
+const MONSTER_WANDER_TRIES: usize = 10;
+
+/// What the monster is currently up to, see [World::update_monster]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonsterState {
+    /// Roaming around a room, moving to another one after a while
+    Wander,
+    /// Going to where a noise came from
+    Investigate,
+    /// Roaming around the place of a noise or where the player was lost
+    Search,
+    /// Going after the player it sees
+    Chase,
+    /// Lost sight of the player, going to where they were last seen
+    LoseTrack,
+    /// Standing in front of a door while it opens
+    OpenDoor,
+    /// Moved around by cutscenes
+    Scripted,
 }
 
 pub struct Monster {
+    pub state: MonsterState,
+    pub previous_state: MonsterState,
+    /// Time since the last state transition
+    pub state_time: f32,
+    /// Time spent in [Monster::previous_state] before the door got in the way, to carry on with it
+    pub previous_state_time: f32,
     pub stand_still_time: f32,
     pub pos: Vec3<f32>,
     pub dir: Vec3<f32>,
-    pub scan_timer_going: bool,
-    pub target_type: TargetType,
     pub next_pathfind_pos: Vec3<f32>,
-    pub scan_timer: f32,
+    /// Way to [Monster::next_target_pos], planned again when that changes
+    pub path: Option<NavPath>,
+    /// Doors on the [Monster::path] and their gates when it was planned
+    pub path_doors: Vec<(String, DoorGate)>,
+    /// Center of the area roamed while wandering or searching
     pub next_scan_pos: Vec3<f32>,
+    pub last_seen_pos: Vec3<f32>,
+    pub last_seen_vel: Vec3<f32>,
+    /// Room where the current search started
+    pub search_room: Option<String>,
+    /// How many rooms away from [Monster::search_room] the search got
+    pub search_round: usize,
+    /// Spots left to check this round
+    pub search_spots: Vec<Vec3<f32>>,
     pub next_target_pos: Vec3<f32>,
     pub speed: f32,
-    pub loop_sound: geng::SoundEffect,
-    pub chase_loop_sound: geng::SoundEffect,
-    pub scream_time: f32,
     pub next_flashdark_flicker_time: f32,
-    pub pause_time: f32,
     pub detect_timer: f32,
     pub chase_fade: f32,
     pub next_alarm_sfx: f32,
 }
 
-impl Drop for Monster {
+/// Sounds following the monster around, kept outside of [Monster] so that it stays headless
+pub struct MonsterSounds {
+    pub loop_sound: geng::SoundEffect,
+    pub chase_loop_sound: geng::SoundEffect,
+}
+
+impl Drop for MonsterSounds {
     fn drop(&mut self) {
-        self.stop_sounds();
+        self.stop();
     }
 }
 
-impl Monster {
-    pub fn stop_sounds(&mut self) {
-        self.loop_sound.stop();
-        self.chase_loop_sound.stop();
-    }
-    pub fn new(assets: &Assets, level: &LevelData) -> Self {
-        let pos = level.trigger_cubes["GhostSpawn"].center();
+impl MonsterSounds {
+    pub fn new(assets: &Assets) -> Self {
         Self {
-            next_alarm_sfx: 0.0,
-            chase_fade: 0.0,
-            detect_timer: 0.0,
-            scan_timer: 0.0,
-            scan_timer_going: true,
-            next_scan_pos: pos,
-            stand_still_time: 0.0,
-            next_flashdark_flicker_time: assets.config.flashdark_flicker_interval,
-            scream_time: 0.0,
-            pos,
-            target_type: TargetType::Rng,
-            dir: vec3(0.0, -1.0, 0.0),
-            next_pathfind_pos: pos,
-            next_target_pos: pos,
-            speed: 1.0,
-            pause_time: 0.0,
             loop_sound: {
                 let mut effect = assets.sfx.ghost_loop.effect();
                 effect.set_volume(0.0);
@@ -75,19 +87,65 @@ impl Monster {
             },
         }
     }
+    pub fn stop(&mut self) {
+        self.loop_sound.stop();
+        self.chase_loop_sound.stop();
+    }
 }
 
-impl Game {
+impl Monster {
+    pub fn new(config: &Config, difficulty: &Difficulty, level: &LevelData) -> Self {
+        let pos = level.monster_spawn();
+        Self {
+            state: MonsterState::Wander,
+            previous_state: MonsterState::Wander,
+            // Start by picking a room right away
+            state_time: difficulty.monster_wander_time,
+            previous_state_time: 0.0,
+            next_alarm_sfx: 0.0,
+            chase_fade: 0.0,
+            detect_timer: 0.0,
+            next_scan_pos: pos,
+            last_seen_pos: pos,
+            last_seen_vel: Vec3::ZERO,
+            search_room: None,
+            search_round: 0,
+            search_spots: Vec::new(),
+            stand_still_time: 0.0,
+            next_flashdark_flicker_time: config.flashdark_flicker_interval,
+            pos,
+            dir: vec3(0.0, -1.0, 0.0),
+            next_pathfind_pos: pos,
+            path: None,
+            path_doors: Vec::new(),
+            next_target_pos: pos,
+            speed: difficulty.monster_walk_speed,
+        }
+    }
+
+    pub fn is_chasing(&self) -> bool {
+        match self.state {
+            MonsterState::Chase | MonsterState::LoseTrack => true,
+            MonsterState::OpenDoor => matches!(
+                self.previous_state,
+                MonsterState::Chase | MonsterState::LoseTrack
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl World {
     pub fn can_see(&self, pos: Vec3<f32>, target: Vec3<f32>) -> bool {
-        let check = |obj: &Obj, matrix: Mat4<f32>| -> bool {
-            if let Some(ray_t) = intersect_ray_with_obj(
-                obj,
+        let check = |bvh: &ObjBvh, matrix: Mat4<f32>| -> bool {
+            if let Some(ray_t) = bvh.raycast(
                 matrix,
                 self.difficulty.peek_distance,
                 geng::CameraRay {
                     from: pos,
                     dir: (target - pos).normalize_or_zero(),
                 },
+                CollisionLayer::Sight,
             ) {
                 if ray_t < (target - pos).len() {
                     return false;
@@ -95,18 +153,18 @@ impl Game {
             }
             true
         };
-        if !check(&self.level.obj, Mat4::identity()) {
+        if !check(&self.level.bvh, Mat4::identity()) {
             return false;
         }
         for interactable in &self.interactables {
-            if !check(&interactable.data.obj, interactable.matrix()) {
+            if !check(&interactable.data.bvh, interactable.matrix()) {
                 return false;
             }
         }
         true
     }
     pub fn monster_sees_player(&self) -> bool {
-        if self.player.god_mode {
+        if self.player.god_mode || self.player.hiding.is_some() {
             return false;
         }
         let distance = (self.monster.pos - self.player.pos).xy().len();
@@ -132,175 +190,240 @@ impl Game {
             self.player.pos + vec3(0.0, 0.0, self.player.height),
         )
     }
-    pub fn monster_walk_to(&mut self, pos: Vec3<f32>, target_type: TargetType) {
-        info!("Going to {:?}", pos);
-        if target_type != TargetType::Rng {
-            self.monster.scan_timer = self.difficulty.monster_scan_time;
-            self.monster.scan_timer_going = false;
-            self.monster.next_scan_pos = pos;
+    pub fn set_monster_state(&mut self, state: MonsterState) {
+        if self.monster.state == state {
+            return;
         }
-        // if target_type != self.monster.target_type {
-        if (pos - self.monster.next_target_pos).len() > 0.5
-            || target_type != self.monster.target_type
-        {
-            match target_type {
-                TargetType::Player => {
-                    if self.monster.speed == 1.0 {
-                        self.monster.scream_time = 1.0;
-                        let mut effect = self.assets.sfx.ghost_scream.effect();
-                        effect.set_position(self.monster.pos.map(|x| x as f64));
-                        // effect.set_max_distance(self.assets.config.max_sound_distance * 5.0);
-                        effect.play();
-                    }
-                }
-                TargetType::Noise | TargetType::Flashdark => {
-                    if self.monster.speed == 1.0 && self.monster.next_alarm_sfx < 0.0 {
-                        self.monster.next_alarm_sfx = 2.0;
-                        let mut effect = self
-                            .assets
-                            .sfx
-                            .ghost_alarmed
-                            .choose(&mut global_rng())
-                            .unwrap()
-                            .effect();
-                        effect.set_position(self.monster.pos.map(|x| x as f64));
-                        // effect.set_max_distance(self.assets.config.max_sound_distance);
-                        effect.play();
-                    }
-                }
-                TargetType::Rng => {}
-            };
-            self.monster.target_type = target_type;
+        info!("Monster {:?} -> {:?}", self.monster.state, state);
+        self.monster.previous_state = self.monster.state;
+        self.monster.previous_state_time = self.monster.state_time;
+        self.monster.state = state;
+        self.monster.state_time = 0.0;
+        match state {
+            MonsterState::Wander | MonsterState::Investigate | MonsterState::Search => {
+                self.monster.speed = self.difficulty.monster_walk_speed;
+            }
+            _ => {}
         }
+    }
+
+    /// Whether the monster is still standing still screaming after noticing the player
+    pub fn monster_screaming(&self) -> bool {
+        self.monster.state == MonsterState::Chase
+            && !matches!(
+                self.monster.previous_state,
+                MonsterState::LoseTrack | MonsterState::OpenDoor
+            )
+            && self.monster.state_time < self.difficulty.monster_scream_time
+    }
+
+    pub fn monster_go_to(&mut self, pos: Vec3<f32>) {
+        info!("Going to {:?}", pos);
         self.monster.next_target_pos = pos; // TODO ??? self.navmesh.waypoints[self.navmesh.closest_waypoint(pos)];
         self.monster.next_pathfind_pos = self.monster.pos;
-        if let TargetType::Player = target_type {
-            let ((s, s_speed), (t, t_speed)) = self.difficulty.monster_chase_speed;
-            let k = (((pos - self.monster.pos).len() - s) / (t - s)).clamp(0.0, 1.0);
-            self.monster.speed = s_speed * (1.0 - k) + t_speed * k;
+        self.monster.path = None;
+    }
+
+    /// Plans the way to [Monster::next_target_pos] around locked doors,
+    /// getting as close as possible if it can not be reached
+    fn monster_plan_path(&mut self) {
+        let path = match self
+            .navmesh
+            .find_partial_path(self.monster.pos, self.monster.next_target_pos)
+        {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Could not pathfind: {e}");
+                NavPath::default()
+            }
+        };
+        self.monster.path_doors = self
+            .navmesh
+            .path_doors(&path)
+            .into_iter()
+            .map(|door| {
+                let gate = self.navmesh.gate(&door);
+                (door, gate)
+            })
+            .collect();
+        self.monster.path = Some(path);
+    }
+
+    /// Whether a door on the way was opened, closed or locked since the path was planned
+    fn monster_path_outdated(&self) -> bool {
+        self.monster
+            .path_doors
+            .iter()
+            .any(|(door, gate)| self.navmesh.gate(door) != *gate)
+    }
+
+    /// Makes the monster check out a noise, unless it is busy chasing the player
+    pub fn monster_investigate(&mut self, pos: Vec3<f32>) {
+        match self.monster.state {
+            MonsterState::Chase | MonsterState::Scripted => return,
+            MonsterState::LoseTrack => {}
+            // Check it out once the door is open
+            MonsterState::OpenDoor => match self.monster.previous_state {
+                MonsterState::Chase => return,
+                MonsterState::LoseTrack => {}
+                _ => {
+                    self.monster.previous_state = MonsterState::Investigate;
+                    self.monster.previous_state_time = 0.0;
+                }
+            },
+            state => {
+                if (state != MonsterState::Investigate
+                    || (pos - self.monster.next_target_pos).len() > 0.5)
+                    && self.monster.next_alarm_sfx < 0.0
+                {
+                    self.monster.next_alarm_sfx = 2.0;
+                    self.events.push(WorldEvent::Sfx {
+                        sfx: Sfx::GhostAlarmed,
+                        pos: Some(self.monster.pos),
+                        max_distance: None, // Some(self.config.max_sound_distance)
+                        volume: None,
+                    });
+                }
+                self.set_monster_state(MonsterState::Investigate);
+            }
         }
+        self.monster.next_scan_pos = pos;
+        self.monster_go_to(pos);
     }
-    pub fn check_monster_sfx(&mut self, pos: Vec3<f32>) {
-        if !self.player_inside_house {
-            return;
+
+    pub fn monster_chase(&mut self, pos: Vec3<f32>) {
+        if !self.monster.is_chasing() {
+            self.events.push(WorldEvent::Sfx {
+                sfx: Sfx::GhostScream,
+                pos: Some(self.monster.pos),
+                max_distance: None, // Some(self.config.max_sound_distance * 5.0)
+                volume: None,
+            });
         }
-        if !self.monster_spawned {
-            return;
+        if self.monster.state == MonsterState::OpenDoor {
+            if self.monster.previous_state != MonsterState::Chase {
+                self.monster.previous_state = MonsterState::Chase;
+                self.monster.previous_state_time = 0.0;
+            }
+        } else {
+            self.set_monster_state(MonsterState::Chase);
         }
-        if (pos - self.monster.pos).xy().len() < self.difficulty.max_ghost_sound_distance as f32 {
-            self.monster_walk_to(pos, TargetType::Noise);
+        self.monster.last_seen_pos = pos;
+        self.monster.last_seen_vel = self.player.vel;
+        self.monster_go_to(pos);
+        let ((s, s_speed), (t, t_speed)) = self.difficulty.monster_chase_speed;
+        let k = (((pos - self.monster.pos).len() - s) / (t - s)).clamp(0.0, 1.0);
+        self.monster.speed = s_speed * (1.0 - k) + t_speed * k;
+    }
+
+    /// Picks another room to wander around in
+    pub fn monster_wander(&mut self) {
+        self.set_monster_state(MonsterState::Wander);
+        self.monster.state_time = 0.0;
+        // Sorted so that the choice only depends on the rng
+        let mut room_names: Vec<&String> = self.level.room_data.keys().collect();
+        room_names.sort();
+        let current_room = self.room_at(self.monster.pos).map(|room| room.to_owned());
+        let mut next_scan_pos = None;
+        // Rooms without any navmesh in them are skipped, but not forever
+        for _ in 0..MONSTER_WANDER_TRIES {
+            let study = if self.player_inside_house {
+                None
+            } else {
+                self.level.room_data.get("StudyRoom")
+            };
+            let room = study.or_else(|| {
+                room_names
+                    .iter()
+                    .copied()
+                    .filter(|&name| Some(name) != current_room.as_ref())
+                    .choose(&mut self.run_rng)
+                    .map(|name| &self.level.room_data[name])
+            });
+            let room_aabb = match room {
+                Some(room) => room.horizontal_aabb(),
+                None => break,
+            };
+            next_scan_pos = self
+                .navmesh
+                .waypoints
+                .iter()
+                .copied()
+                .filter(|&p| room_aabb.contains(p.xy()))
+                .choose(&mut self.run_rng);
+            if next_scan_pos.is_some() {
+                break;
+            }
         }
+        // Nowhere to go, so it looks around where it is
+        self.monster.next_scan_pos = next_scan_pos.unwrap_or(self.monster.pos);
+        self.monster_go_to(self.monster.next_scan_pos);
     }
+
+    /// Counts down standing at a spot, returns false once it is time to move on
+    fn monster_stand_still(&mut self, delta_time: f32) -> bool {
+        self.monster.stand_still_time -= delta_time;
+        if self.monster.stand_still_time > 0.0 {
+            return true;
+        }
+        self.monster.stand_still_time = {
+            let (a, b) = self.difficulty.ghost_stand_still_time;
+            self.run_rng.gen_range(a..b)
+        };
+        false
+    }
+
+    /// Goes to a random spot near the scan position
+    fn monster_roam(&mut self) {
+        let pos = self.navmesh.find_close_point(
+            self.monster.next_scan_pos,
+            self.difficulty.monster_scan_radius,
+            &mut self.run_rng,
+        );
+        self.monster_go_to(pos);
+    }
+
     pub fn update_monster(&mut self, delta_time: f32) {
         if self.game_over || self.ending {
             return;
         }
         self.monster.next_alarm_sfx -= delta_time;
-        if self.monster.speed == 1.0 {
-            if let Some((vol, music)) = &mut self.chase_music {
-                *vol -= delta_time as f64;
-                if *vol < 0.0 {
-                    music.stop();
-                    self.chase_music = None;
-                } else {
-                    music.set_volume(*vol);
-                }
-            }
-        } else {
-            if self.chase_music.is_none() && self.monster.scream_time <= 0.0 {
-                self.chase_music = Some((0.0, {
-                    let mut effect = self
-                        .assets
-                        .music
-                        .chase
-                        .choose(&mut global_rng())
-                        .unwrap()
-                        .effect();
-                    effect.set_volume(0.0);
-                    effect.play();
-                    effect
-                }));
-            }
-            if let Some((vol, music)) = &mut self.chase_music {
-                *vol = (*vol + delta_time as f64).min(1.0);
-                music.set_volume(*vol);
-            }
-        }
         if !self.monster_spawned {
             return;
         }
-        unsafe {
-            BEEN_INSIDE_HOUSE = true;
-        }
-        // Scan timer
-        if self.monster.scan_timer_going {
-            self.monster.scan_timer -= delta_time;
-            // Scan ended
-            if self.monster.scan_timer < 0.0 {
-                self.monster.speed = 1.0;
-                self.monster.scan_timer = self.difficulty.monster_scan_time;
-                self.monster.next_scan_pos = loop {
-                    // *self.navmesh.waypoints.choose(&mut global_rng()).unwrap();
-                    let current_room = self
-                        .level
-                        .room_data
-                        .iter()
-                        .find(|(_name, room)| {
-                            room.horizontal_aabb().contains(self.monster.pos.xy())
-                        })
-                        .unwrap_or(self.level.room_data.iter().next().unwrap())
-                        .0;
-                    let room = if self.player_inside_house {
-                        self.level
-                            .room_data
-                            .keys()
-                            .filter(|&name| name != current_room)
-                            .choose(&mut global_rng())
-                            .unwrap()
-                    } else {
-                        "StudyRoom"
-                    };
-                    let room = &self.level.room_data[room];
-                    let room_aabb = room.horizontal_aabb();
-                    if let Some(res) = self
-                        .navmesh
-                        .waypoints
-                        .iter()
-                        .copied()
-                        .filter(|&p| room_aabb.contains(p.xy()))
-                        .choose(&mut global_rng())
-                    {
-                        break res;
-                    }
-                };
-                self.monster.next_target_pos = self.monster.next_scan_pos;
-                self.monster.next_pathfind_pos = self.monster.pos;
+        self.monster.state_time += delta_time;
+
+        let arrived = (self.monster.pos - self.monster.next_target_pos).xy().len() < 0.1;
+        match self.monster.state {
+            MonsterState::Wander => {
+                if self.monster.state_time > self.difficulty.monster_wander_time {
+                    self.monster_wander();
+                } else if arrived && !self.monster_stand_still(delta_time) {
+                    self.monster_roam();
+                }
             }
-        }
-        self.monster.pause_time -= delta_time * self.monster.speed;
-        if (self.monster.pos - self.monster.next_target_pos).xy().len() < 0.1 {
-            let mut go_next = true;
-            self.monster.scan_timer_going = true;
-            if self.monster.target_type == TargetType::Rng {
-                self.monster.stand_still_time -= delta_time;
-                if self.monster.stand_still_time > 0.0 {
-                    go_next = false;
+            MonsterState::Search => {
+                if self.monster.state_time > self.difficulty.monster_search_time {
+                    self.monster_wander();
+                } else if arrived && !self.monster_stand_still(delta_time) {
+                    self.monster_search_next();
                 }
             }
-            if go_next {
-                self.monster.stand_still_time = {
-                    let (a, b) = self.difficulty.ghost_stand_still_time;
-                    global_rng().gen_range(a..b)
-                };
-                self.monster_walk_to(
-                    //*self.navmesh.waypoints.choose(&mut global_rng()).unwrap(),
-                    self.navmesh.find_close_point(
-                        self.monster.next_scan_pos,
-                        self.difficulty.monster_scan_radius,
-                    ),
-                    TargetType::Rng,
-                );
+            MonsterState::Investigate | MonsterState::LoseTrack => {
+                if arrived {
+                    self.monster_search(self.monster.next_target_pos);
+                }
+            }
+            MonsterState::Chase => {}
+            MonsterState::OpenDoor => {
+                if self.monster.state_time >= self.difficulty.monster_door_open_time {
+                    // Opening the door counts towards whatever it was doing
+                    let time = self.monster.previous_state_time + self.monster.state_time;
+                    self.set_monster_state(self.monster.previous_state);
+                    self.monster.state_time = time;
+                }
             }
+            MonsterState::Scripted => return,
         }
 
         if self.player_inside_house {
@@ -310,14 +433,18 @@ impl Game {
                 } else {
                     delta_time / self.difficulty.crouch_detect_time_multiplier
                 };
-                if self.monster.speed != 1.0 {
+                if self.monster.is_chasing() {
                     self.monster.detect_timer = self.difficulty.monster_detect_time;
                 }
                 if self.monster.detect_timer >= self.difficulty.monster_detect_time {
-                    self.monster_walk_to(self.player.pos, TargetType::Player);
+                    self.monster_chase(self.player.pos);
                 }
             } else {
                 self.monster.detect_timer -= delta_time;
+                if self.monster.state == MonsterState::Chase {
+                    self.set_monster_state(MonsterState::LoseTrack);
+                    self.monster_go_to(self.predicted_player_pos());
+                }
             }
             self.monster.detect_timer = self
                 .monster
@@ -327,28 +454,31 @@ impl Game {
 
         if (self.monster.pos - self.player.pos).len() < 0.5 && !self.player.god_mode {
             if !self.game_over {
-                self.stop_sounds();
-                self.game_over_sfx = Some(self.assets.sfx.jumpscare.play());
                 self.game_over = true;
-                self.monster.target_type = TargetType::Player;
-                self.monster.speed = 10.0;
+                self.events.push(WorldEvent::GameOver);
+                self.play_cutscene("game_over", 0.0);
             }
             // self.transition = Some(geng::Transition::Switch(Box::new(Game::new(
             //     &self.geng,
             //     &self.assets,
             // ))));
         }
-        if (self.monster.pos - self.monster.next_pathfind_pos).len() < 0.1 {
-            self.monster.next_pathfind_pos = self
-                .navmesh
-                .pathfind(self.monster.pos, self.monster.next_target_pos);
-            if (self.monster.pos - self.monster.next_pathfind_pos).len() < 0.1 {
+        self.update_door_gates();
+        if self.monster.path.is_none() || self.monster_path_outdated() {
+            self.monster_plan_path();
+        }
+        let path = self.monster.path.as_mut().unwrap();
+        path.advance(self.monster.pos, 0.1);
+        match path.next() {
+            Some(next) => self.monster.next_pathfind_pos = next,
+            None => {
+                // Got as close as it could
+                self.monster.next_pathfind_pos = self.monster.pos;
                 self.monster.next_target_pos = self.monster.pos;
             }
         }
 
-        self.monster.scream_time -= delta_time;
-        if self.monster.scream_time < 0.0 {
+        if !self.monster_screaming() {
             let target = self.monster.next_target_pos;
             let target = target
                 .xy()
@@ -360,63 +490,45 @@ impl Game {
                     .xy()
                     .extend(self.monster.next_pathfind_pos.z);
             };
-            if self.monster.pause_time <= 0.0 {
+            if self.monster.state != MonsterState::OpenDoor {
                 self.monster.pos += (self.monster.next_pathfind_pos - self.monster.pos)
                     .clamp_len(..=delta_time * self.monster.speed);
             }
         }
 
         for (id, interactable) in self.interactables.iter().enumerate() {
-            let name = &interactable.data.obj.meshes[0].name;
-            if !name.starts_with("D") {
+            if !interactable.data.is_door() {
                 continue;
             }
             if interactable.open {
                 continue;
             }
-            let v = vector_from_obj(
-                &interactable.data.obj,
+            let v = match interactable.data.bvh.vector_from(
                 interactable.matrix(),
                 self.monster.pos,
-            );
+                CollisionLayer::Monster,
+            ) {
+                Some(v) => v,
+                None => continue,
+            };
             let radius = 0.25;
             if v.len() < radius {
-                let mut can_open = true;
-                if name == "D_DoorStudy"
-                    && (!self.player_inside_house
-                        || (self.key_puzzle_state != KeyPuzzleState::Begin
-                            && self.key_puzzle_state != KeyPuzzleState::Finish))
-                {
-                    can_open = false;
-                }
-                // COPYPASTE YAY
-                if interactable.config.disabled
-                    || (interactable.data.obj.meshes[0].name == "D_DoorStorage"
-                        && !self.storage_unlocked)
-                    || (interactable.data.obj.meshes[0]
-                        .name
-                        .ends_with("S_StudyCloset")
-                        && self.key_puzzle_state != KeyPuzzleState::Finish)
-                {
-                    can_open = false;
-                }
-                if can_open {
+                if interactable.can_monster_open(self) {
                     self.click_interactable(id, false, self.monster.pos);
-                    self.monster.pause_time = 0.6;
+                    self.set_monster_state(MonsterState::OpenDoor);
                     break;
-                } else if self.monster.target_type != TargetType::Player {
-                    self.monster.next_target_pos = self.monster.pos;
-                    self.monster.scan_timer = 0.0;
-                    self.monster.scan_timer_going = true;
-                    self.monster.stand_still_time = 0.0;
+                } else if self.monster.state != MonsterState::Chase {
+                    // Can't get there, try somewhere else
                     let n = v.normalize_or_zero();
                     self.monster.pos += n * (radius - v.len());
+                    self.monster_wander();
+                    break;
                 }
             }
         }
 
-        let look_at_pos = match self.monster.target_type {
-            TargetType::Player => self.monster.next_target_pos,
+        let look_at_pos = match self.monster.state {
+            MonsterState::Chase => self.monster.next_target_pos,
             _ => self.monster.next_pathfind_pos,
         };
         let dv = look_at_pos - self.monster.pos;
@@ -432,33 +544,98 @@ impl Game {
         }
 
         self.monster.chase_fade = (self.monster.chase_fade
-            + if self.monster.speed == 1.0 { -1.0 } else { 1.0 } * delta_time / 0.5)
+            + if self.monster.is_chasing() { 1.0 } else { -1.0 } * delta_time / 0.5)
             .clamp(0.0, 1.0);
-        self.monster
+    }
+}
+
+impl Game {
+    pub fn update_monster_sounds(&mut self, delta_time: f32) {
+        let monster = &self.world.monster;
+        if !monster.is_chasing() {
+            if let Some((vol, music)) = &mut self.chase_music {
+                *vol -= delta_time as f64;
+                if *vol < 0.0 {
+                    music.stop();
+                    self.chase_music = None;
+                } else {
+                    music.set_volume(*vol);
+                }
+            }
+        } else {
+            if self.chase_music.is_none() && !self.world.monster_screaming() {
+                self.chase_music = Some((0.0, {
+                    let mut effect = self
+                        .assets
+                        .music
+                        .chase
+                        .choose(&mut global_rng())
+                        .unwrap()
+                        .effect();
+                    effect.set_volume(0.0);
+                    effect.play();
+                    effect
+                }));
+            }
+            if let Some((vol, music)) = &mut self.chase_music {
+                *vol = (*vol + delta_time as f64).min(1.0);
+                music.set_volume(*vol);
+            }
+        }
+        if !self.world.monster_spawned {
+            return;
+        }
+        let sounds = &mut self.monster_sounds;
+        sounds
             .loop_sound
-            .set_volume(1.0 - self.monster.chase_fade as f64);
-        self.monster
+            .set_volume(1.0 - monster.chase_fade as f64);
+        sounds
             .chase_loop_sound
-            .set_volume(self.monster.chase_fade as f64 * 1.5);
-        self.monster
+            .set_volume(monster.chase_fade as f64 * 1.5);
+        sounds
             .loop_sound
-            .set_position(self.monster.pos.map(|x| x as f64));
-        self.monster
+            .set_position(monster.pos.map(|x| x as f64));
+        sounds
             .chase_loop_sound
-            .set_position(self.monster.pos.map(|x| x as f64));
+            .set_position(monster.pos.map(|x| x as f64));
     }
+
     pub fn draw_monster(&mut self, framebuffer: &mut ugli::Framebuffer) {
-        if !self.monster_spawned {
-            return;
-        }
-        let textures = if self.monster.speed == 1.0 {
-            &self.assets.ghost.normal
-        } else {
+        let textures = if self.world.monster.is_chasing() {
             &self.assets.ghost.chasing
+        } else {
+            &self.assets.ghost.normal
         };
-        let texture = if self.ending_t > 3.0 {
-            &self.assets.ghost.dying
-        } else if self.game_over {
+        let cue_sprite = self.world.cutscene_cues().into_iter().rev().find_map(
+            |(action, progress)| match action {
+                CueAction::MonsterSprite { sprite, size, fade } => Some((
+                    *sprite,
+                    size[0] + (size[1] - size[0]) * progress,
+                    fade[0] + (fade[1] - fade[0]) * progress,
+                )),
+                _ => None,
+            },
+        );
+        if let Some((sprite, size, fade)) = cue_sprite {
+            let texture = match sprite {
+                MonsterSprite::Front => &textures.front,
+                MonsterSprite::Back => &textures.back,
+                MonsterSprite::Dying => &self.assets.ghost.dying,
+            };
+            self.draw_billboard(
+                framebuffer,
+                texture,
+                self.world.monster.pos,
+                size,
+                0.0,
+                fade,
+            );
+            return;
+        }
+        if !self.world.monster_spawned {
+            return;
+        }
+        let texture = if self.world.game_over {
             &textures.front
         } else {
             [
@@ -470,35 +647,28 @@ impl Game {
             .into_iter()
             .max_by_key(|(_texture, angle)| {
                 r32(Vec2::dot(
-                    self.monster.dir.xy(),
-                    vec2(0.0, 1.0).rotate(self.camera.rot_h + angle * f32::PI / 180.0),
+                    self.world.monster.dir.xy(),
+                    vec2(0.0, 1.0).rotate(self.world.camera.rot_h + angle * f32::PI / 180.0),
                 ))
             })
             .unwrap()
             .0
         };
 
-        if self.monster_sees_player() {
+        if self.world.monster_sees_player() {
             // texture = &self.assets.hand;
         }
-        self.draw_billboard(
-            framebuffer,
-            texture,
-            self.monster.pos,
-            if self.ending_t > 3.0 { 2.0 } else { 1.5 },
-            0.0,
-            ((self.ending_t - 3.0) / 5.0).clamp(0.0, 1.0),
-        );
+        self.draw_billboard(framebuffer, texture, self.world.monster.pos, 1.5, 0.0, 0.0);
         // self.draw_sprite(
         //     framebuffer,
         //     texture,
-        //     self.monster.pos + self.monster.dir * 0.4,
+        //     self.world.monster.pos + self.world.monster.dir * 0.4,
         //     0.3,
         //     0.0,
         // );
-        // let look_at_pos = match self.monster.target_type {
-        //     TargetType::Player => self.monster.next_target_pos,
-        //     _ => self.monster.next_pathfind_pos,
+        // let look_at_pos = match self.world.monster.state {
+        //     MonsterState::Chase => self.world.monster.next_target_pos,
+        //     _ => self.world.monster.next_pathfind_pos,
         // };
         // self.draw_sprite(framebuffer, &self.assets.hand, look_at_pos, 0.3, 0.0);
     }
@@ -1,157 +1,312 @@
 use super::*;
 
-impl Game {
-    pub fn update_movement(&mut self, delta_time: f32) {
-        if self.lock_controls || self.intro_t > 0.0 {
+/// Radius of the player's capsule. Its bottom sphere is centered at [Player::pos],
+/// and the top one [Player::height] above, where the camera is
+pub const PLAYER_RADIUS: f32 = 0.25;
+/// How high the player steps to get onto a ledge, like a stair
+pub const PLAYER_MAX_STEP: f32 = 0.3;
+/// Anything steeper is a wall, which the player slides down from
+pub const PLAYER_MIN_GROUND_NORMAL_Z: f32 = 0.7;
+/// Movement is split into steps no longer than this, so that nothing is passed through
+/// and ledges are landed on instead of sunk into
+const MAX_SUBSTEP: f32 = PLAYER_RADIUS * 0.2;
+/// Overlaps smaller than this are ignored, so that touching a wall does not count as bumping into it
+const CONTACT_SKIN: f32 = 1e-4;
+
+/// What the capsule bumped into while being pushed out, see [resolve_capsule]
+#[derive(Debug, Default, Clone, Copy)]
+struct CapsuleCollisions {
+    ground: bool,
+    wall: bool,
+}
+
+fn player_capsule(pos: Vec3<f32>, height: f32) -> Capsule {
+    Capsule {
+        a: pos,
+        b: pos + vec3(0.0, 0.0, height),
+        radius: PLAYER_RADIUS,
+    }
+}
+
+/// Ground is a walkable triangle touched from above by the bottom sphere
+fn is_ground(tri: [Vec3<f32>; 3], contact: Contact) -> bool {
+    let n = Vec3::cross(tri[1] - tri[0], tri[2] - tri[0]).normalize_or_zero();
+    n.z.abs() >= PLAYER_MIN_GROUND_NORMAL_Z && contact.normal.z > EPS
+}
+
+/// How far up the bottom sphere has to go to get out of the ground
+fn ground_lift(contact: Contact) -> f32 {
+    if contact.normal.z >= PLAYER_MIN_GROUND_NORMAL_Z {
+        return contact.depth / contact.normal.z;
+    }
+    // Standing on an edge, which is that far from the center of the sphere
+    let distance = PLAYER_RADIUS - contact.depth;
+    let horizontal = contact.normal.xy().len() * distance;
+    (PLAYER_RADIUS * PLAYER_RADIUS - horizontal * horizontal)
+        .max(0.0)
+        .sqrt()
+        - contact.normal.z * distance
+}
+
+/// Pushes the player's capsule out of the triangles.
+/// Ground only pushes up, so that the player does not slide down the slopes that can be walked on,
+/// and goes first, so that the edge of a stair lifts the player instead of stopping them
+fn resolve_capsule(
+    triangles: &[[Vec3<f32>; 3]],
+    pos: &mut Vec3<f32>,
+    vel: &mut Vec3<f32>,
+    height: f32,
+) -> CapsuleCollisions {
+    let mut result = CapsuleCollisions::default();
+    for _ in 0..4 {
+        let mut pushed = false;
+        for ground in [true, false] {
+            for &tri in triangles {
+                let contact = match player_capsule(*pos, height).contact(tri) {
+                    Some(contact) if contact.depth > CONTACT_SKIN => contact,
+                    _ => continue,
+                };
+                if is_ground(tri, contact) != ground {
+                    continue;
+                }
+                if ground {
+                    pos.z += ground_lift(contact);
+                    vel.z = vel.z.max(0.0);
+                    result.ground = true;
+                } else {
+                    // Too steep to stand on, so walls never push the player up
+                    let (normal, depth) = if contact.normal.z > 0.0 {
+                        let away = contact.normal.xy();
+                        ((away / away.len()).extend(0.0), contact.depth / away.len())
+                    } else {
+                        (contact.normal, contact.depth)
+                    };
+                    *pos += normal * depth;
+                    *vel -= normal * Vec3::dot(normal, *vel).min(0.0);
+                    result.wall = true;
+                }
+                pushed = true;
+            }
+        }
+        if !pushed {
+            break;
+        }
+    }
+    result
+}
+
+/// Lowers the capsule until it stands on something, at most by `distance`
+fn drop_capsule(
+    triangles: &[[Vec3<f32>; 3]],
+    pos: &mut Vec3<f32>,
+    vel: &mut Vec3<f32>,
+    height: f32,
+    distance: f32,
+) -> bool {
+    let steps = (distance / MAX_SUBSTEP).ceil().max(1.0) as usize;
+    for _ in 0..steps {
+        pos.z -= distance / steps as f32;
+        if resolve_capsule(triangles, pos, vel, height).ground {
+            return true;
+        }
+    }
+    false
+}
+
+/// Moves the player's capsule by `vel` over `delta_time`, stepping up onto ledges
+/// and following the ground down when it was standing on it. Returns whether it ends up on the ground
+pub fn move_capsule(
+    triangles: &[[Vec3<f32>; 3]],
+    pos: &mut Vec3<f32>,
+    vel: &mut Vec3<f32>,
+    height: f32,
+    delta_time: f32,
+    was_on_ground: bool,
+) -> bool {
+    let substeps = ((*vel * delta_time).len() / MAX_SUBSTEP).ceil().max(1.0) as usize;
+    let delta_time = delta_time / substeps as f32;
+    let mut on_ground = was_on_ground;
+    for _ in 0..substeps {
+        let start = *pos;
+        let start_vel = *vel;
+        *pos += *vel * delta_time;
+        let collisions = resolve_capsule(triangles, pos, vel, height);
+
+        if collisions.wall && on_ground {
+            // Try going over whatever is in the way
+            let mut stepped = start + vec3(0.0, 0.0, PLAYER_MAX_STEP);
+            let mut stepped_vel = start_vel;
+            resolve_capsule(triangles, &mut stepped, &mut stepped_vel, height);
+            stepped += (stepped_vel * delta_time).xy().extend(0.0);
+            resolve_capsule(triangles, &mut stepped, &mut stepped_vel, height);
+            if drop_capsule(
+                triangles,
+                &mut stepped,
+                &mut stepped_vel,
+                height,
+                PLAYER_MAX_STEP,
+            ) && (stepped - start).xy().len() > (*pos - start).xy().len() + EPS
+            {
+                *pos = stepped;
+                *vel = stepped_vel;
+                continue;
+            }
+        }
+
+        if !collisions.ground && on_ground && vel.z <= 0.0 {
+            // Stick to the ground when walking down the stairs
+            let mut down = *pos;
+            let mut down_vel = *vel;
+            if drop_capsule(triangles, &mut down, &mut down_vel, height, PLAYER_MAX_STEP) {
+                *pos = down;
+                *vel = down_vel;
+                continue;
+            }
+        }
+        on_ground = collisions.ground;
+    }
+    on_ground
+}
+
+/// Whether the player's head would get into something when standing up from `height` to `new_height`
+pub fn is_headroom_blocked(
+    triangles: &[[Vec3<f32>; 3]],
+    pos: Vec3<f32>,
+    height: f32,
+    new_height: f32,
+) -> bool {
+    if new_height <= height {
+        return false;
+    }
+    let head = Capsule {
+        a: pos + vec3(0.0, 0.0, height),
+        b: pos + vec3(0.0, 0.0, new_height),
+        // Walls the player is touching are not in the way
+        radius: PLAYER_RADIUS - 0.01,
+    };
+    triangles.iter().any(|&tri| head.contact(tri).is_some())
+}
+
+impl World {
+    /// Triangles of the level and the interactables on the layer near `pos`
+    pub fn triangles_near(
+        &self,
+        pos: Vec3<f32>,
+        radius: f32,
+        layer: CollisionLayer,
+    ) -> Vec<[Vec3<f32>; 3]> {
+        let mut triangles = self
+            .level
+            .bvh
+            .triangles_near(Mat4::identity(), pos, radius, layer);
+        for interactable in &self.interactables {
+            triangles.extend(interactable.data.bvh.triangles_near(
+                interactable.matrix(),
+                pos,
+                radius,
+                layer,
+            ));
+        }
+        triangles
+    }
+
+    /// Everything the player's capsule can touch this frame, moving or standing up
+    fn player_triangles(&self, delta_time: f32) -> Vec<[Vec3<f32>; 3]> {
+        let player = &self.player;
+        let reach = (player.vel * delta_time).len() + 1.0 + PLAYER_RADIUS + PLAYER_MAX_STEP;
+        self.triangles_near(
+            player.pos + vec3(0.0, 0.0, 0.5),
+            reach,
+            CollisionLayer::Player,
+        )
+    }
+
+    pub fn update_movement(&mut self, delta_time: f32, input: &PlayerInput) {
+        if self.lock_controls {
             return;
         }
         self.player.rot_h = normalize_angle(self.player.rot_h);
+        if self.player.hiding.is_some() {
+            self.player.vel = Vec3::ZERO;
+            return;
+        }
+
+        let triangles = self.player_triangles(delta_time);
 
         const CROUCH_TIME: f32 = 0.2;
         if self.player.crouch {
             self.show_crouch_tutorial = false;
             self.player.height += (0.5 - self.player.height).clamp(-delta_time / CROUCH_TIME, 1.0);
         } else {
-            self.player.height += (1.0 - self.player.height).clamp(-1.0, delta_time / CROUCH_TIME);
+            let height = self.player.height
+                + (1.0 - self.player.height).clamp(-1.0, delta_time / CROUCH_TIME);
+            if !is_headroom_blocked(&triangles, self.player.pos, self.player.height, height) {
+                self.player.height = height;
+            }
         }
 
         let mut walk_speed = 3.0;
         walk_speed *= self.player.height;
-        if self.player.god_mode && self.geng.window().is_key_pressed(geng::Key::LShift) {
+        if self.player.god_mode && input.sprint {
             // TODO: disable
             walk_speed *= 3.0;
         }
-        let mut mov = vec2(0.0, 0.0);
-        if self
-            .assets
-            .config
-            .controls
-            .move_forward
-            .iter()
-            .any(|button| button.is_pressed(&self.geng))
-        {
-            mov.y += 1.0;
-        }
-        if self
-            .assets
-            .config
-            .controls
-            .move_left
-            .iter()
-            .any(|button| button.is_pressed(&self.geng))
-        {
-            mov.x -= 1.0;
-        }
-        if self
-            .assets
-            .config
-            .controls
-            .move_backward
-            .iter()
-            .any(|button| button.is_pressed(&self.geng))
-        {
-            mov.y -= 1.0;
-        }
-        if self
-            .assets
-            .config
-            .controls
-            .move_right
-            .iter()
-            .any(|button| button.is_pressed(&self.geng))
-        {
-            mov.x += 1.0;
-        }
-        let mov = mov.clamp_len(..=1.0);
+        let mov = input.movement.clamp_len(..=1.0);
         let target_vel = mov.rotate(self.camera.rot_h) * walk_speed;
         let accel = 50.0;
         self.player.vel += (target_vel - self.player.vel.xy())
             .clamp_len(..=accel * delta_time)
             .extend(0.0);
         if self.player.god_mode {
-            if self.geng.window().is_key_pressed(geng::Key::Space) {
-                self.player.pos.z += delta_time * walk_speed;
+            // Flying, but still not through walls
+            self.player.vel.z = 0.0;
+            if input.fly_up {
+                self.player.vel.z += walk_speed;
             }
-            if self.geng.window().is_key_pressed(geng::Key::LCtrl) {
-                self.player.pos.z -= delta_time * walk_speed;
+            if input.fly_down {
+                self.player.vel.z -= walk_speed;
             }
-            self.player.vel.z = 0.0;
         } else {
             let gravity = 15.0;
             self.player.vel.z -= gravity * delta_time;
         }
-        self.player.pos += self.player.vel * delta_time;
+        self.player.on_ground = move_capsule(
+            &triangles,
+            &mut self.player.pos,
+            &mut self.player.vel,
+            self.player.height,
+            delta_time,
+            self.player.on_ground,
+        );
 
-        if self.player.height == 1.0 && !self.player.god_mode {
+        if self.player.height == 1.0 && self.player.on_ground {
             self.player.next_footstep -= self.player.vel.len() * delta_time;
             if self.player.next_footstep < 0.0 {
-                self.player.next_footstep = self.assets.config.footstep_dist;
-                self.assets
-                    .sfx
-                    .footsteps
-                    .choose(&mut global_rng())
-                    .unwrap()
-                    .play()
-                    .set_volume(0.5);
-                if self.player_inside_house {
-                    self.assets
-                        .sfx
-                        .footstep_creaks
-                        .choose(&mut global_rng())
-                        .unwrap()
-                        .play()
-                        .set_volume(0.5);
-                } else {
-                    self.assets
-                        .sfx
-                        .footsteps_grass
-                        .choose(&mut global_rng())
-                        .unwrap()
-                        .play()
-                        .set_volume(0.5);
-                }
-                self.check_monster_sfx(self.player.pos);
-            }
-        }
-
-        // Collisions
-        if !self.player.god_mode {
-            for _ in 0..1 {
-                let mut check = |obj: &Obj, matrix: Mat4<f32>| {
-                    for tri in obj
-                        .meshes
-                        .iter()
-                        .filter(|mesh| {
-                            if mesh.name.starts_with("B_SmallGrass")
-                                || mesh.name.starts_with("B_TallGrass")
-                                || mesh.name.starts_with("B_Tree")
-                            {
-                                return false;
-                            }
-                            true
-                        })
-                        .flat_map(|mesh| mesh.geometry.chunks(3))
-                    {
-                        let v = vector_from_triangle(
-                            [tri[0].a_v, tri[1].a_v, tri[2].a_v]
-                                .map(|pos| (matrix * pos.extend(1.0)).xyz()),
-                            self.player.pos,
-                        );
-                        let radius = 0.25;
-                        if v.len() < radius {
-                            let n = v.normalize_or_zero();
-                            self.player.vel -= n * Vec3::dot(n, self.player.vel);
-                            self.player.pos += n * (radius - v.len());
-                        }
-                    }
-                };
-                check(&self.level.obj, Mat4::identity());
-                for interactable in &self.interactables {
-                    check(&interactable.data.obj, interactable.matrix());
-                }
-                if self.player.vel.z > 1.0 {
-                    self.player.vel.z = 1.0;
+                self.player.next_footstep = self.config.footstep_dist;
+                let footsteps = [
+                    Sfx::Footstep,
+                    if self.player_inside_house {
+                        Sfx::FootstepCreak
+                    } else {
+                        Sfx::FootstepGrass
+                    },
+                ];
+                for sfx in footsteps {
+                    self.events.push(WorldEvent::Sfx {
+                        sfx,
+                        pos: None,
+                        max_distance: None,
+                        volume: Some(0.5),
+                    });
                 }
+                self.make_noise(
+                    if self.player_inside_house {
+                        Noise::Creak
+                    } else {
+                        Noise::Footstep
+                    },
+                    self.player.pos,
+                );
             }
         }
     }
@@ -0,0 +1,373 @@
+use super::*;
+
+/// Size of the heightfield cells the walkable surface is sampled with
+const CELL_SIZE: f32 = 0.2;
+/// How far from walls and furniture the monster stays
+const AGENT_RADIUS: f32 = 0.3;
+/// Waypoints float this high above the floor, same as the monster
+pub const HEIGHT_ABOVE_FLOOR: f32 = 0.45;
+/// Biggest height difference between neighbouring cells that can still be walked over
+const MAX_STEP: f32 = 0.35;
+/// Floors steeper than this are not walkable
+const MIN_FLOOR_NORMAL_Z: f32 = 0.7;
+/// Walkable area is looked for around the rooms with this margin
+const BOUNDS_MARGIN: f32 = 1.0;
+
+type Triangle = [Vec3<f32>; 3];
+
+/// Everything on the [CollisionLayer::Monster] layer.
+/// Doors are left out, they are gated at runtime instead, see [NavMesh::find_doors]
+fn obstacles(
+    obj: &Obj,
+    interactables: &[Rc<InteractableData>],
+    slides: &[String],
+    rules: &CollisionRules,
+) -> Vec<Triangle> {
+    let mut result = Vec::new();
+    let mut add = |obj: &Obj| {
+        for mesh in &obj.meshes {
+            if !rules.layers(mesh).contains(CollisionLayer::Monster) {
+                continue;
+            }
+            for tri in mesh.geometry.chunks(3) {
+                result.push([tri[0], tri[1], tri[2]].map(|v| v.a_v + v.a_bv));
+            }
+        }
+    };
+    add(obj);
+    for interactable in interactables {
+        if !is_gate(interactable, slides) {
+            add(&interactable.obj);
+        }
+    }
+    result
+}
+
+/// Where the doors stand when they are closed, as the corners of their bounding boxes
+fn gate_bounds(
+    interactables: &[Rc<InteractableData>],
+    slides: &[String],
+) -> Vec<(Vec3<f32>, Vec3<f32>)> {
+    interactables
+        .iter()
+        .filter(|interactable| is_gate(interactable, slides))
+        .map(|interactable| {
+            let mut min = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+            let mut max = -min;
+            for v in interactable.obj.meshes[0].geometry.iter() {
+                min = vec3(min.x.min(v.a_v.x), min.y.min(v.a_v.y), min.z.min(v.a_v.z));
+                max = vec3(max.x.max(v.a_v.x), max.y.max(v.a_v.y), max.z.max(v.a_v.z));
+            }
+            (min, max)
+        })
+        .collect()
+}
+
+/// Area around the rooms and triggers, or around the whole level if it has none
+fn bounds(
+    triangles: &[Triangle],
+    rooms: &HashMap<String, TriggerCube>,
+    triggers: &HashMap<String, TriggerCube>,
+) -> AABB<f32> {
+    let points: Vec<Vec2<f32>> = if rooms.is_empty() && triggers.is_empty() {
+        triangles.iter().flatten().map(|p| p.xy()).collect()
+    } else {
+        rooms
+            .values()
+            .chain(triggers.values())
+            .flat_map(|area| [vec2(area.min_x, area.min_y), vec2(area.max_x, area.max_y)])
+            .collect()
+    };
+    let mut result = AABB {
+        x_min: f32::INFINITY,
+        x_max: -f32::INFINITY,
+        y_min: f32::INFINITY,
+        y_max: -f32::INFINITY,
+    };
+    for p in points {
+        result.x_min = result.x_min.min(p.x);
+        result.x_max = result.x_max.max(p.x);
+        result.y_min = result.y_min.min(p.y);
+        result.y_max = result.y_max.max(p.y);
+    }
+    result.extend_uniform(BOUNDS_MARGIN)
+}
+
+/// Walkable floor heights of every cell, more than one where floors are stacked
+struct Heightfield {
+    origin: Vec2<f32>,
+    size: Vec2<usize>,
+    spans: Vec<Vec<f32>>,
+}
+
+impl Heightfield {
+    fn index(&self, cell: Vec2<usize>) -> usize {
+        cell.x + cell.y * self.size.x
+    }
+
+    fn cell_center(&self, cell: Vec2<usize>) -> Vec2<f32> {
+        self.origin + cell.map(|x| (x as f32 + 0.5) * CELL_SIZE)
+    }
+
+    fn corner(&self, cell: Vec2<usize>) -> Vec2<f32> {
+        self.origin + cell.map(|x| x as f32 * CELL_SIZE)
+    }
+
+    fn new(triangles: &[Triangle], bounds: AABB<f32>) -> Self {
+        let origin = vec2(bounds.x_min, bounds.y_min);
+        let size = vec2(
+            ((bounds.x_max - bounds.x_min) / CELL_SIZE).ceil() as usize,
+            ((bounds.y_max - bounds.y_min) / CELL_SIZE).ceil() as usize,
+        );
+        let cell_of =
+            |p: Vec2<f32>| -> Vec2<i64> { ((p - origin) / CELL_SIZE).map(|x| x.floor() as i64) };
+
+        // Triangles touching every cell
+        let mut bins = vec![Vec::new(); size.x * size.y];
+        for (index, tri) in triangles.iter().enumerate() {
+            let min = cell_of(vec2(
+                tri.iter().map(|p| p.x).fold(f32::INFINITY, f32::min),
+                tri.iter().map(|p| p.y).fold(f32::INFINITY, f32::min),
+            ));
+            let max = cell_of(vec2(
+                tri.iter().map(|p| p.x).fold(-f32::INFINITY, f32::max),
+                tri.iter().map(|p| p.y).fold(-f32::INFINITY, f32::max),
+            ));
+            for x in min.x.max(0)..=max.x.min(size.x as i64 - 1) {
+                for y in min.y.max(0)..=max.y.min(size.y as i64 - 1) {
+                    bins[x as usize + y as usize * size.x].push(index);
+                }
+            }
+        }
+
+        let reach = (AGENT_RADIUS / CELL_SIZE).ceil() as i64;
+        let mut result = Self {
+            origin,
+            size,
+            spans: vec![Vec::new(); size.x * size.y],
+        };
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let cell = vec2(x, y);
+                let p = result.cell_center(cell);
+                let mut floors: Vec<f32> = bins[result.index(cell)]
+                    .iter()
+                    .filter_map(|&index| floor_height(triangles[index], p))
+                    .collect();
+                floors.sort_by_key(|&z| r32(z));
+                // Closer than a step is the same floor, with a rug or a threshold on it
+                floors.dedup_by(|a, b| (*a - *b).abs() < MAX_STEP);
+                for z in floors {
+                    let agent = p.extend(z + HEIGHT_ABOVE_FLOOR);
+                    let blocked = (-reach..=reach)
+                        .flat_map(|dx| (-reach..=reach).map(move |dy| vec2(dx, dy)))
+                        .map(|d| cell.map(|x| x as i64) + d)
+                        .filter(|c| {
+                            c.x >= 0 && c.y >= 0 && c.x < size.x as i64 && c.y < size.y as i64
+                        })
+                        .flat_map(|c| &bins[c.x as usize + c.y as usize * size.x])
+                        .any(|&index| {
+                            vector_from_triangle(triangles[index], agent).len() < AGENT_RADIUS
+                        });
+                    if !blocked {
+                        let index = result.index(cell);
+                        result.spans[index].push(z);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Height of an upwards facing triangle above the point, if it is there
+fn floor_height(tri: Triangle, p: Vec2<f32>) -> Option<f32> {
+    let n = Vec3::cross(tri[1] - tri[0], tri[2] - tri[0]).normalize_or_zero();
+    if n.z < MIN_FLOOR_NORMAL_Z {
+        return None;
+    }
+    let [a, b, c] = tri.map(|v| v.xy());
+    let det = Vec2::skew(b - a, c - a);
+    if det.abs() < EPS * EPS {
+        return None;
+    }
+    let u = Vec2::skew(p - a, c - a) / det;
+    let v = Vec2::skew(b - a, p - a) / det;
+    if u < -EPS || v < -EPS || u + v > 1.0 + EPS {
+        return None;
+    }
+    Some(tri[0].z + (tri[1].z - tri[0].z) * u + (tri[2].z - tri[0].z) * v)
+}
+
+impl NavMesh {
+    /// Extracts the walkable surface of the level as convex polygons.
+    /// Cells of a heightfield are merged greedily into rectangles as big as they get
+    /// without going up or down by more than a step, going from one room into another
+    /// or going under a door, so that [NavMesh::find_rooms] and [NavMesh::find_doors]
+    /// can tell where the polygons are
+    pub fn build(
+        obj: &Obj,
+        interactables: &[Rc<InteractableData>],
+        slides: &[String],
+        rooms: &HashMap<String, TriggerCube>,
+        triggers: &HashMap<String, TriggerCube>,
+        rules: &CollisionRules,
+    ) -> Self {
+        let triangles = obstacles(obj, interactables, slides, rules);
+        let heightfield = Heightfield::new(&triangles, bounds(&triangles, rooms, triggers));
+        let size = heightfield.size;
+
+        // Room (the first one by name, same as NavMesh::find_rooms) and door of every span
+        let mut room_names: Vec<&String> = rooms.keys().collect();
+        room_names.sort();
+        let gates = gate_bounds(interactables, slides);
+        let regions: Vec<Vec<(Option<usize>, Option<usize>)>> = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| vec2(x, y)))
+            .map(|cell| {
+                let p = heightfield.cell_center(cell);
+                let min = heightfield.corner(cell);
+                let max = heightfield.corner(cell + vec2(1, 1));
+                let room = room_names
+                    .iter()
+                    .position(|name| rooms[*name].horizontal_aabb().contains(p));
+                heightfield.spans[heightfield.index(cell)]
+                    .iter()
+                    .map(|&z| {
+                        let agent = z + HEIGHT_ABOVE_FLOOR;
+                        let gate = gates.iter().position(|(a, b)| {
+                            a.x < max.x
+                                && b.x > min.x
+                                && a.y < max.y
+                                && b.y > min.y
+                                && a.z <= agent
+                                && agent <= b.z
+                        });
+                        (room, gate)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Polygon every span ended up in
+        let mut owners: Vec<Vec<Option<usize>>> = heightfield
+            .spans
+            .iter()
+            .map(|spans| vec![None; spans.len()])
+            .collect();
+        let free_span = |owners: &Vec<Vec<Option<usize>>>,
+                         cell: Vec2<usize>,
+                         z: f32,
+                         region: (Option<usize>, Option<usize>)| {
+            let index = heightfield.index(cell);
+            heightfield.spans[index]
+                .iter()
+                .enumerate()
+                .filter(|&(span, &other)| {
+                    owners[index][span].is_none()
+                        && (other - z).abs() <= MAX_STEP / 2.0
+                        && regions[index][span] == region
+                })
+                .min_by_key(|&(_, &other)| r32((other - z).abs()))
+                .map(|(span, _)| span)
+        };
+        let mut polygons = Vec::new();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let index = heightfield.index(vec2(x, y));
+                for span in 0..heightfield.spans[index].len() {
+                    if owners[index][span].is_some() {
+                        continue;
+                    }
+                    let id = polygons.len();
+                    let z = heightfield.spans[index][span];
+                    let region = regions[index][span];
+                    let mut cells = vec![(index, span)];
+                    owners[index][span] = Some(id);
+                    let mut width = 1;
+                    while x + width < size.x {
+                        let cell = vec2(x + width, y);
+                        match free_span(&owners, cell, z, region) {
+                            Some(span) => {
+                                let index = heightfield.index(cell);
+                                owners[index][span] = Some(id);
+                                cells.push((index, span));
+                                width += 1;
+                            }
+                            None => break,
+                        }
+                    }
+                    let mut height = 1;
+                    while y + height < size.y {
+                        let row: Vec<Option<usize>> = (x..x + width)
+                            .map(|x| free_span(&owners, vec2(x, y + height), z, region))
+                            .collect();
+                        if row.iter().any(|span| span.is_none()) {
+                            break;
+                        }
+                        for (dx, span) in row.into_iter().enumerate() {
+                            let index = heightfield.index(vec2(x + dx, y + height));
+                            owners[index][span.unwrap()] = Some(id);
+                            cells.push((index, span.unwrap()));
+                        }
+                        height += 1;
+                    }
+                    let z = cells
+                        .iter()
+                        .map(|&(index, span)| heightfield.spans[index][span])
+                        .sum::<f32>()
+                        / cells.len() as f32
+                        + HEIGHT_ABOVE_FLOOR;
+                    let min = heightfield.corner(vec2(x, y));
+                    let max = heightfield.corner(vec2(x + width, y + height));
+                    polygons.push(NavPolygon {
+                        vertices: vec![
+                            vec3(min.x, min.y, z),
+                            vec3(max.x, min.y, z),
+                            vec3(max.x, max.y, z),
+                            vec3(min.x, max.y, z),
+                        ],
+                        portals: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        // Portals are where connected spans of neighbouring cells belong to different polygons
+        let mut shared = HashMap::<(usize, usize), (Vec2<f32>, Vec2<f32>)>::new();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let index = heightfield.index(vec2(x, y));
+                for (dx, dy) in [(1, 0), (0, 1)] {
+                    if x + dx >= size.x || y + dy >= size.y {
+                        continue;
+                    }
+                    let other = heightfield.index(vec2(x + dx, y + dy));
+                    let a = heightfield.corner(vec2(x + dx, y + dy));
+                    let b = heightfield.corner(vec2(x + 1, y + 1));
+                    for (span, &z) in heightfield.spans[index].iter().enumerate() {
+                        for (other_span, &other_z) in heightfield.spans[other].iter().enumerate() {
+                            let v = owners[index][span].unwrap();
+                            let u = owners[other][other_span].unwrap();
+                            if v == u || (z - other_z).abs() > MAX_STEP {
+                                continue;
+                            }
+                            let (min, max) = shared.entry((v.min(u), v.max(u))).or_insert((a, b));
+                            *min = vec2(min.x.min(a.x), min.y.min(a.y));
+                            *max = vec2(max.x.max(b.x), max.y.max(b.y));
+                        }
+                    }
+                }
+            }
+        }
+        let mut shared: Vec<_> = shared.into_iter().collect();
+        shared.sort_by_key(|&((v, u), _)| (v, u));
+        for ((v, u), (a, b)) in shared {
+            let z = (polygons[v].vertices[0].z + polygons[u].vertices[0].z) / 2.0;
+            let (a, b) = (a.extend(z), b.extend(z));
+            polygons[v].portals.push(NavPortal { to: u, a, b });
+            polygons[u].portals.push(NavPortal { to: v, a, b });
+        }
+        info!("Built a navmesh of {} polygons", polygons.len());
+        Self::from_polygons(polygons)
+    }
+}
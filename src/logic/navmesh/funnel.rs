@@ -0,0 +1,85 @@
+use super::*;
+
+impl NavMesh {
+    /// Left and right ends of the portal as seen when walking from `v` to `u`
+    fn portal_sides(&self, v: usize, u: usize) -> (Vec3<f32>, Vec3<f32>) {
+        let portal = self.polygons[v]
+            .portals
+            .iter()
+            .find(|portal| portal.to == u)
+            .expect("Corridor goes through polygons that are not neighbours");
+        let dir = (self.waypoints[u] - self.waypoints[v]).xy();
+        if Vec2::skew(dir, (portal.a - self.waypoints[v]).xy()) > 0.0 {
+            (portal.a, portal.b)
+        } else {
+            (portal.b, portal.a)
+        }
+    }
+
+    /// Pulls a string through the corridor (simple stupid funnel algorithm),
+    /// giving the corners of the shortest path from `from` to `to`, not including `from`
+    pub fn string_pull(
+        &self,
+        from: Vec3<f32>,
+        to: Vec3<f32>,
+        corridor: &[usize],
+    ) -> Vec<Vec3<f32>> {
+        let mut portals = vec![(from, from)];
+        for pair in corridor.windows(2) {
+            portals.push(self.portal_sides(pair[0], pair[1]));
+        }
+        portals.push((to, to));
+
+        let same = |a: Vec3<f32>, b: Vec3<f32>| (a - b).xy().len_sqr() < 1e-6;
+        let skew = |apex: Vec3<f32>, a: Vec3<f32>, b: Vec3<f32>| {
+            Vec2::skew((a - apex).xy(), (b - apex).xy())
+        };
+
+        let mut result = Vec::new();
+        let (mut apex, mut left, mut right) = (from, from, from);
+        let (mut apex_index, mut left_index, mut right_index) = (0, 0, 0);
+        let mut i = 1;
+        while i < portals.len() {
+            let (new_left, new_right) = portals[i];
+
+            // Tighten the right side of the funnel
+            if skew(apex, right, new_right) >= 0.0 {
+                if same(apex, right) || skew(apex, left, new_right) < 0.0 {
+                    right = new_right;
+                    right_index = i;
+                } else {
+                    // Crossed over the left side, which becomes a corner
+                    result.push(left);
+                    apex = left;
+                    apex_index = left_index;
+                    right = apex;
+                    right_index = apex_index;
+                    i = apex_index + 1;
+                    continue;
+                }
+            }
+
+            // Tighten the left side of the funnel
+            if skew(apex, left, new_left) <= 0.0 {
+                if same(apex, left) || skew(apex, right, new_left) > 0.0 {
+                    left = new_left;
+                    left_index = i;
+                } else {
+                    result.push(right);
+                    apex = right;
+                    apex_index = right_index;
+                    left = apex;
+                    left_index = apex_index;
+                    i = apex_index + 1;
+                    continue;
+                }
+            }
+
+            i += 1;
+        }
+        if result.last().map_or(true, |&last| !same(last, to)) {
+            result.push(to);
+        }
+        result
+    }
+}
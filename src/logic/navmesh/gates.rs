@@ -0,0 +1,87 @@
+use super::*;
+
+/// How a door (or anything else standing on navmesh edges) lets the monster through
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoorGate {
+    Open,
+    /// Closed, but the monster can open it, which takes as long as walking `cost` further
+    Openable {
+        cost: f32,
+    },
+    Locked,
+}
+
+/// Doors and whatever puzzles slide out of the way, see [Config::slides].
+/// The navmesh is built through them, and the edges they stand on are gated at runtime
+pub fn is_gate(data: &InteractableData, slides: &[String]) -> bool {
+    let name = &data.obj.meshes[0].name;
+    data.is_door() || slides.iter().any(|slide| name.starts_with(slide.as_str()))
+}
+
+impl NavMesh {
+    pub fn gate(&self, door: &str) -> DoorGate {
+        self.gates.get(door).copied().unwrap_or(DoorGate::Open)
+    }
+
+    /// Cached routes are forgotten when the gate changes
+    pub fn set_gate(&mut self, door: &str, gate: DoorGate) {
+        if self.gates.get(door) != Some(&gate) {
+            self.gates.insert(door.to_owned(), gate);
+            self.clear_routes();
+        }
+    }
+
+    /// Cost of walking along the edge, none if a locked door is in the way
+    pub fn edge_cost(&self, v: usize, u: usize) -> Option<f32> {
+        let mut cost = (self.waypoints[v] - self.waypoints[u]).len();
+        for door in self.doors_between(v, u) {
+            match self.gate(door) {
+                DoorGate::Open => {}
+                DoorGate::Openable { cost: door_cost } => cost += door_cost,
+                DoorGate::Locked => return None,
+            }
+        }
+        Some(cost)
+    }
+}
+
+impl World {
+    /// Whether the monster can get through right now
+    pub fn door_gate(&self, name: &str) -> DoorGate {
+        let interactable = match self.interactables.iter().find(|i| i.name() == name) {
+            Some(interactable) => interactable,
+            // Transformed into something else
+            None => return DoorGate::Open,
+        };
+        if self
+            .config
+            .slides()
+            .iter()
+            .any(|slide| name.starts_with(slide.as_str()))
+        {
+            return if interactable.extra_hacky_library_moving_closet_progress >= 1.0 {
+                DoorGate::Open
+            } else {
+                DoorGate::Locked
+            };
+        }
+        if interactable.open {
+            DoorGate::Open
+        } else if interactable.can_monster_open(self) {
+            DoorGate::Openable {
+                cost: self.difficulty.monster_door_open_time * self.difficulty.monster_walk_speed,
+            }
+        } else {
+            DoorGate::Locked
+        }
+    }
+
+    pub fn update_door_gates(&mut self) {
+        let doors: Vec<String> = self.navmesh.gates.keys().cloned().collect();
+        for door in doors {
+            let gate = self.door_gate(&door);
+            self.navmesh.set_gate(&door, gate);
+        }
+    }
+}
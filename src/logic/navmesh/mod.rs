@@ -0,0 +1,467 @@
+use super::*;
+
+mod build;
+mod funnel;
+mod gates;
+mod path;
+mod report;
+
+pub use build::*;
+pub use gates::*;
+pub use path::*;
+pub use report::*;
+
+/// Convex piece of walkable surface, vertices going counter-clockwise when looking from above
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NavPolygon {
+    pub vertices: Vec<Vec3<f32>>,
+    pub portals: Vec<NavPortal>,
+}
+
+/// Edge shared with a neighbouring polygon
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NavPortal {
+    pub to: usize,
+    pub a: Vec3<f32>,
+    pub b: Vec3<f32>,
+}
+
+impl NavPolygon {
+    pub fn center(&self) -> Vec3<f32> {
+        self.vertices.iter().copied().fold(Vec3::ZERO, |a, b| a + b) / self.vertices.len() as f32
+    }
+
+    pub fn contains(&self, p: Vec2<f32>) -> bool {
+        (0..self.vertices.len()).all(|i| {
+            let a = self.vertices[i].xy();
+            let b = self.vertices[(i + 1) % self.vertices.len()].xy();
+            Vec2::skew(b - a, p - a) >= -1e-4
+        })
+    }
+
+    /// Point of the polygon closest to `p` when looking from above
+    pub fn closest_point(&self, p: Vec2<f32>) -> Vec2<f32> {
+        if self.contains(p) {
+            return p;
+        }
+        (0..self.vertices.len())
+            .map(|i| {
+                let a = self.vertices[i].xy();
+                let b = self.vertices[(i + 1) % self.vertices.len()].xy();
+                let t = (Vec2::dot(p - a, b - a) / (b - a).len_sqr().max(EPS)).clamp(0.0, 1.0);
+                a + (b - a) * t
+            })
+            .min_by_key(|&q| r32((q - p).len_sqr()))
+            .unwrap_or(p)
+    }
+}
+
+#[derive(geng::Assets, Serialize, Deserialize, Clone)]
+#[asset(json)]
+pub struct NavMesh {
+    /// Polygon centers when there are polygons
+    pub waypoints: Vec<Vec3<f32>>,
+    pub edges: Vec<Vec<usize>>,
+    /// Empty for the old waypoint grid, which is pathfound through without string pulling
+    #[serde(default)]
+    pub polygons: Vec<NavPolygon>,
+    /// Doors standing on each edge, see [NavMesh::find_doors]
+    #[serde(skip)]
+    pub doors: HashMap<(usize, usize), Vec<String>>,
+    /// Rooms connected by an edge, see [NavMesh::find_rooms]
+    #[serde(skip)]
+    pub room_neighbours: HashMap<String, Vec<String>>,
+    /// Current state of every door found by [NavMesh::find_doors]
+    #[serde(skip)]
+    pub gates: HashMap<String, DoorGate>,
+    /// Routes found so far, see [NavMesh::route]
+    #[serde(skip)]
+    pub routes: RefCell<HashMap<(usize, usize), NavRoute>>,
+    // pub debug_obj: Obj,
+}
+
+impl NavMesh {
+    pub fn from_polygons(polygons: Vec<NavPolygon>) -> Self {
+        Self {
+            waypoints: polygons.iter().map(|polygon| polygon.center()).collect(),
+            edges: polygons
+                .iter()
+                .map(|polygon| polygon.portals.iter().map(|portal| portal.to).collect())
+                .collect(),
+            polygons,
+            doors: HashMap::new(),
+            room_neighbours: HashMap::new(),
+            gates: HashMap::new(),
+            routes: default(),
+        }
+    }
+
+    /// Polygon right under (or above) the point, the closest one vertically where floors are stacked
+    pub fn polygon_at(&self, p: Vec3<f32>) -> Option<usize> {
+        self.polygons
+            .iter()
+            .enumerate()
+            .filter(|(_, polygon)| polygon.contains(p.xy()))
+            .min_by_key(|&(index, _)| r32((self.waypoints[index].z - p.z).abs()))
+            .map(|(index, _)| index)
+    }
+
+    /// Straight pieces walked along an edge, through the middle of the portal if there is one
+    fn edge_segments(&self, v: usize, u: usize) -> Vec<(Vec3<f32>, Vec3<f32>)> {
+        let (a, b) = (self.waypoints[v], self.waypoints[u]);
+        match self
+            .polygons
+            .get(v)
+            .and_then(|polygon| polygon.portals.iter().find(|portal| portal.to == u))
+        {
+            Some(portal) => {
+                let mid = (portal.a + portal.b) / 2.0;
+                vec![(a, mid), (mid, b)]
+            }
+            None => vec![(a, b)],
+        }
+    }
+
+    pub fn doors_between(&self, v: usize, u: usize) -> &[String] {
+        self.doors
+            .get(&(v.min(u), v.max(u)))
+            .map_or(&[], |doors| doors.as_slice())
+    }
+
+    /// Finds the edges that go through doors (see [is_gate]) in their closed position
+    pub fn find_doors(&mut self, interactables: &[Rc<InteractableData>], slides: &[String]) {
+        self.doors.clear();
+        self.gates.clear();
+        self.clear_routes();
+        for data in interactables {
+            let mesh = &data.obj.meshes[0];
+            if !is_gate(data, slides) {
+                continue;
+            }
+            let mut min = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+            let mut max = -min;
+            for v in mesh.geometry.iter() {
+                min = vec3(min.x.min(v.a_v.x), min.y.min(v.a_v.y), min.z.min(v.a_v.z));
+                max = vec3(max.x.max(v.a_v.x), max.y.max(v.a_v.y), max.z.max(v.a_v.z));
+            }
+            for v in 0..self.waypoints.len() {
+                for &u in &self.edges[v] {
+                    if u < v {
+                        continue;
+                    }
+                    let blocked = self.edge_segments(v, u).into_iter().any(|(a, b)| {
+                        if a.x.max(b.x) < min.x
+                            || a.x.min(b.x) > max.x
+                            || a.y.max(b.y) < min.y
+                            || a.y.min(b.y) > max.y
+                            || a.z.max(b.z) < min.z
+                            || a.z.min(b.z) > max.z
+                        {
+                            return false;
+                        }
+                        let ray = geng::CameraRay {
+                            from: a,
+                            dir: b - a,
+                        };
+                        data.bvh
+                            .raycast(Mat4::identity(), 0.0, ray, CollisionLayer::Monster)
+                            .map_or(false, |t| t <= 1.0)
+                    });
+                    if blocked {
+                        let doors = self.doors.entry((v, u)).or_default();
+                        if !doors.contains(&mesh.name) {
+                            doors.push(mesh.name.clone());
+                        }
+                        self.gates.insert(mesh.name.clone(), DoorGate::Open);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds which rooms are connected by walking from one to another
+    pub fn find_rooms(&mut self, rooms: &HashMap<String, TriggerCube>) {
+        let room_of = |p: Vec3<f32>| -> Option<&String> {
+            rooms
+                .iter()
+                .filter(|(_, room)| room.horizontal_aabb().contains(p.xy()))
+                .map(|(name, _)| name)
+                .min()
+        };
+        let waypoint_rooms: Vec<Option<&String>> =
+            self.waypoints.iter().map(|&p| room_of(p)).collect();
+        let mut neighbours: HashMap<String, std::collections::BTreeSet<String>> = HashMap::new();
+        for (start, room) in waypoint_rooms.iter().enumerate() {
+            let room = match *room {
+                Some(room) => room,
+                None => continue,
+            };
+            // Doorways are often in no room at all, so walk through them to the other side
+            let mut visited = HashSet::from([start]);
+            let mut stack = vec![start];
+            while let Some(v) = stack.pop() {
+                for &u in &self.edges[v] {
+                    if !visited.insert(u) {
+                        continue;
+                    }
+                    match waypoint_rooms[u] {
+                        Some(other) => {
+                            if other != room {
+                                neighbours
+                                    .entry(room.clone())
+                                    .or_default()
+                                    .insert(other.clone());
+                            }
+                        }
+                        None => stack.push(u),
+                    }
+                }
+            }
+        }
+        self.room_neighbours = neighbours
+            .into_iter()
+            .map(|(room, neighbours)| (room, neighbours.into_iter().collect()))
+            .collect();
+    }
+
+    /// Rooms that take exactly `depth` steps through [NavMesh::room_neighbours] to get to
+    pub fn rooms_at_depth(&self, room: &str, depth: usize) -> Vec<String> {
+        let mut visited: HashSet<String> = HashSet::from_iter([room.to_owned()]);
+        let mut layer = vec![room.to_owned()];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for room in &layer {
+                for neighbour in self.room_neighbours.get(room).into_iter().flatten() {
+                    if visited.insert(neighbour.clone()) {
+                        next.push(neighbour.clone());
+                    }
+                }
+            }
+            layer = next;
+        }
+        layer
+    }
+
+    /// Length of the shortest path, with `extra_cost` added for every edge taken.
+    /// Gives up once the path gets longer than `max_distance`
+    pub fn distance(
+        &self,
+        from: Vec3<f32>,
+        to: Vec3<f32>,
+        max_distance: f32,
+        extra_cost: impl Fn(usize, usize) -> f32,
+    ) -> Option<f32> {
+        let s = self.closest_waypoint(from);
+        let t = self.closest_waypoint(to);
+        let start = (from - self.waypoints[s]).len();
+        let end = (to - self.waypoints[t]).len();
+
+        let mut d = vec![r32(1e9f32); self.waypoints.len()];
+
+        let mut q = std::collections::BinaryHeap::new();
+        d[s] = r32(start);
+        q.push((-d[s], s));
+        while let Some((dd, v)) = q.pop() {
+            if dd != -d[v] {
+                continue;
+            }
+            if v == t {
+                let total = d[v].raw() + end;
+                return (total <= max_distance).then_some(total);
+            }
+            for u in self.edges[v].iter().copied() {
+                let cost = (self.waypoints[v] - self.waypoints[u]).len() + extra_cost(v, u);
+                let new_d = d[v] + r32(cost);
+                if new_d < d[u] && new_d <= r32(max_distance) {
+                    d[u] = new_d;
+                    q.push((-d[u], u));
+                }
+            }
+        }
+        None
+    }
+
+    /// Polygon closest to the point, going by the closest point of every polygon
+    /// rather than its center, so that a big floor right next to the point beats
+    /// a small table top above it
+    pub fn closest_waypoint(&self, p: Vec3<f32>) -> usize {
+        if let Some(index) = self
+            .polygons
+            .iter()
+            .enumerate()
+            .min_by_key(|(index, polygon)| {
+                let q = polygon.closest_point(p.xy());
+                r32((q.extend(self.waypoints[*index].z) - p).len_sqr())
+            })
+            .map(|(index, _)| index)
+        {
+            return index;
+        }
+        self.waypoints
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|(index, w)| r32((*w - p).len_sqr()))
+            .unwrap()
+            .0
+    }
+    pub fn find_close_point(
+        &self,
+        p: Vec3<f32>,
+        max_distance: f32,
+        rng: &mut impl Rng,
+    ) -> Vec3<f32> {
+        let s = self.closest_waypoint(p);
+
+        let mut d = vec![r32(1e9f32); self.waypoints.len()];
+
+        let mut q = std::collections::BinaryHeap::new();
+        d[s] = r32(0.0);
+        q.push((-d[s], s));
+        let mut options = Vec::new();
+        while let Some((dd, v)) = q.pop() {
+            if d[v] > r32(max_distance) {
+                continue;
+            }
+            options.push(v);
+            if dd != -d[v] {
+                continue;
+            }
+            for u in self.edges[v].iter().copied() {
+                let cost = (self.waypoints[v] - self.waypoints[u]).len();
+                let new_d = d[v] + r32(cost);
+                if new_d < d[u] {
+                    d[u] = new_d;
+                    q.push((-d[u], u));
+                }
+            }
+        }
+        let index = *options.choose(rng).unwrap();
+        self.waypoints[index]
+    }
+
+    pub fn remove_unreachable_from(&mut self, p: Vec3<f32>) {
+        let mut q = std::collections::VecDeque::new();
+        let mut reachable = vec![false; self.waypoints.len()];
+        let s = self.closest_waypoint(p);
+        q.push_back(s);
+        reachable[s] = true;
+        while let Some(v) = q.pop_front() {
+            for &u in &self.edges[v] {
+                if !reachable[u] {
+                    reachable[u] = true;
+                    q.push_back(u);
+                }
+            }
+        }
+        let mut new_indices = vec![0; self.waypoints.len()];
+        let mut next = 0;
+        let mut new_waypoints = Vec::new();
+        for v in 0..self.waypoints.len() {
+            if reachable[v] {
+                new_waypoints.push(self.waypoints[v]);
+                new_indices[v] = next;
+                next += 1;
+            }
+        }
+        let mut new_edges = vec![vec![]; new_waypoints.len()];
+        for v in 0..self.waypoints.len() {
+            if reachable[v] {
+                new_edges[new_indices[v]] = self.edges[v].iter().map(|&u| new_indices[u]).collect();
+            }
+        }
+        let new_polygons = self
+            .polygons
+            .iter()
+            .enumerate()
+            .filter(|&(v, _)| reachable[v])
+            .map(|(_, polygon)| NavPolygon {
+                vertices: polygon.vertices.clone(),
+                portals: polygon
+                    .portals
+                    .iter()
+                    .map(|portal| NavPortal {
+                        to: new_indices[portal.to],
+                        ..portal.clone()
+                    })
+                    .collect(),
+            })
+            .collect();
+        *self = Self {
+            waypoints: new_waypoints,
+            edges: new_edges,
+            polygons: new_polygons,
+            doors: HashMap::new(),
+            room_neighbours: HashMap::new(),
+            gates: HashMap::new(),
+            routes: default(),
+        }
+    }
+}
+
+impl World {
+    /// Bakes the navmesh of the level, `cargo run --bin bake-navmesh` saves it to navmesh.json
+    pub fn init_navmesh(level: &LevelData, config: &Config) -> NavMesh {
+        NavMesh::build(
+            &level.obj,
+            &level.interactables,
+            &config.slides(),
+            &level.room_data,
+            &level.trigger_cubes,
+            &config.collision_layers,
+        )
+    }
+}
+
+impl Game {
+    pub fn draw_debug_navmesh(&self, framebuffer: &mut ugli::Framebuffer) {
+        if !self.world.config.create_navmesh {
+            return;
+        }
+        let debug_obj = Obj {
+            meshes: vec![ObjMesh {
+                name: "debug navmesh".to_owned(),
+                geometry: Geometry::new({
+                    let mut vs = Vec::new();
+                    let navmesh = &self.world.navmesh;
+                    for v in 0..navmesh.waypoints.len() {
+                        for u in navmesh.edges[v].iter().copied() {
+                            let v = navmesh.waypoints[v];
+                            let u = navmesh.waypoints[u];
+                            let n = (v.xy() - u.xy())
+                                .rotate_90()
+                                .normalize_or_zero()
+                                .extend(0.0)
+                                * 0.05;
+                            let quad = [v + n, u + n, u - n, v - n];
+
+                            fn vertex(p: Vec3<f32>) -> Vertex {
+                                Vertex {
+                                    a_b: 0.0,
+                                    a_v: p,
+                                    a_bv: Vec3::ZERO,
+                                    a_vt: Vec2::ZERO,
+                                    a_vn: Vec3::ZERO,
+                                }
+                            }
+                            vs.push(vertex(quad[0]));
+                            vs.push(vertex(quad[1]));
+                            vs.push(vertex(quad[2]));
+                            vs.push(vertex(quad[0]));
+                            vs.push(vertex(quad[2]));
+                            vs.push(vertex(quad[3]));
+                        }
+                    }
+                    vs
+                }),
+                material: Material::untextured("debug"),
+            }],
+        };
+        self.draw_obj(
+            framebuffer,
+            &debug_obj,
+            Mat4::identity(),
+            Rgba::new(1.0, 1.0, 1.0, 0.3),
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// There are no waypoints at all
+    EmptyNavMesh,
+    /// Goal is not connected to the start
+    Unreachable,
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyNavMesh => write!(f, "navmesh is empty"),
+            Self::Unreachable => write!(f, "goal can not be reached"),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Way through the navmesh, see [NavMesh::find_path]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NavPath {
+    /// Points to walk through one after another, the last one is where the path ends
+    pub points: Vec<Vec3<f32>>,
+    /// Total length, counting from the start
+    pub length: f32,
+    /// Waypoints (or polygons) the path goes through
+    pub nodes: Vec<usize>,
+    /// The goal could not be reached, so the path only gets as close to it as possible
+    pub partial: bool,
+}
+
+impl NavPath {
+    /// Point to walk towards next, none once the path is walked through
+    pub fn next(&self) -> Option<Vec3<f32>> {
+        self.points.first().copied()
+    }
+
+    /// Drops the points that are already reached
+    pub fn advance(&mut self, pos: Vec3<f32>, reach: f32) {
+        while let Some(next) = self.next() {
+            if (next - pos).len() >= reach {
+                break;
+            }
+            self.points.remove(0);
+        }
+    }
+}
+
+/// Waypoints to go through, as cached in [NavMesh::routes]
+#[derive(Debug, Clone)]
+pub struct NavRoute {
+    pub nodes: Vec<usize>,
+    /// Whether the last node is the goal, and not just the closest node to it
+    pub reached: bool,
+}
+
+impl NavMesh {
+    /// A* from waypoint `s` to waypoint `t`, or to the one closest to it if it is not reachable.
+    /// Locked doors are not gone through, see [NavMesh::edge_cost]
+    fn search_route(&self, s: usize, t: usize) -> NavRoute {
+        let h = |v: usize| -> R32 { r32((self.waypoints[t] - self.waypoints[v]).len()) };
+
+        let mut d = vec![r32(1e9); self.waypoints.len()];
+        let mut p = vec![s; self.waypoints.len()];
+        let mut closest = s;
+
+        let mut q = std::collections::BinaryHeap::new();
+        d[s] = r32(0.0);
+        q.push((-h(s), s));
+        while let Some((ff, v)) = q.pop() {
+            if ff != -(d[v] + h(v)) {
+                continue;
+            }
+            if h(v) < h(closest) {
+                closest = v;
+            }
+            if v == t {
+                break;
+            }
+            for u in self.edges[v].iter().copied() {
+                let cost = match self.edge_cost(v, u) {
+                    Some(cost) => cost,
+                    None => continue,
+                };
+                let new_d = d[v] + r32(cost);
+                if new_d < d[u] {
+                    d[u] = new_d;
+                    p[u] = v;
+                    q.push((-(new_d + h(u)), u));
+                }
+            }
+        }
+
+        let mut nodes = vec![closest];
+        while *nodes.last().unwrap() != s {
+            nodes.push(p[*nodes.last().unwrap()]);
+        }
+        nodes.reverse();
+        NavRoute {
+            nodes,
+            reached: closest == t,
+        }
+    }
+
+    /// Waypoints to go through from `s` to `t`, remembered until [NavMesh::clear_routes]
+    pub fn route(&self, s: usize, t: usize) -> NavRoute {
+        if let Some(route) = self.routes.borrow().get(&(s, t)) {
+            return route.clone();
+        }
+        let route = self.search_route(s, t);
+        self.routes.borrow_mut().insert((s, t), route.clone());
+        route
+    }
+
+    /// Forgets the cached routes, needed once the connections change
+    pub fn clear_routes(&self) {
+        self.routes.borrow_mut().clear();
+    }
+
+    /// Shortest path from `from` to `to`. The path ends at the height of the navmesh,
+    /// at its closest point if `to` is off it
+    pub fn find_path(&self, from: Vec3<f32>, to: Vec3<f32>) -> Result<NavPath, PathError> {
+        let path = self.find_partial_path(from, to)?;
+        if path.partial {
+            return Err(PathError::Unreachable);
+        }
+        Ok(path)
+    }
+
+    /// Same as [NavMesh::find_path], but gets as close as possible to a goal that can not be reached
+    pub fn find_partial_path(&self, from: Vec3<f32>, to: Vec3<f32>) -> Result<NavPath, PathError> {
+        if self.waypoints.is_empty() {
+            return Err(PathError::EmptyNavMesh);
+        }
+        let s = self.closest_waypoint(from);
+        let t = self.closest_waypoint(to);
+        let route = self.route(s, t);
+        let end = if route.reached {
+            match self.polygons.get(t) {
+                Some(polygon) => polygon.closest_point(to.xy()),
+                None => to.xy(),
+            }
+            .extend(self.waypoints[t].z)
+        } else {
+            self.waypoints[*route.nodes.last().unwrap()]
+        };
+        let points = if self.polygons.is_empty() {
+            let mut points: Vec<Vec3<f32>> = route.nodes[1..]
+                .iter()
+                .map(|&v| self.waypoints[v])
+                .collect();
+            if points.last().map_or(true, |&last| (last - end).len() > EPS) {
+                points.push(end);
+            }
+            points
+        } else {
+            self.string_pull(from, end, &route.nodes)
+        };
+        let mut length = 0.0;
+        let mut prev = from;
+        for &p in &points {
+            length += (p - prev).len();
+            prev = p;
+        }
+        Ok(NavPath {
+            points,
+            length,
+            nodes: route.nodes,
+            partial: !route.reached,
+        })
+    }
+
+    /// Doors standing in the way of the path
+    pub fn path_doors(&self, path: &NavPath) -> Vec<String> {
+        let mut result = Vec::new();
+        for pair in path.nodes.windows(2) {
+            for door in self.doors_between(pair[0], pair[1]) {
+                if !result.contains(door) {
+                    result.push(door.clone());
+                }
+            }
+        }
+        result
+    }
+}
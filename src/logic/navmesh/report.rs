@@ -0,0 +1,104 @@
+use super::*;
+
+/// How close to a trigger cube a waypoint has to be for the monster to get there
+const TRIGGER_REACH: f32 = 0.5;
+
+/// Problems with a navmesh found by [NavMesh::report]
+#[derive(Debug, Default)]
+pub struct NavMeshReport {
+    /// Parts not connected to where the monster spawns, as a waypoint and the number of waypoints
+    pub islands: Vec<(Vec3<f32>, usize)>,
+    /// Rooms without any waypoints the monster can get to
+    pub empty_rooms: Vec<String>,
+    /// Trigger cubes the monster can not get to
+    pub unreachable_triggers: Vec<String>,
+}
+
+impl NavMeshReport {
+    /// Islands alone are fine, those are usually tops of furniture
+    pub fn has_problems(&self) -> bool {
+        !self.empty_rooms.is_empty() || !self.unreachable_triggers.is_empty()
+    }
+}
+
+impl NavMesh {
+    /// Checks how much of the level the monster can get to from `spawn`
+    pub fn report(
+        &self,
+        spawn: Vec3<f32>,
+        rooms: &HashMap<String, TriggerCube>,
+        triggers: &HashMap<String, TriggerCube>,
+    ) -> NavMeshReport {
+        let mut report = NavMeshReport::default();
+        if self.waypoints.is_empty() {
+            report.empty_rooms = rooms.keys().cloned().collect();
+            report.unreachable_triggers = triggers.keys().cloned().collect();
+            report.empty_rooms.sort();
+            report.unreachable_triggers.sort();
+            return report;
+        }
+
+        let mut component = vec![None; self.waypoints.len()];
+        let mut sizes = Vec::new();
+        for s in 0..self.waypoints.len() {
+            if component[s].is_some() {
+                continue;
+            }
+            let id = sizes.len();
+            let mut size = 0;
+            let mut q = std::collections::VecDeque::from([s]);
+            component[s] = Some(id);
+            while let Some(v) = q.pop_front() {
+                size += 1;
+                for &u in &self.edges[v] {
+                    if component[u].is_none() {
+                        component[u] = Some(id);
+                        q.push_back(u);
+                    }
+                }
+            }
+            sizes.push((s, size));
+        }
+        let main = component[self.closest_waypoint(spawn)];
+        let reachable: Vec<usize> = (0..self.waypoints.len())
+            .filter(|&v| component[v] == main)
+            .collect();
+        // Polygons can be much bigger than the area, so they only have to overlap it
+        let reaches = |aabb: AABB<f32>| {
+            reachable.iter().any(|&v| match self.polygons.get(v) {
+                Some(polygon) => AABB::points_bounding_box(polygon.vertices.iter().map(|p| p.xy()))
+                    .intersects(&aabb),
+                None => aabb.contains(self.waypoints[v].xy()),
+            })
+        };
+
+        report.islands = sizes
+            .into_iter()
+            .filter(|&(v, _)| component[v] != main)
+            .map(|(v, size)| (self.waypoints[v], size))
+            .collect();
+        report
+            .islands
+            .sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+
+        for (name, room) in rooms {
+            if !reaches(room.horizontal_aabb()) {
+                report.empty_rooms.push(name.clone());
+            }
+        }
+        report.empty_rooms.sort();
+
+        for (name, trigger) in triggers {
+            let aabb = trigger.horizontal_aabb().extend_uniform(TRIGGER_REACH);
+            let on_polygon = self
+                .polygon_at(trigger.center())
+                .map_or(false, |v| component[v] == main);
+            if !on_polygon && !reaches(aabb) {
+                report.unreachable_triggers.push(name.clone());
+            }
+        }
+        report.unreachable_triggers.sort();
+
+        report
+    }
+}
@@ -0,0 +1,58 @@
+use super::*;
+
+/// Sounds the monster can hear, how loud they are is set in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Noise {
+    Footstep,
+    Creak,
+    Door,
+    Drawer,
+    Flashlight,
+    ItemDrop,
+}
+
+impl World {
+    /// How far the noise still carries, after the difficulty is applied
+    pub fn noise_range(&self, noise: Noise) -> f32 {
+        self.config
+            .noise
+            .loudness
+            .get(&noise)
+            .copied()
+            .unwrap_or(0.0)
+            * self.difficulty.monster_hearing
+    }
+
+    /// Whether a noise at `pos` reaches the monster going around walls and through closed doors
+    pub fn monster_hears(&self, noise: Noise, pos: Vec3<f32>) -> bool {
+        let attenuation = self.config.noise.closed_door_attenuation;
+        let door_cost = |v: usize, u: usize| {
+            self.navmesh
+                .doors_between(v, u)
+                .iter()
+                .filter(|&door| {
+                    self.interactables
+                        .iter()
+                        .any(|interactable| interactable.name() == door && !interactable.open)
+                })
+                .count() as f32
+                * attenuation
+        };
+        self.navmesh
+            .distance(pos, self.monster.pos, self.noise_range(noise), door_cost)
+            .is_some()
+    }
+
+    pub fn make_noise(&mut self, noise: Noise, pos: Vec3<f32>) {
+        if !self.player_inside_house {
+            return;
+        }
+        if !self.monster_spawned {
+            return;
+        }
+        if self.monster_hears(noise, pos) {
+            self.monster_investigate(pos);
+        }
+    }
+}
@@ -14,11 +14,14 @@ pub struct Player {
     pub pos: Vec3<f32>,
     pub vel: Vec3<f32>,
     pub height: f32,
+    /// Standing on something, as opposed to falling
+    pub on_ground: bool,
     pub crouch: bool,
     pub rot_h: f32,
     pub rot_v: f32,
     pub flashdark: Flashdark,
-    pub item: Option<String>,
+    pub inventory: Inventory,
+    pub hiding: Option<Hiding>,
     pub next_footstep: f32,
     pub god_mode: bool,
 }
@@ -0,0 +1,219 @@
+use super::*;
+
+impl PuzzleKind {
+    /// Which button of a combination lock this is and what symbol it shows
+    pub fn combination_button(&self, name: &str) -> Option<(usize, u8)> {
+        let buttons = match self {
+            PuzzleKind::CombinationLock { buttons, .. } => buttons,
+            _ => return None,
+        };
+        buttons.iter().enumerate().find_map(|(index, button)| {
+            let value = name.strip_prefix(button.as_str())?.parse().ok()?;
+            Some((index, value))
+        })
+    }
+}
+
+impl World {
+    pub fn is_solved(&self, puzzle: &str) -> bool {
+        self.solved_puzzles.contains(puzzle)
+    }
+
+    /// Opened toggles and blown out candles can not be closed again
+    pub fn is_one_way(&self, interactable: &InteractableState) -> bool {
+        let name = &interactable.data.obj.meshes[0].name;
+        interactable.open
+            && self.config.puzzles.iter().any(|puzzle| match &puzzle.kind {
+                PuzzleKind::Toggles { toggles, .. } => toggles
+                    .iter()
+                    .any(|toggle| name.starts_with(toggle.as_str())),
+                PuzzleKind::AllExtinguished { candles, .. } => name.starts_with(candles.as_str()),
+                _ => false,
+            })
+    }
+
+    /// Whether the held item fits the lock, if this is a key matching lock
+    pub fn key_fits(&self, name: &str) -> Option<bool> {
+        self.config
+            .puzzles
+            .iter()
+            .find_map(|puzzle| match &puzzle.kind {
+                PuzzleKind::KeyMatch { lock, key, .. } if name.starts_with(lock.as_str()) => {
+                    Some(key)
+                }
+                _ => None,
+            })
+            .map(|key| {
+                let held = self
+                    .player
+                    .inventory
+                    .active_item()
+                    .and_then(|item| self.level.key_configs.get(item));
+                held.is_some() && held == self.level.key_configs.get(key)
+            })
+    }
+
+    /// Combination lock buttons turn into their next symbol when clicked
+    pub fn puzzle_transform(&self, name: &str) -> Option<String> {
+        self.config
+            .puzzles
+            .iter()
+            .find_map(|puzzle| match &puzzle.kind {
+                PuzzleKind::CombinationLock {
+                    buttons, symbols, ..
+                } => {
+                    let (index, value) = puzzle.kind.combination_button(name)?;
+                    Some(format!("{}{}", buttons[index], (value + 1) % symbols))
+                }
+                _ => None,
+            })
+    }
+
+    pub fn puzzle_sfx(&self, name: &str) -> Option<Sfx> {
+        self.config
+            .puzzles
+            .iter()
+            .find_map(|puzzle| match &puzzle.kind {
+                PuzzleKind::CombinationLock { sfx, .. } => {
+                    puzzle.kind.combination_button(name).and(sfx.clone())
+                }
+                _ => None,
+            })
+    }
+
+    /// Blows out a candle, returns false if it is not part of any puzzle
+    pub fn extinguish(&mut self, id: Id) -> bool {
+        let name = self.interactables[id].data.obj.meshes[0].name.clone();
+        let config = self.config.clone();
+        let (index, candles, sfx, dim_ambient_light) = match config
+            .puzzles
+            .iter()
+            .enumerate()
+            .find_map(|(index, puzzle)| match &puzzle.kind {
+                PuzzleKind::AllExtinguished {
+                    candles,
+                    sfx,
+                    dim_ambient_light,
+                } if name.starts_with(candles.as_str()) => {
+                    Some((index, candles, sfx, dim_ambient_light))
+                }
+                _ => None,
+            }) {
+            Some(puzzle) => puzzle,
+            None => return false,
+        };
+        self.interactables[id].open = true;
+        let all = self
+            .interactables
+            .iter()
+            .filter(|i| i.data.obj.meshes[0].name.starts_with(candles.as_str()))
+            .count();
+        let lit = self
+            .interactables
+            .iter()
+            .filter(|i| i.data.obj.meshes[0].name.starts_with(candles.as_str()))
+            .filter(|i| !i.open)
+            .count();
+        if *dim_ambient_light {
+            self.ambient_light = Rgba::lerp(
+                Rgba::BLACK,
+                self.config.ambient_light_inside_house,
+                lit as f32 / all as f32,
+            );
+        }
+        if lit == 0 {
+            self.solve_puzzle(index);
+        }
+        if let Some(sfx) = sfx {
+            self.play_sfx(sfx.clone());
+        }
+        true
+    }
+
+    /// Checks the puzzles after `name` has been used
+    pub fn update_puzzles(&mut self, name: &str) {
+        let config = self.config.clone();
+        for (index, puzzle) in config.puzzles.iter().enumerate() {
+            if self.is_solved(&puzzle.name) {
+                continue;
+            }
+            let solved = match &puzzle.kind {
+                PuzzleKind::CombinationLock { buttons, .. } => {
+                    let combination = &self.level.combinations[&puzzle.name];
+                    puzzle.kind.combination_button(name).is_some()
+                        && (0..buttons.len()).all(|index| {
+                            self.interactables.iter().any(|interactable| {
+                                puzzle
+                                    .kind
+                                    .combination_button(&interactable.data.obj.meshes[0].name)
+                                    == Some((index, combination[index]))
+                            })
+                        })
+                }
+                PuzzleKind::Toggles {
+                    toggles,
+                    required,
+                    slides,
+                    step,
+                } => {
+                    let open = self
+                        .interactables
+                        .iter()
+                        .filter(|i| {
+                            let name = &i.data.obj.meshes[0].name;
+                            toggles
+                                .iter()
+                                .any(|toggle| name.starts_with(toggle.as_str()))
+                        })
+                        .filter(|i| i.open)
+                        .count();
+                    let progress = if open >= *required {
+                        1.0
+                    } else {
+                        open as f32 * step
+                    };
+                    for i in self.interactables.iter_mut().filter(|i| {
+                        let name = &i.data.obj.meshes[0].name;
+                        slides.iter().any(|slide| name.starts_with(slide.as_str()))
+                    }) {
+                        i.extra_hacky_library_moving_closet_progress = progress;
+                    }
+                    open >= *required
+                }
+                PuzzleKind::KeyMatch { lock: part, .. }
+                | PuzzleKind::ItemSocket { socket: part } => name.starts_with(part.as_str()),
+                PuzzleKind::AllExtinguished { .. } | PuzzleKind::Scripted => false,
+            };
+            if solved {
+                self.solve_puzzle(index);
+            }
+        }
+    }
+
+    /// Does nothing if it is already solved
+    pub fn solve_named_puzzle(&mut self, name: &str) {
+        match self
+            .config
+            .puzzles
+            .iter()
+            .position(|puzzle| puzzle.name == name)
+        {
+            Some(index) => {
+                if !self.is_solved(name) {
+                    self.solve_puzzle(index);
+                }
+            }
+            None => warn!("There is no puzzle {name:?}"),
+        }
+    }
+
+    fn solve_puzzle(&mut self, index: usize) {
+        let config = self.config.clone();
+        let puzzle = &config.puzzles[index];
+        info!("Solved {:?}", puzzle.name);
+        self.solved_puzzles.insert(puzzle.name.clone());
+        for effect in &puzzle.on_solved {
+            self.apply_effect(effect);
+        }
+    }
+}
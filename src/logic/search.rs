@@ -0,0 +1,131 @@
+use super::*;
+
+impl World {
+    pub fn room_at(&self, pos: Vec3<f32>) -> Option<&str> {
+        self.level
+            .room_data
+            .iter()
+            .filter(|(_, room)| room.horizontal_aabb().contains(pos.xy()))
+            .map(|(name, _)| name.as_str())
+            .min()
+    }
+
+    /// Like [World::room_at], but something in a doorway counts as in the closest room next to it
+    pub fn room_near(&self, pos: Vec3<f32>) -> Option<&str> {
+        const DOORWAY_DEPTH: f32 = 0.5;
+        self.room_at(pos).or_else(|| {
+            self.level
+                .room_data
+                .iter()
+                .map(|(name, room)| {
+                    let aabb = room.horizontal_aabb();
+                    let dx = (aabb.x_min - pos.x).max(pos.x - aabb.x_max).max(0.0);
+                    let dy = (aabb.y_min - pos.y).max(pos.y - aabb.y_max).max(0.0);
+                    (name.as_str(), vec2(dx, dy).len())
+                })
+                .filter(|&(_, distance)| distance < DOORWAY_DEPTH)
+                .min_by_key(|&(name, distance)| (r32(distance), name))
+                .map(|(name, _)| name)
+        })
+    }
+
+    /// Where the player probably went, judging by how they were moving when last seen
+    pub fn predicted_player_pos(&self) -> Vec3<f32> {
+        let last_seen = self.monster.last_seen_pos;
+        let predicted = last_seen
+            + self.monster.last_seen_vel.xy().extend(0.0) * self.difficulty.monster_predict_time;
+        let predicted = self.navmesh.waypoints[self.navmesh.closest_waypoint(predicted)];
+        // Don't guess through walls
+        let up = vec3(0.0, 0.0, 0.5);
+        if self.can_see(last_seen + up, predicted + up) {
+            predicted
+        } else {
+            last_seen
+        }
+    }
+
+    /// Navmesh points next to everything the player could be hiding in or behind
+    pub fn hiding_spots(&self) -> Vec<Vec3<f32>> {
+        let is_spot = |name: &str| {
+            self.config
+                .monster_hiding_spots
+                .iter()
+                .any(|spot| name.starts_with(spot.as_str()))
+        };
+        let level = self
+            .level
+            .obj
+            .meshes
+            .iter()
+            .filter(|mesh| is_spot(&mesh.name))
+            .map(|mesh| find_center(&mesh.geometry));
+        let interactables = self
+            .interactables
+            .iter()
+            .filter(|interactable| is_spot(interactable.name()))
+            .map(|interactable| {
+                (interactable.matrix()
+                    * find_center(&interactable.data.obj.meshes[0].geometry).extend(1.0))
+                .xyz()
+            });
+        let mut waypoints: Vec<usize> = level
+            .chain(interactables)
+            .map(|pos| self.navmesh.closest_waypoint(pos))
+            .collect();
+        waypoints.sort();
+        waypoints.dedup();
+        waypoints
+            .into_iter()
+            .map(|v| self.navmesh.waypoints[v])
+            .collect()
+    }
+
+    /// Starts looking around `pos`, spreading to the neighbouring rooms over time
+    pub fn monster_search(&mut self, pos: Vec3<f32>) {
+        self.set_monster_state(MonsterState::Search);
+        self.monster.next_scan_pos = pos;
+        self.monster.search_room = self.room_at(pos).map(|room| room.to_owned());
+        self.monster.search_round = 0;
+        self.monster.search_spots = self.search_spots(0);
+        self.monster.stand_still_time = 0.0;
+    }
+
+    /// Hiding spots in the rooms `round` steps away from where the search started, nearest first,
+    /// followed by a random point that gets further away every round
+    pub fn search_spots(&mut self, round: usize) -> Vec<Vec3<f32>> {
+        let rooms = match &self.monster.search_room {
+            Some(room) => self.navmesh.rooms_at_depth(room, round),
+            None => Vec::new(),
+        };
+        let mut spots: Vec<Vec3<f32>> = self
+            .hiding_spots()
+            .into_iter()
+            .filter(|&pos| {
+                self.room_near(pos)
+                    .map_or(false, |room| rooms.iter().any(|name| name == room))
+            })
+            .collect();
+        spots.sort_by_key(|&pos| r32((pos - self.monster.pos).len()));
+        spots.push(self.navmesh.find_close_point(
+            self.monster.next_scan_pos,
+            self.difficulty.monster_scan_radius * (round + 1) as f32,
+            &mut self.run_rng,
+        ));
+        spots
+    }
+
+    /// Goes to the next spot to check, or gives up once every round is done
+    pub fn monster_search_next(&mut self) {
+        if self.monster.search_spots.is_empty() {
+            if self.monster.search_round >= self.difficulty.monster_search_rounds {
+                self.monster_wander();
+                return;
+            }
+            self.monster.search_round += 1;
+            info!("Widening the search to round {}", self.monster.search_round);
+            self.monster.search_spots = self.search_spots(self.monster.search_round);
+        }
+        let pos = self.monster.search_spots.remove(0);
+        self.monster_go_to(pos);
+    }
+}
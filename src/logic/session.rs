@@ -0,0 +1,119 @@
+use super::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReachedCheckpoint {
+    /// Name of the [CheckpointConfig]
+    pub checkpoint: String,
+    /// Checkpoints the run was past when this one was reached, in order
+    pub passed: Vec<String>,
+    pub pos: Vec3<f32>,
+    pub rot_h: f32,
+}
+
+/// What carries over when the run is restarted after dying
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub intro_seen: bool,
+    /// Latest time each checkpoint was reached, in order
+    pub checkpoints: Vec<ReachedCheckpoint>,
+    /// Checkpoint the run starts from, the very start if `None`
+    pub restart_from: Option<String>,
+}
+
+impl Session {
+    pub fn reached(&self, checkpoint: &str) -> Option<&ReachedCheckpoint> {
+        self.checkpoints
+            .iter()
+            .find(|reached| reached.checkpoint == checkpoint)
+    }
+
+    /// Checkpoints the run starts past, in order
+    pub fn restored(&self) -> Vec<String> {
+        match self
+            .restart_from
+            .as_deref()
+            .and_then(|checkpoint| self.reached(checkpoint))
+        {
+            Some(reached) => {
+                let mut restored = reached.passed.clone();
+                restored.push(reached.checkpoint.clone());
+                restored
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl World {
+    /// Shown on the game over screen, the name if the config has no such checkpoint
+    pub fn checkpoint_title<'a>(&'a self, checkpoint: &'a str) -> &'a str {
+        self.config
+            .checkpoints
+            .iter()
+            .find(|config| config.name == checkpoint)
+            .map_or(checkpoint, |config| config.title.as_str())
+    }
+
+    /// Does nothing if the run is already past it
+    pub fn reach_checkpoint(&mut self, checkpoint: &str) {
+        if self.main_menu || self.passed_checkpoints.iter().any(|c| c == checkpoint) {
+            return;
+        }
+        info!("Reached {checkpoint:?}");
+        self.session
+            .checkpoints
+            .retain(|reached| reached.checkpoint != checkpoint);
+        self.session.checkpoints.push(ReachedCheckpoint {
+            checkpoint: checkpoint.to_owned(),
+            passed: self.passed_checkpoints.clone(),
+            pos: self.player.pos,
+            rot_h: self.player.rot_h,
+        });
+        self.passed_checkpoints.push(checkpoint.to_owned());
+        self.events.push(WorldEvent::CheckpointReached);
+    }
+
+    /// Puts the world past the checkpoint, without moving the player
+    pub fn restore_checkpoint(&mut self, checkpoint: &str) {
+        if self.passed_checkpoints.iter().any(|c| c == checkpoint) {
+            return;
+        }
+        self.passed_checkpoints.push(checkpoint.to_owned());
+        let config = self.config.clone();
+        match config
+            .checkpoints
+            .iter()
+            .find(|config| config.name == checkpoint)
+        {
+            Some(config) => {
+                for effect in &config.restore {
+                    self.apply_effect(effect);
+                }
+            }
+            None => warn!("There is no checkpoint {checkpoint:?}"),
+        }
+    }
+
+    /// Starts the run from where the session says
+    pub fn restart_session(&mut self) {
+        for checkpoint in self.session.restored() {
+            self.restore_checkpoint(&checkpoint);
+        }
+        let start = self
+            .session
+            .restart_from
+            .as_deref()
+            .and_then(|checkpoint| self.session.reached(checkpoint))
+            .map(|reached| (reached.pos, reached.rot_h));
+        match start {
+            // The intro would walk the player back to the spawn
+            Some((pos, rot_h)) => {
+                self.player.pos = pos;
+                self.player.rot_h = rot_h;
+                self.player.vel = Vec3::ZERO;
+            }
+            None if self.session.intro_seen => self.play_cutscene_skipped("intro"),
+            None => self.play_cutscene("intro", 0.0),
+        }
+    }
+}
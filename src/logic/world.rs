@@ -0,0 +1,236 @@
+use super::*;
+
+/// State of the controls, sampled once per tick
+#[derive(Debug, Clone, Default)]
+pub struct PlayerInput {
+    /// Walking direction relative to the camera, `y` is forward
+    pub movement: Vec2<f32>,
+    /// Any key is held, used for skipping cutscenes
+    pub skip: bool,
+    // God mode only
+    pub sprint: bool,
+    pub fly_up: bool,
+    pub fly_down: bool,
+}
+
+/// One-shot sound effects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sfx {
+    Footstep,
+    FootstepCreak,
+    FootstepGrass,
+    GhostScream,
+    GhostAlarmed,
+    FlashOn,
+    FlashOff,
+    BrokenFlashlight,
+    LightFlicker,
+    StudyLights,
+    BlowCandle,
+    Ending,
+    GenericPickup,
+    Bat,
+    GrandClock,
+    DoorOpen,
+    DoorClose,
+    DrawerOpen,
+    DrawerClose,
+    SymbolsPuzzleButton,
+    SymbolsPuzzleSolved,
+    /// Sound file name from the interactables config
+    Named(String),
+}
+
+/// Long sounds that are started and stopped by the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Track {
+    TvNoise,
+    Swing,
+    CreepySinging,
+    MusicBox,
+    Intro,
+    GameOver,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundMusic {
+    Outside,
+    Ambient,
+}
+
+/// The world does not play or save anything itself,
+/// these are collected during the update and drained by [World::take_events]
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldEvent {
+    Sfx {
+        sfx: Sfx,
+        pos: Option<Vec3<f32>>,
+        max_distance: Option<f64>,
+        volume: Option<f64>,
+    },
+    Music(BackgroundMusic),
+    StartTrack(Track),
+    StopTrack(Track),
+    StopSounds,
+    /// A good moment to save the run
+    CheckpointReached,
+    /// The run can not be continued anymore
+    GameOver,
+}
+
+/// All of the gameplay state, runs without a window or audio
+pub struct World {
+    pub config: Rc<Config>,
+    pub difficulty: Difficulty,
+    pub session: Session,
+    /// Checkpoints this run is past, the restored ones included
+    pub passed_checkpoints: Vec<String>,
+    pub events: Vec<WorldEvent>,
+    pub main_menu: bool,
+    pub paused: bool,
+    pub rng: RngState,
+    pub run_rng: StdRng,
+    pub level: LevelData,
+    pub navmesh: NavMesh,
+    pub camera: Camera,
+    pub player: Player,
+    pub interactables: Vec<InteractableState>,
+    pub items: Vec<Item>,
+    pub monster: Monster,
+    pub lights: Collection<Light>,
+    pub time: f32,
+    pub ambient_light: Rgba<f32>,
+    pub player_inside_house: bool,
+    pub show_crouch_tutorial: bool,
+    pub show_flashlight_tutorial: bool,
+    pub solved_puzzles: HashSet<String>,
+    pub key_puzzle_state: KeyPuzzleState,
+    pub monster_spawned: bool,
+    pub fuse_spawned: bool,
+    pub fuse_placed: bool,
+    pub lock_controls: bool,
+    pub game_over: bool,
+    pub ending: bool,
+    pub cutscenes: Vec<CutscenePlayback>,
+    /// How long any key has been held for to skip the cutscene, up to 1
+    pub skip_t: f32,
+    /// Names of the `once` events that have fired
+    pub fired_events: HashSet<String>,
+    /// Seconds until the events can fire again
+    pub event_cooldowns: HashMap<String, f32>,
+    /// Seconds left to shake the level meshes for
+    pub shaking: HashMap<String, f32>,
+    pub light_flicker_time: f32,
+    pub bat_t: f32,
+    pub bat_go: bool,
+}
+
+impl World {
+    pub fn new(
+        config: Rc<Config>,
+        difficulty: Difficulty,
+        level: LevelData,
+        mut navmesh: NavMesh,
+        mut run_rng: StdRng,
+        session: Session,
+        main_menu: bool,
+    ) -> Self {
+        navmesh.find_doors(&level.interactables, &config.slides());
+        navmesh.find_rooms(&level.room_data);
+        let items = Self::initialize_items(&config, &level, &mut run_rng);
+        let interactables = Self::initialize_interactables(&config, &level, &mut run_rng);
+        let mut res = Self {
+            events: Vec::new(),
+            main_menu,
+            paused: false,
+            rng: RngState::new(),
+            run_rng,
+            time: 0.0,
+            ambient_light: config.ambient_light,
+            player_inside_house: false,
+            show_crouch_tutorial: true,
+            show_flashlight_tutorial: true,
+            solved_puzzles: HashSet::new(),
+            key_puzzle_state: KeyPuzzleState::Begin,
+            monster_spawned: false,
+            fuse_spawned: main_menu, // LOL
+            fuse_placed: false,
+            lock_controls: false,
+            game_over: false,
+            ending: false,
+            cutscenes: Vec::new(),
+            skip_t: 0.0,
+            fired_events: HashSet::new(),
+            event_cooldowns: HashMap::new(),
+            shaking: HashMap::new(),
+            light_flicker_time: 0.0,
+            bat_t: 0.0,
+            bat_go: false,
+            player: Player {
+                crouch: false,
+                pos: level.spawn_point,
+                height: 1.0,
+                on_ground: false,
+                vel: Vec3::ZERO,
+                rot_h: 0.0,
+                rot_v: 0.0,
+                flashdark: Flashdark {
+                    pos: Vec3::ZERO,
+                    rot_h: 0.0,
+                    rot_v: 0.0,
+                    dir: vec3(0.0, 1.0, 0.0),
+                    on: true,
+                    strength: 1.0,
+                    dark: 0.0,
+                },
+                inventory: Inventory::new(config.inventory_size),
+                hiding: None,
+                next_footstep: 0.0,
+                god_mode: false,
+            },
+            camera: Camera {
+                pos: level.spawn_point,
+                fov: f32::PI / 2.0,
+                rot_h: 0.0,
+                rot_v: 0.0,
+            },
+            lights: Self::initialize_lights(&level),
+            monster: Monster::new(&config, &difficulty, &level),
+            items,
+            interactables,
+            navmesh,
+            level,
+            difficulty,
+            session,
+            passed_checkpoints: Vec::new(),
+            config,
+        };
+        res.restart_session();
+        res
+    }
+
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        mem::take(&mut self.events)
+    }
+
+    pub fn play_sfx(&mut self, sfx: Sfx) {
+        self.events.push(WorldEvent::Sfx {
+            sfx,
+            pos: None,
+            max_distance: None,
+            volume: None,
+        });
+    }
+
+    pub fn play_sfx_at(&mut self, sfx: Sfx, pos: Vec3<f32>) {
+        self.events.push(WorldEvent::Sfx {
+            sfx,
+            pos: Some(pos),
+            max_distance: Some(self.config.max_sound_distance),
+            volume: None,
+        });
+    }
+}
@@ -29,7 +29,7 @@ impl Game {
             self.particles.next_particle += 1.0 / 100.0;
             let range = 10.0;
             self.particles.instances.push(Particle {
-                i_pos: (self.camera.pos.xy()
+                i_pos: (self.world.camera.pos.xy()
                     + vec2(
                         global_rng().gen_range(-range..range),
                         global_rng().gen_range(-range..range),
@@ -61,16 +61,16 @@ impl Game {
             (
                 ugli::uniforms! {
                     u_color: Rgba::WHITE,
-                    u_flashdark_pos: self.player.flashdark.pos,
-                    u_flashdark_dir: self.player.flashdark.dir,
+                    u_flashdark_pos: self.world.player.flashdark.pos,
+                    u_flashdark_dir: self.world.player.flashdark.dir,
                     u_flashdark_angle: f32::PI / 4.0,
-                    u_flashdark_strength: self.player.flashdark.strength,
-                    u_flashdark_dark: self.player.flashdark.dark,
-                    u_ambient_light_color: self.ambient_light,
+                    u_flashdark_strength: self.world.player.flashdark.strength,
+                    u_flashdark_dark: self.world.player.flashdark.dark,
+                    u_ambient_light_color: self.world.ambient_light,
                     u_noise: &self.noise,
                     u_texture: &self.assets.dust_particle,
                 },
-                geng::camera3d_uniforms(&self.camera, self.framebuffer_size),
+                geng::camera3d_uniforms(&self.world.camera, self.framebuffer_size),
                 self.light_uniforms(),
             ),
             ugli::DrawParameters {
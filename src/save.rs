@@ -0,0 +1,421 @@
+use super::*;
+
+/// Bump this whenever the layout of [SaveData] changes,
+/// old saves are then rejected instead of being misread
+pub const SAVE_VERSION: u32 = 14;
+
+const SAVE_KEY: &str = "flashdark_save.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedPlayer {
+    pub pos: Vec3<f32>,
+    pub rot_h: f32,
+    pub rot_v: f32,
+    pub height: f32,
+    pub crouch: bool,
+    pub flashdark_on: bool,
+    pub flashdark_dark: f32,
+    pub inventory: Vec<Option<String>>,
+    pub active_slot: usize,
+    pub hiding: Option<Hiding>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedInteractable {
+    pub name: String,
+    pub open: bool,
+    pub open_other_way: bool,
+    pub progress: f32,
+    pub extra_hacky_library_moving_closet_progress: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedItem {
+    pub name: String,
+    pub matrix: Mat4<f32>,
+    pub mesh_index: usize,
+    pub dropped_mesh: Option<String>,
+    pub parent_interactable: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedMonster {
+    pub pos: Vec3<f32>,
+    pub dir: Vec3<f32>,
+    pub state: MonsterState,
+    pub previous_state: MonsterState,
+    pub state_time: f32,
+    pub previous_state_time: f32,
+    pub next_target_pos: Vec3<f32>,
+    pub next_scan_pos: Vec3<f32>,
+    pub last_seen_pos: Vec3<f32>,
+    pub last_seen_vel: Vec3<f32>,
+    pub search_room: Option<String>,
+    pub search_round: usize,
+    pub search_spots: Vec<Vec3<f32>>,
+    pub stand_still_time: f32,
+    pub speed: f32,
+}
+
+/// Everything about a run in progress, see [World::save_data]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedWorld {
+    pub level: LevelRandomization,
+    /// [World::run_rng] is reseeded with this when saving, so that the resumed run
+    /// gets the same random numbers as the one that kept going
+    pub rng_seed: u64,
+    pub player: SavedPlayer,
+    pub interactables: Vec<SavedInteractable>,
+    pub items: Vec<SavedItem>,
+    pub monster: SavedMonster,
+    pub key_puzzle_state: KeyPuzzleState,
+    pub solved_puzzles: Vec<String>,
+    /// [World::fired_events]
+    pub fired_events: Vec<String>,
+    pub fuse_spawned: bool,
+    pub fuse_placed: bool,
+    pub monster_spawned: bool,
+    pub player_inside_house: bool,
+    pub cutscenes: Vec<CutscenePlayback>,
+    pub ambient_light: Rgba<f32>,
+    pub session: Session,
+    /// [World::passed_checkpoints]
+    pub passed_checkpoints: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveData {
+    pub version: u32,
+    pub seed: u64,
+    /// [LevelInfo::id]
+    pub level_id: String,
+    pub world: SavedWorld,
+}
+
+impl SaveData {
+    /// Returns `Ok(None)` if there is no save,
+    /// and an error if the save exists but can not be used
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let value: serde_json::Value = match batbox::preferences::load(SAVE_KEY) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if value.is_null() {
+            return Ok(None);
+        }
+        let version = value.get("version").and_then(|version| version.as_u64());
+        if version != Some(SAVE_VERSION as u64) {
+            anyhow::bail!("Save version is {version:?}, expected {SAVE_VERSION}");
+        }
+        Ok(Some(serde_json::from_value(value)?))
+    }
+
+    pub fn save(&mut self) {
+        batbox::preferences::save(SAVE_KEY, self);
+    }
+
+    pub fn clear() {
+        batbox::preferences::save(SAVE_KEY, &serde_json::Value::Null);
+    }
+
+    pub fn exists() -> bool {
+        matches!(Self::load(), Ok(Some(_)))
+    }
+}
+
+impl Game {
+    pub fn can_save(&self) -> bool {
+        !self.world.main_menu
+            && !self.world.game_over
+            && !self.world.ending
+            && !self.world.cutscene_playing("intro")
+    }
+
+    /// Does nothing if the run can not be resumed from here.
+    /// The game saves on pausing, exiting and at checkpoints,
+    /// since nothing is run when the page gets closed on the web
+    pub fn save(&mut self) {
+        if self.can_save() {
+            self.save_data().save();
+        }
+    }
+
+    pub fn save_data(&mut self) -> SaveData {
+        SaveData {
+            version: SAVE_VERSION,
+            seed: self.seed,
+            level_id: self.level_id.clone(),
+            world: self.world.save_data(),
+        }
+    }
+
+    pub fn from_save(
+        geng: &Geng,
+        assets: &Rc<Assets>,
+        save: SaveData,
+        cli_seed: Option<u64>,
+    ) -> Self {
+        // The saved state is applied over the run, no checkpoint is restored
+        let session = Session {
+            intro_seen: true,
+            restart_from: None,
+            ..save.world.session.clone()
+        };
+        let mut game = Self::new_with_level(
+            geng,
+            assets,
+            &save.level_id,
+            session,
+            false,
+            Some(&save.world.level),
+            save.seed,
+            cli_seed,
+        );
+        game.apply_save(save);
+        game
+    }
+
+    fn apply_save(&mut self, save: SaveData) {
+        self.stop_sounds();
+        self.music = None;
+        self.tv_noise = None;
+        self.swing_sfx = None;
+        self.intro_sfx = None;
+        self.monster_sounds = MonsterSounds::new(&self.assets);
+        if let Some(sfx) = &mut self.piano_music {
+            sfx.play();
+        }
+
+        self.world.apply_save(save.world);
+
+        self.music = Some(if self.world.player_inside_house {
+            let mut music = self.assets.sfx.ambient.effect();
+            music.set_volume(0.8);
+            music.play();
+            music
+        } else {
+            self.assets.music.outside.play()
+        });
+        if self.world.fuse_spawned && !self.world.player_inside_house {
+            if let Some(swing) = self.world.level.trigger_cubes.get("SwingingSwing") {
+                let mut swing_sfx = self.assets.sfx.swing_loop.effect();
+                swing_sfx.set_position(swing.center().map(|x| x as f64));
+                swing_sfx.play();
+                self.swing_sfx = Some(swing_sfx);
+            }
+        }
+        if self.world.fuse_placed && self.world.key_puzzle_state != KeyPuzzleState::Finish {
+            let mut tv_noise = self.assets.sfx.tv_static.effect();
+            tv_noise.set_position(self.world.level.monster_spawn().map(|x| x as f64));
+            tv_noise.set_max_distance(2.0);
+            tv_noise.play();
+            self.tv_noise = Some(tv_noise);
+        }
+    }
+
+    /// Resume the saved run if there is one, otherwise start a new one
+    pub fn continue_or_reset(&mut self) {
+        if self.cli_seed.is_some() {
+            // Replaying a specific seed
+            self.reset();
+            return;
+        }
+        match SaveData::load() {
+            // Starting another level replaces its save
+            Ok(Some(save)) if save.level_id != self.level_id => self.reset(),
+            Ok(Some(save)) => {
+                self.transition = Some(geng::Transition::Switch(Box::new(Game::from_save(
+                    &self.geng,
+                    &self.assets,
+                    save,
+                    self.cli_seed,
+                ))));
+            }
+            Ok(None) => self.reset(),
+            Err(e) => {
+                error!("Failed to load the save: {e}");
+                SaveData::clear();
+                self.reset();
+            }
+        }
+    }
+}
+
+impl World {
+    /// Reseeds [World::run_rng], see [SavedWorld::rng_seed]
+    pub fn save_data(&mut self) -> SavedWorld {
+        let rng_seed = self.run_rng.gen();
+        self.run_rng = StdRng::seed_from_u64(rng_seed);
+        SavedWorld {
+            rng_seed,
+            level: self.level.randomization(),
+            player: SavedPlayer {
+                pos: self.player.pos,
+                rot_h: self.player.rot_h,
+                rot_v: self.player.rot_v,
+                height: self.player.height,
+                crouch: self.player.crouch,
+                flashdark_on: self.player.flashdark.on,
+                flashdark_dark: self.player.flashdark.dark,
+                inventory: self.player.inventory.slots.clone(),
+                active_slot: self.player.inventory.active,
+                hiding: self.player.hiding.clone(),
+            },
+            interactables: self
+                .interactables
+                .iter()
+                .map(|interactable| SavedInteractable {
+                    name: interactable.data.obj.meshes[0].name.clone(),
+                    open: interactable.open,
+                    open_other_way: interactable.open_other_way,
+                    progress: interactable.progress,
+                    extra_hacky_library_moving_closet_progress: interactable
+                        .extra_hacky_library_moving_closet_progress,
+                })
+                .collect(),
+            items: self
+                .items
+                .iter()
+                .map(|item| SavedItem {
+                    name: item.name.clone(),
+                    matrix: item.matrix,
+                    mesh_index: item.mesh_index,
+                    dropped_mesh: item.dropped_mesh.clone(),
+                    parent_interactable: item.parent_interactable.clone(),
+                })
+                .collect(),
+            monster: SavedMonster {
+                pos: self.monster.pos,
+                dir: self.monster.dir,
+                state: self.monster.state,
+                previous_state: self.monster.previous_state,
+                state_time: self.monster.state_time,
+                previous_state_time: self.monster.previous_state_time,
+                next_target_pos: self.monster.next_target_pos,
+                next_scan_pos: self.monster.next_scan_pos,
+                last_seen_pos: self.monster.last_seen_pos,
+                last_seen_vel: self.monster.last_seen_vel,
+                search_room: self.monster.search_room.clone(),
+                search_round: self.monster.search_round,
+                search_spots: self.monster.search_spots.clone(),
+                stand_still_time: self.monster.stand_still_time,
+                speed: self.monster.speed,
+            },
+            key_puzzle_state: self.key_puzzle_state,
+            solved_puzzles: {
+                let mut solved: Vec<String> = self.solved_puzzles.iter().cloned().collect();
+                solved.sort();
+                solved
+            },
+            fired_events: {
+                let mut fired: Vec<String> = self.fired_events.iter().cloned().collect();
+                fired.sort();
+                fired
+            },
+            fuse_spawned: self.fuse_spawned,
+            fuse_placed: self.fuse_placed,
+            monster_spawned: self.monster_spawned,
+            player_inside_house: self.player_inside_house,
+            cutscenes: self.cutscenes.clone(),
+            ambient_light: self.ambient_light,
+            session: self.session.clone(),
+            passed_checkpoints: self.passed_checkpoints.clone(),
+        }
+    }
+
+    /// Puts the world where it was when [World::save_data] was called,
+    /// the level has to be generated from [SavedWorld::level]
+    pub fn apply_save(&mut self, save: SavedWorld) {
+        self.monster = Monster::new(&self.config, &self.difficulty, &self.level);
+        self.run_rng = StdRng::seed_from_u64(save.rng_seed);
+        self.key_puzzle_state = save.key_puzzle_state;
+        self.solved_puzzles = save.solved_puzzles.into_iter().collect();
+        self.fired_events = save.fired_events.into_iter().collect();
+        self.fuse_spawned = save.fuse_spawned;
+        self.fuse_placed = save.fuse_placed;
+        self.monster_spawned = save.monster_spawned;
+        self.passed_checkpoints = save.passed_checkpoints;
+        self.player_inside_house = save.player_inside_house;
+        self.cutscenes = save.cutscenes;
+        self.lock_controls = self.cutscene_locks_controls();
+        self.ambient_light = save.ambient_light;
+
+        self.player.pos = save.player.pos;
+        self.player.vel = Vec3::ZERO;
+        self.player.rot_h = save.player.rot_h;
+        self.player.rot_v = save.player.rot_v;
+        self.player.height = save.player.height;
+        self.player.crouch = save.player.crouch;
+        self.player.flashdark.on = save.player.flashdark_on;
+        self.player.flashdark.dark = save.player.flashdark_dark;
+        self.player.inventory = Inventory::new(save.player.inventory.len());
+        self.player.inventory.slots = save.player.inventory;
+        self.player.inventory.active = save
+            .player
+            .active_slot
+            .min(self.player.inventory.slots.len() - 1);
+        self.player.hiding = save.player.hiding;
+
+        self.interactables = save
+            .interactables
+            .into_iter()
+            .filter_map(|saved| {
+                let data = match self
+                    .level
+                    .interactables
+                    .iter()
+                    .find(|data| data.obj.meshes[0].name == saved.name)
+                {
+                    Some(data) => data.clone(),
+                    None => {
+                        warn!("Saved interactable {:?} not found in the level", saved.name);
+                        return None;
+                    }
+                };
+                Some(InteractableState {
+                    open: saved.open,
+                    open_other_way: saved.open_other_way,
+                    progress: saved.progress,
+                    extra_hacky_library_moving_closet_progress: saved
+                        .extra_hacky_library_moving_closet_progress,
+                    config: self
+                        .config
+                        .interactables
+                        .get(&saved.name)
+                        .cloned()
+                        .unwrap_or_default(),
+                    data,
+                })
+            })
+            .collect();
+        self.items = save
+            .items
+            .into_iter()
+            .map(|saved| Item {
+                name: saved.name,
+                matrix: saved.matrix,
+                mesh_index: saved.mesh_index,
+                dropped_mesh: saved.dropped_mesh,
+                parent_interactable: saved.parent_interactable,
+            })
+            .collect();
+
+        self.monster.pos = save.monster.pos;
+        self.monster.dir = save.monster.dir;
+        self.monster.state = save.monster.state;
+        self.monster.previous_state = save.monster.previous_state;
+        self.monster.state_time = save.monster.state_time;
+        self.monster.previous_state_time = save.monster.previous_state_time;
+        self.monster.next_target_pos = save.monster.next_target_pos;
+        self.monster.next_pathfind_pos = save.monster.pos;
+        self.monster.path = None;
+        self.monster.next_scan_pos = save.monster.next_scan_pos;
+        self.monster.last_seen_pos = save.monster.last_seen_pos;
+        self.monster.last_seen_vel = save.monster.last_seen_vel;
+        self.monster.search_room = save.monster.search_room;
+        self.monster.search_round = save.monster.search_round;
+        self.monster.search_spots = save.monster.search_spots;
+        self.monster.stand_still_time = save.monster.stand_still_time;
+        self.monster.speed = save.monster.speed;
+    }
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+fn random_ray(rng: &mut StdRng) -> geng::CameraRay {
+    geng::CameraRay {
+        from: vec3(
+            rng.gen_range(-20.0..20.0),
+            rng.gen_range(-20.0..20.0),
+            rng.gen_range(0.0..3.0),
+        ),
+        dir: vec3(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        ),
+    }
+}
+
+#[test]
+fn level_bvh_agrees_with_checking_every_triangle() {
+    let t = TestWorld::new();
+    let level = &t.world.level;
+    let rules = &t.world.config.collision_layers;
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for _ in 0..200 {
+        let ray = random_ray(&mut rng);
+        assert_eq!(
+            level
+                .bvh
+                .raycast(Mat4::identity(), 0.1, ray, CollisionLayer::Sight),
+            intersect_ray_with_obj(
+                &level.obj,
+                Mat4::identity(),
+                0.1,
+                ray,
+                rules,
+                CollisionLayer::Sight
+            ),
+        );
+        assert_eq!(
+            level
+                .bvh
+                .vector_from(Mat4::identity(), ray.from, CollisionLayer::Player)
+                .unwrap()
+                .len(),
+            vector_from_obj(
+                &level.obj,
+                Mat4::identity(),
+                ray.from,
+                rules,
+                CollisionLayer::Player
+            )
+            .len(),
+        );
+    }
+}
+
+#[test]
+fn interactable_bvh_moves_with_the_interactable() {
+    let mut t = TestWorld::new();
+    let rules = t.world.config.collision_layers.clone();
+    let rules = &rules;
+    let door = t
+        .world
+        .interactables
+        .iter_mut()
+        .find(|i| i.data.is_door())
+        .unwrap();
+    door.progress = 0.7;
+    let matrix = door.matrix();
+    let data = &door.data;
+    let center = (matrix * find_center(&data.obj.meshes[0].geometry).extend(1.0)).xyz();
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for _ in 0..50 {
+        let from = center
+            + vec3(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+        // Not right at the center, that can be on the edge between two triangles
+        let to = center + vec3(rng.gen_range(-0.1..0.1), 0.0, rng.gen_range(-0.1..0.1));
+        let ray = geng::CameraRay {
+            from,
+            dir: to - from,
+        };
+        let layer = CollisionLayer::Raycast;
+        let expected = intersect_ray_with_obj(&data.obj, matrix, 0.0, ray, rules, layer);
+        match data.bvh.raycast(matrix, 0.0, ray, layer) {
+            Some(t) => assert!((t - expected.unwrap()).abs() < 1e-3),
+            None => assert_eq!(expected, None),
+        }
+        let layer = CollisionLayer::Player;
+        let v = data.bvh.vector_from(matrix, from, layer).unwrap();
+        let expected = vector_from_obj(&data.obj, matrix, from, rules, layer);
+        assert!((v.len() - expected.len()).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn collision_rules_pick_the_longest_prefix() {
+    let t = TestWorld::new();
+    let rules: CollisionRules =
+        serde_json::from_str(r#"{"B_": [], "B_Tree": ["sight", "raycast"]}"#).unwrap();
+    let mesh = |prefix: &str| {
+        t.world
+            .level
+            .obj
+            .meshes
+            .iter()
+            .find(|mesh| mesh.name.starts_with(prefix))
+            .unwrap()
+    };
+    let tree = rules.layers(mesh("B_Tree"));
+    assert!(tree.contains(CollisionLayer::Sight));
+    assert!(!tree.contains(CollisionLayer::Player));
+    assert_eq!(rules.layers(mesh("B_TallGrass")), CollisionLayers::NONE);
+    assert_eq!(rules.layers(mesh("S_")), CollisionLayers::ALL);
+}
+
+#[test]
+fn grass_only_blocks_sight() {
+    let t = TestWorld::new();
+    let rules = &t.world.config.collision_layers;
+    for mesh in &t.world.level.obj.meshes {
+        let layers = rules.layers(mesh);
+        let is_grass = ["B_SmallGrass", "B_TallGrass", "B_Tree"]
+            .iter()
+            .any(|prefix| mesh.name.starts_with(prefix));
+        assert_eq!(layers.contains(CollisionLayer::Player), !is_grass);
+        assert_eq!(layers.contains(CollisionLayer::Monster), !is_grass);
+        assert!(layers.contains(CollisionLayer::Sight));
+        assert!(layers.contains(CollisionLayer::Raycast));
+    }
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+#[test]
+fn intro_is_skipped_by_holding_a_key() {
+    let mut t = TestWorld::with_session(default());
+    assert!(t.world.cutscene_playing("intro"));
+    assert!(t.world.lock_controls);
+    let fence = t
+        .world
+        .interactables
+        .iter()
+        .position(|i| i.name().contains("FenceDoor"))
+        .unwrap();
+
+    let skip = PlayerInput {
+        skip: true,
+        ..default()
+    };
+    for _ in 0..(1.5 / TICK) as usize {
+        t.tick(&skip);
+    }
+    assert!(!t.world.cutscene_playing("intro"));
+    assert!(!t.world.lock_controls);
+    assert!(t.world.session.intro_seen);
+    assert!(!t.world.interactables[fence].open);
+    assert!(t.events.contains(&WorldEvent::StopTrack(Track::Intro)));
+    assert!(t
+        .events
+        .contains(&WorldEvent::Music(BackgroundMusic::Outside)));
+    assert!((t.world.player.pos.xy() - t.world.level.spawn_point.xy()).len() < 0.01);
+}
+
+#[test]
+fn game_over_plays_out() {
+    let mut t = TestWorld::new();
+    t.world.monster_spawned = true;
+    t.world.monster.pos = t.world.player.pos;
+    t.wait(TICK);
+    assert!(t.world.game_over);
+    assert!(t.world.cutscene_playing("game_over"));
+    assert!(t.world.lock_controls);
+    assert!(t.events.contains(&WorldEvent::StartTrack(Track::GameOver)));
+    assert!(t.events.contains(&WorldEvent::GameOver));
+    assert_eq!(t.world.monster.state, MonsterState::Chase);
+
+    t.wait(6.1);
+    assert!(!t.world.cutscene_playing("game_over"));
+}
+
+#[test]
+fn instant_cues_fire_once() {
+    let mut t = TestWorld::new();
+    let mut config = (*t.world.config).clone();
+    config.cutscenes.insert(
+        "test".to_owned(),
+        serde_json::from_str(
+            r#"{
+                "duration": 1.0,
+                "cues": [
+                    { "at": 0.0, "type": "effect", "effect": { "type": "play_sfx", "sfx": "bat" } },
+                    { "at": 0.5, "type": "effect", "effect": { "type": "play_sfx", "sfx": "bat" } },
+                    { "at": 1.0, "type": "effect", "effect": { "type": "play_sfx", "sfx": "bat" } }
+                ]
+            }"#,
+        )
+        .unwrap(),
+    );
+    t.world.config = Rc::new(config);
+    let bats = |t: &TestWorld| {
+        t.events
+            .iter()
+            .filter(|event| matches!(event, WorldEvent::Sfx { sfx: Sfx::Bat, .. }))
+            .count()
+    };
+
+    t.world.play_cutscene("test", 0.0);
+    assert!(!t.world.lock_controls);
+    t.wait(2.0);
+    assert!(!t.world.cutscene_playing("test"));
+    assert_eq!(bats(&t), 3);
+
+    // Starting part way skips the earlier cues
+    t.world.play_cutscene("test", 0.7);
+    t.wait(1.0);
+    assert_eq!(bats(&t), 4);
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+fn count(t: &TestWorld, sfx: Sfx) -> usize {
+    t.events
+        .iter()
+        .filter(|event| matches!(event, WorldEvent::Sfx { sfx: played, .. } if *played == sfx))
+        .count()
+}
+
+fn area_center(t: &TestWorld, name: &str) -> Vec2<f32> {
+    t.world.area(name).unwrap().center().xy()
+}
+
+#[test]
+fn bat_only_flies_when_looking_its_way() {
+    let mut t = TestWorld::new();
+    let pos = area_center(&t, "BatTrigger");
+    t.teleport_xy(pos);
+    t.world.player.rot_h = -f32::PI / 2.0;
+    t.wait(0.5);
+    assert!(!t.world.bat_go);
+
+    t.world.player.rot_h = f32::PI / 2.0;
+    t.wait(0.5);
+    assert!(t.world.bat_go);
+    assert_eq!(count(&t, Sfx::Bat), 1);
+
+    // Once per run
+    t.teleport_xy(area_center(&t, "Kitchen"));
+    t.wait(0.1);
+    t.teleport_xy(pos);
+    t.wait(0.5);
+    assert_eq!(count(&t, Sfx::Bat), 1);
+    assert!(t.world.fired_events.contains("bat"));
+}
+
+#[test]
+fn grand_clock_chimes_again_after_the_cooldown() {
+    let mut t = TestWorld::new();
+    t.teleport_xy(area_center(&t, "GrandClock"));
+    t.wait(0.5);
+    assert_eq!(count(&t, Sfx::GrandClock), 1);
+    assert!(t.world.shaking.contains_key("S_GrandfatherClock"));
+    t.wait(1.0);
+    assert!(t.world.shaking.is_empty());
+    t.wait(59.0);
+    assert_eq!(count(&t, Sfx::GrandClock), 2);
+}
+
+#[test]
+fn events_come_from_the_config() {
+    let mut t = TestWorld::new();
+    let mut config = (*t.world.config).clone();
+    config.events = serde_json::from_str(
+        r##"[{
+            "name": "kitchen_scare",
+            "when": { "kind": "inside", "area": "Kitchen" },
+            "once": true,
+            "require_item": "Doll",
+            "actions": [
+                { "type": "set_ambient_light", "color": "#FF0000" },
+                { "type": "move_monster", "to": "Kitchen" },
+                { "type": "play_sfx_at", "sfx": "bat", "at": "Kitchen" }
+            ]
+        }]"##,
+    )
+    .unwrap();
+    t.world.config = Rc::new(config);
+    let kitchen = area_center(&t, "Kitchen");
+    t.teleport_xy(kitchen);
+    t.wait(0.1);
+    assert_eq!(count(&t, Sfx::Bat), 0);
+
+    t.give("Doll");
+    t.wait(0.5);
+    assert_eq!(count(&t, Sfx::Bat), 1);
+    assert_eq!(t.world.ambient_light, Rgba::new(1.0, 0.0, 0.0, 1.0));
+    assert!((t.world.monster.pos.xy() - kitchen).len() < 1.0);
+}
+
+#[test]
+fn front_door_slams_behind_the_player() {
+    let mut t = TestWorld::new();
+    let door = t.interactable_id("D_DoorMain").unwrap();
+    t.world.click_interactable(door, false, Vec3::ZERO);
+    t.wait(1.0);
+    assert!(t.interactable("D_DoorMain").open);
+    t.teleport_xy(area_center(&t, "HouseEntrance"));
+    t.wait(0.1);
+    assert!(t.world.player_inside_house);
+    assert!(!t.interactable("D_DoorMain").open);
+    assert!(t.events.contains(&WorldEvent::StopTrack(Track::Swing)));
+    assert_eq!(
+        t.world.ambient_light,
+        t.world.config.ambient_light_inside_house
+    );
+}
+
+#[test]
+fn effects_set_flags_and_solve_puzzles() {
+    let mut t = TestWorld::new();
+    t.world.apply_effect(&Effect::SetFlag {
+        flag: WorldFlag::MonsterSpawned,
+        value: true,
+    });
+    assert!(t.world.monster_spawned);
+
+    // Closing the front door brings the music from the config
+    let id = t.world.interactable_id("D_DoorMain").unwrap();
+    t.world.interactables[id].open = true;
+    t.world.interactables[id].progress = 1.0;
+    t.world.apply_effect(&Effect::Close {
+        interactable: "D_DoorMain".to_owned(),
+    });
+    t.wait(1.0);
+    assert!(t
+        .events
+        .contains(&WorldEvent::Music(BackgroundMusic::Ambient)));
+
+    t.world.apply_effect(&Effect::SolvePuzzle {
+        puzzle: "light_out".to_owned(),
+        without_effects: false,
+    });
+    assert!(t.world.is_solved("light_out"));
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::LightOut);
+    assert!(!t.world.player.flashdark.on);
+    assert_eq!(t.world.monster.state, MonsterState::Wander);
+}
+
+#[test]
+fn lights_out_without_a_key_puzzle() {
+    let mut t = TestWorld::new();
+    let mut config = (*t.world.config).clone();
+    config.puzzles.retain(|puzzle| puzzle.name != "study_key");
+    t.world.config = Rc::new(config);
+    t.world.key_puzzle_state = KeyPuzzleState::LightOut;
+    t.world.toggle_flashdark(false);
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::Ready);
+    assert!(!t.has_interactable("I_HintKey"));
+    assert!(t.item_id("TheStudyKeyPuzzleSolution").is_none());
+}
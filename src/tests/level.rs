@@ -0,0 +1,237 @@
+use super::*;
+
+fn level() -> Level {
+    Level::load_headless(&assets_path().join("level").join("roomMVP.obj")).unwrap()
+}
+
+#[test]
+fn level_manifest_matches_the_mesh_names() {
+    let level = level();
+    assert_eq!(LevelManifest::import(&level.obj), level.manifest);
+}
+
+#[test]
+fn level_manifest_problems_are_all_reported() {
+    let level = level();
+    let mut manifest = level.manifest.clone();
+    manifest.skybox = "NoSuchMesh".to_owned();
+    manifest
+        .rooms
+        .insert("Attic".to_owned(), manifest.player_spawn.clone());
+    manifest.planks.as_mut().unwrap().count = 100;
+    manifest.uv_rules[0].scale = 0.0;
+    let error = manifest.validate(&level.obj).unwrap_err();
+    assert_eq!(error.problems.len(), 4, "{error}");
+    assert!(error.problems[0].contains("\"NoSuchMesh\""), "{error}");
+    assert!(error.problems[1].contains("room \"Attic\""), "{error}");
+    assert!(error.problems[2].contains("100 planks"), "{error}");
+}
+
+#[test]
+fn level_manifest_decides_what_meshes_are() {
+    let mut level = level();
+    let door = level
+        .manifest
+        .interactables
+        .iter_mut()
+        .find(|interactable| matches!(interactable.kind, InteractableKind::Door { .. }))
+        .unwrap();
+    let door_name = door.mesh.clone();
+    door.kind = InteractableKind::Static;
+    let trigger = level.manifest.triggers.remove("BatTrigger").unwrap();
+    level.manifest.triggers.insert("Bats".to_owned(), trigger);
+
+    let config: Config = load_json("config.json");
+    let data = LevelData::generate(
+        &level,
+        &config.puzzles,
+        &config.collision_layers,
+        None,
+        &mut StdRng::seed_from_u64(SEED),
+    );
+    let door = data
+        .interactables
+        .iter()
+        .find(|interactable| interactable.obj.meshes[0].name == door_name)
+        .unwrap();
+    assert!(matches!(door.typ, InteractableType::Static));
+    assert!(data.trigger_cubes.contains_key("Bats"));
+    assert!(!data.trigger_cubes.contains_key("BatTrigger"));
+    assert!(data.trigger_cubes.contains_key("GhostSpawn"));
+}
+
+#[test]
+fn interactables_keep_their_manifest_kind() {
+    let mut level = level();
+    let drawer = level
+        .manifest
+        .interactables
+        .iter_mut()
+        .find(|interactable| interactable.kind == InteractableKind::Drawer)
+        .unwrap();
+    let drawer_name = drawer.mesh.clone();
+    drawer.open_time = Some(1.5);
+
+    let config: Config = load_json("config.json");
+    let data = LevelData::generate(
+        &level,
+        &config.puzzles,
+        &config.collision_layers,
+        None,
+        &mut StdRng::seed_from_u64(SEED),
+    );
+    let find = |name: &str| {
+        data.interactables
+            .iter()
+            .find(|interactable| interactable.obj.meshes[0].name == name)
+            .unwrap()
+    };
+    assert_eq!(find(&drawer_name).open_time, 1.5);
+    assert!(!find(&drawer_name).is_door());
+    let fence = find("DL_FenceDoor_L");
+    assert!(fence.is_door());
+    assert_eq!(fence.open_time, 2.0);
+    assert!(matches!(
+        find("DL_S_StudyCloset").kind,
+        InteractableKind::Door {
+            fixed_swing: true,
+            ..
+        }
+    ));
+    assert_eq!(find("D_DoorMain").open_time, 0.6);
+}
+
+#[test]
+fn level_registry_checks_ids() {
+    let mut infos: Vec<LevelInfo> = load_json("levels.json");
+    LevelRegistry::check(&infos).unwrap();
+
+    infos[0].next = Some("nowhere".to_owned());
+    assert!(LevelRegistry::check(&infos).is_err());
+    infos[0].next = Some(infos[0].id.clone());
+    LevelRegistry::check(&infos).unwrap();
+
+    infos.push(infos[0].clone());
+    assert!(LevelRegistry::check(&infos).is_err());
+    assert!(LevelRegistry::check(&[]).is_err());
+}
+
+#[test]
+fn level_config_overrides_config_json() {
+    let base: serde_json::Value = load_json("config.json");
+    let mut info = load_json::<Vec<LevelInfo>>("levels.json").remove(0);
+    info.config = serde_json::from_str(
+        r#"{ "inventory_size": 2, "noise": { "closed_door_attenuation": 1.0 } }"#,
+    )
+    .unwrap();
+    let config = info.config(&base).unwrap();
+    assert_eq!(config.inventory_size, 2);
+    assert_eq!(config.noise.closed_door_attenuation, 1.0);
+    assert_eq!(config.noise.loudness[&Noise::Door], 4.0);
+
+    info.config = serde_json::from_str(r#"{ "inventory_size": "two" }"#).unwrap();
+    assert!(info.config(&base).is_err());
+}
+
+#[test]
+fn level_config_with_an_unknown_sound_fails() {
+    let base: serde_json::Value = load_json("config.json");
+    let mut info = load_json::<Vec<LevelInfo>>("levels.json").remove(0);
+    info.config = serde_json::from_str(
+        r#"{ "interactables": { "I_FusePlaceholder": { "sfx": "fuseBoxHum.mp3" } } }"#,
+    )
+    .unwrap();
+    let error = info.config(&base).unwrap_err().to_string();
+    assert!(error.contains("\"fuseBoxHum.mp3\""), "{error}");
+    assert!(error.contains(&format!("{:?}", info.id)), "{error}");
+}
+
+#[test]
+fn level_without_the_house_triggers_still_plays() {
+    let mut t = TestWorld::new();
+    let level = &mut t.world.level;
+    level.trigger_cubes.retain(|name, _| name == "GhostSpawn");
+    level.room_data.remove("Kitchen");
+    level.room_data.remove("PlayRoom");
+    t.world.play_cutscene("tv", 0.0);
+    t.wait(6.0);
+    assert!(t.world.monster_spawned);
+    assert!(!t.world.player_inside_house);
+}
+
+#[test]
+fn monster_wanders_in_a_level_without_rooms() {
+    let mut t = TestWorld::new();
+    let level = &mut t.world.level;
+    level.room_data.remove("StudyRoom");
+    assert!(!t.world.player_inside_house);
+    t.world.monster_wander();
+    assert_ne!(t.world.monster.next_scan_pos, t.world.monster.pos);
+
+    t.world.level.room_data.clear();
+    t.world.level.trigger_cubes.clear();
+    assert_eq!(t.world.level.monster_spawn(), t.world.level.spawn_point);
+    t.world.monster_wander();
+    assert_eq!(t.world.monster.next_scan_pos, t.world.monster.pos);
+}
+
+#[test]
+fn level_without_planks_or_key_puzzles() {
+    let mut level = level();
+    level.manifest.planks = None;
+    let mut config: Config = load_json("config.json");
+    config
+        .puzzles
+        .retain(|puzzle| !matches!(puzzle.kind, PuzzleKind::KeyMatch { .. }));
+
+    let data = LevelData::generate(
+        &level,
+        &config.puzzles,
+        &config.collision_layers,
+        None,
+        &mut StdRng::seed_from_u64(SEED),
+    );
+    assert!(data.planks.is_empty());
+    assert!(data.key_configs.is_empty());
+    assert!(!data.items.contains_key("TheStudyKeyPuzzleSolution"));
+    assert!(data.items["Doll"]
+        .spawns
+        .iter()
+        .all(|spawn| spawn.parent_interactable.is_none()));
+}
+
+/// Everything that is picked at random when a run starts
+fn run_layout(t: &TestWorld) -> (serde_json::Value, Vec<(String, Vec3<f32>)>, Vec<String>) {
+    let mut items: Vec<(String, Vec3<f32>)> = t
+        .world
+        .items
+        .iter()
+        .map(|item| {
+            let pos = t.world.item_matrix(item) * vec4(0.0, 0.0, 0.0, 1.0);
+            (item.name.clone(), pos.xyz())
+        })
+        .collect();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut interactables: Vec<String> = t
+        .world
+        .interactables
+        .iter()
+        .map(|interactable| interactable.data.obj.meshes[0].name.clone())
+        .collect();
+    interactables.sort();
+    (
+        serde_json::to_value(t.world.level.randomization()).unwrap(),
+        items,
+        interactables,
+    )
+}
+
+#[test]
+fn same_seed_gives_the_same_run() {
+    let layout = run_layout(&TestWorld::with_seed(SEED));
+    assert_eq!(run_layout(&TestWorld::with_seed(SEED)), layout);
+
+    let other = run_layout(&TestWorld::with_seed(SEED + 1));
+    assert_ne!(other.0, layout.0);
+    assert_ne!(other.1, layout.1);
+}
@@ -0,0 +1,344 @@
+//! Scripted playthroughs of the puzzle chains on the real level, without a window
+
+use super::*;
+
+mod bvh;
+mod cutscene;
+mod events;
+mod level;
+mod monster;
+mod movement;
+mod navmesh;
+mod noise;
+mod obj;
+mod puzzles;
+mod save;
+mod session;
+
+const TICK: f32 = 1.0 / 60.0;
+const SEED: u64 = 42;
+
+fn assets_path() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("static")
+        .join("assets")
+}
+
+fn load_json<T: serde::de::DeserializeOwned>(path: impl AsRef<std::path::Path>) -> T {
+    let path = assets_path().join(path);
+    let file = std::fs::File::open(&path)
+        .unwrap_or_else(|e| panic!("Failed to open {}: {e}", path.display()));
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {e}", path.display()))
+}
+
+pub struct TestWorld {
+    pub world: World,
+    /// Everything the world asked to play since the start
+    pub events: Vec<WorldEvent>,
+}
+
+impl TestWorld {
+    pub fn new() -> Self {
+        Self::with_session(Session {
+            intro_seen: true,
+            ..default()
+        })
+    }
+
+    pub fn with_session(session: Session) -> Self {
+        Self::with_seed_and_session(SEED, session)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_seed_and_session(
+            seed,
+            Session {
+                intro_seen: true,
+                ..default()
+            },
+        )
+    }
+
+    fn with_seed_and_session(seed: u64, session: Session) -> Self {
+        let mut res = Self::generate(session, seed, None);
+        // Let the (already seen) intro finish
+        res.wait(0.2);
+        res
+    }
+
+    /// Resumes the run like [Game::from_save] does
+    pub fn from_save(save: SavedWorld) -> Self {
+        let session = Session {
+            intro_seen: true,
+            restart_from: None,
+            ..save.session.clone()
+        };
+        let mut res = Self::generate(session, SEED, Some(&save.level));
+        res.world.apply_save(save);
+        res
+    }
+
+    fn generate(session: Session, seed: u64, saved: Option<&LevelRandomization>) -> Self {
+        let config: Config = load_json("config.json");
+        let difficulty: Difficulty = load_json("difficulty/1.json");
+        let mut navmesh: NavMesh = load_json("navmesh.json");
+        let level = Level::load_headless(&assets_path().join("level").join("roomMVP.obj")).unwrap();
+        let mut run_rng = StdRng::seed_from_u64(seed);
+        let level = LevelData::generate(
+            &level,
+            &config.puzzles,
+            &config.collision_layers,
+            saved,
+            &mut run_rng,
+        );
+        navmesh.remove_unreachable_from(level.trigger_cubes["GhostSpawn"].center());
+        Self {
+            world: World::new(
+                Rc::new(config),
+                difficulty,
+                level,
+                navmesh,
+                run_rng,
+                session,
+                false,
+            ),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn tick(&mut self, input: &PlayerInput) {
+        self.world.update(TICK, input);
+        self.events.extend(self.world.take_events());
+    }
+
+    pub fn wait(&mut self, time: f32) {
+        for _ in 0..(time / TICK).ceil() as usize {
+            self.tick(&default());
+        }
+    }
+
+    /// Also looks at the events the world has not handed out yet
+    pub fn played(&self, sfx: Sfx) -> bool {
+        self.events
+            .iter()
+            .chain(&self.world.events)
+            .any(|event| matches!(event, WorldEvent::Sfx { sfx: played, .. } if *played == sfx))
+    }
+
+    pub fn interactable_id(&self, name: &str) -> Option<Id> {
+        self.world.interactable_id(name)
+    }
+
+    pub fn has_interactable(&self, name: &str) -> bool {
+        self.interactable_id(name).is_some()
+    }
+
+    pub fn interactable(&self, name: &str) -> &InteractableState {
+        let id = self
+            .interactable_id(name)
+            .unwrap_or_else(|| panic!("No interactable {name:?}"));
+        &self.world.interactables[id]
+    }
+
+    pub fn item_id(&self, name: &str) -> Option<Id> {
+        self.world.items.iter().position(|item| item.name == name)
+    }
+
+    pub fn give(&mut self, item: &str) {
+        self.world.player.inventory.replace_active(item.to_owned());
+    }
+
+    pub fn teleport(&mut self, pos: Vec3<f32>) {
+        self.world.player.pos = pos;
+        self.world.player.vel = Vec3::ZERO;
+        self.world.update_camera(0.0);
+    }
+
+    /// Puts the player on the floor below `pos`
+    pub fn teleport_xy(&mut self, pos: Vec2<f32>) {
+        let navmesh = &self.world.navmesh;
+        let z = navmesh.waypoints[navmesh.closest_waypoint(pos.extend(0.0))].z - 0.45;
+        self.teleport(pos.extend(z));
+    }
+
+    /// Walks along the navmesh, panics if the target can not be reached in time
+    pub fn walk_to(&mut self, target: Vec3<f32>) {
+        let navmesh = self.world.navmesh.clone();
+        let mut time = 0.0;
+        while (self.world.player.pos.xy() - target.xy()).len() > 0.2 {
+            let pos = self.world.player.pos;
+            let next = navmesh.find_path(pos, target).unwrap().points[0];
+            self.world.player.rot_h = (next - pos).xy().arg() - f32::PI / 2.0;
+            self.tick(&PlayerInput {
+                movement: vec2(0.0, 1.0),
+                ..default()
+            });
+            time += TICK;
+            assert!(time < 30.0, "Could not walk from {pos:?} to {target:?}");
+        }
+        self.wait(0.5);
+    }
+
+    pub fn look_at(&mut self, target: Vec3<f32>) {
+        let player = &mut self.world.player;
+        let dv = target - (player.pos + vec3(0.0, 0.0, player.height));
+        player.rot_h = dv.xy().arg() - f32::PI / 2.0;
+        player.rot_v = vec2(dv.xy().len(), dv.z).arg();
+        self.world.update_camera(0.0);
+    }
+
+    pub fn looking_at(&self) -> Option<Object> {
+        self.world.look().target.map(|target| target.object)
+    }
+
+    pub fn interact(&mut self) {
+        self.world.interact();
+        self.events.extend(self.world.take_events());
+    }
+
+    pub fn drop_item(&mut self) {
+        self.world.drop_item();
+        self.events.extend(self.world.take_events());
+    }
+
+    /// Stands somewhere next to `target` from where `object` is in reach
+    fn approach(&mut self, target: Vec3<f32>, object: impl Fn(&World) -> Option<Object>) {
+        let mut candidates = Vec::new();
+        for distance in [0.4, 0.6, 0.8, 1.0] {
+            for i in 0..16 {
+                let angle = i as f32 * 2.0 * f32::PI / 16.0;
+                let xy = target.xy() + vec2(distance, 0.0).rotate(angle);
+                let from = xy.extend(target.z + 0.5);
+                let floor = match self.world.level.bvh.raycast(
+                    Mat4::identity(),
+                    0.0,
+                    geng::CameraRay {
+                        from,
+                        dir: vec3(0.0, 0.0, -1.0),
+                    },
+                    CollisionLayer::Player,
+                ) {
+                    Some(t) => from.z - t,
+                    None => continue,
+                };
+                let pos = xy.extend(floor);
+                if self
+                    .world
+                    .level
+                    .bvh
+                    .vector_from(
+                        Mat4::identity(),
+                        pos + vec3(0.0, 0.0, 0.5),
+                        CollisionLayer::Player,
+                    )
+                    .map_or(false, |v| v.len() < 0.25)
+                {
+                    continue;
+                }
+                candidates.push(pos);
+            }
+        }
+        for pos in candidates {
+            self.teleport(pos);
+            // Let the player get pushed out of whatever they were put into,
+            // without firing any events on the way
+            for _ in 0..5 {
+                self.world.update_movement(TICK, &default());
+            }
+            self.world.player.vel = Vec3::ZERO;
+            self.look_at(target);
+            if object(&self.world).is_some() && self.looking_at() == object(&self.world) {
+                return;
+            }
+        }
+        panic!("Could not find a spot to reach {target:?} from");
+    }
+
+    pub fn approach_interactable(&mut self, name: &str) {
+        let interactable = self.interactable(name);
+        let target = (interactable.matrix()
+            * find_center(&interactable.data.obj.meshes[0].geometry).extend(1.0))
+        .xyz();
+        let name = name.to_owned();
+        self.approach(target, move |world| {
+            world
+                .interactables
+                .iter()
+                .position(|i| i.data.obj.meshes[0].name == name)
+                .map(Object::Interactable)
+        });
+    }
+
+    /// Goes next to a level mesh from [Config::hiding_meshes]
+    pub fn approach_hiding_mesh(&mut self, name: &str) {
+        let mesh = self
+            .world
+            .level
+            .obj
+            .meshes
+            .iter()
+            .find(|mesh| mesh.name == name)
+            .unwrap();
+        let target = find_center(&mesh.geometry);
+        let name = name.to_owned();
+        self.approach(target, move |world| {
+            let look = world.look();
+            match (&look.target, look.position()) {
+                (
+                    Some(LookAtTarget {
+                        object: Object::StaticLevel,
+                        ..
+                    }),
+                    Some(pos),
+                ) if world
+                    .hiding_mesh_at(pos)
+                    .map_or(false, |mesh| mesh.name == name) =>
+                {
+                    Some(Object::StaticLevel)
+                }
+                _ => None,
+            }
+        });
+    }
+
+    /// Goes to the interactable, clicks it and waits for it to finish moving
+    pub fn use_interactable(&mut self, name: &str) {
+        // It might still be moving from being used by something else
+        let mut time = 0.0;
+        while self.interactable(name).locked_reason(&self.world) == Some(LockedReason::Moving) {
+            self.tick(&default());
+            time += TICK;
+            assert!(time < 10.0, "{name:?} does not stop moving");
+        }
+        self.approach_interactable(name);
+        self.interact();
+        self.wait(1.0);
+    }
+
+    /// Goes to an item with the given name and picks it up,
+    /// opening the drawer it is in if needed
+    pub fn pick_up(&mut self, name: &str) {
+        let id = self
+            .item_id(name)
+            .unwrap_or_else(|| panic!("No item {name:?}"));
+        if let Some(parent) = self.world.items[id].parent_interactable.clone() {
+            if !self.interactable(&parent).open {
+                self.use_interactable(&parent);
+            }
+        }
+        let id = self.item_id(name).unwrap();
+        let target =
+            (self.world.item_matrix(&self.world.items[id]) * vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+        let name_owned = name.to_owned();
+        self.approach(target, move |world| {
+            world
+                .items
+                .iter()
+                .position(|item| item.name == name_owned)
+                .map(Object::Item)
+        });
+        self.interact();
+        assert_eq!(self.world.player.inventory.active_item(), Some(name));
+    }
+}
@@ -0,0 +1,233 @@
+use super::*;
+
+fn spawned() -> TestWorld {
+    let mut t = TestWorld::new();
+    t.world.restore_checkpoint("entered_house");
+    t.wait(0.2);
+    t.world.player_inside_house = true;
+    // Never seen and never caught
+    t.world.player.god_mode = true;
+    t
+}
+
+fn wait_for_state(t: &mut TestWorld, state: MonsterState) {
+    let mut time = 0.0;
+    while t.world.monster.state != state {
+        t.wait(TICK);
+        time += TICK;
+        assert!(time < 30.0, "Monster never got to {state:?}");
+    }
+}
+
+#[test]
+fn investigating_a_noise() {
+    let mut t = spawned();
+    assert_eq!(t.world.monster.state, MonsterState::Wander);
+    let noise = t.world.monster.next_scan_pos;
+    t.world.monster_investigate(noise);
+    assert_eq!(t.world.monster.state, MonsterState::Investigate);
+    assert!(!t.world.monster.is_chasing());
+
+    wait_for_state(&mut t, MonsterState::Search);
+    assert!((t.world.monster.pos - noise).xy().len() < 0.5);
+    assert_eq!(t.world.monster.next_scan_pos, noise);
+
+    t.wait(t.world.difficulty.monster_search_time + 0.1);
+    assert_eq!(t.world.monster.state, MonsterState::Wander);
+}
+
+#[test]
+fn losing_track_of_the_player() {
+    let mut t = spawned();
+    let last_seen = t.world.monster.next_scan_pos;
+    t.world.monster_chase(last_seen);
+    assert_eq!(t.world.monster.state, MonsterState::Chase);
+    assert!(t.played(Sfx::GhostScream));
+    assert!(t.world.monster_screaming());
+
+    // Noises don't distract it from the chase
+    t.world.monster_investigate(t.world.monster.pos);
+    assert_eq!(t.world.monster.state, MonsterState::Chase);
+
+    // God mode hides the player
+    t.wait(TICK);
+    assert_eq!(t.world.monster.state, MonsterState::LoseTrack);
+    assert!(t.world.monster.is_chasing());
+
+    wait_for_state(&mut t, MonsterState::Search);
+    assert!(!t.world.monster.is_chasing());
+    assert_eq!(t.world.monster.next_scan_pos, last_seen);
+}
+
+#[test]
+fn path_is_replanned_when_a_door_on_it_changes() {
+    let mut t = spawned();
+    let navmesh = &t.world.navmesh;
+    let outside = navmesh.waypoints[navmesh.closest_waypoint(vec3(-9.0, 3.5, 0.5))];
+    let inside = navmesh.waypoints[navmesh.closest_waypoint(vec3(-9.0, 0.7, 0.5))];
+    t.world.monster.pos = outside;
+    t.world.monster_investigate(inside);
+    t.wait(TICK);
+    let path = t.world.monster.path.clone().unwrap();
+    assert!(!path.partial);
+    let study_gate = |t: &TestWorld| {
+        t.world
+            .monster
+            .path_doors
+            .iter()
+            .find(|(door, _)| door == "D_DoorStudy")
+            .map(|&(_, gate)| gate)
+    };
+    let before = study_gate(&t).expect("Path should go through the study door");
+
+    let door = t.interactable_id("D_DoorStudy").unwrap();
+    let open = t.world.interactables[door].open;
+    t.world.interactables[door].open = !open;
+    t.wait(TICK);
+    let after = study_gate(&t);
+    assert_ne!(after, Some(before));
+    assert_eq!(after, Some(t.world.door_gate("D_DoorStudy")));
+}
+
+#[test]
+fn locked_doors_are_gated() {
+    let mut t = spawned();
+    assert_eq!(t.world.door_gate("D_DoorStorage"), DoorGate::Locked);
+    assert_eq!(t.world.door_gate("DL_S_StudyCloset"), DoorGate::Locked);
+    assert_eq!(t.world.door_gate("I_LibraryMovingCloset"), DoorGate::Locked);
+
+    let study = t.interactable_id("D_DoorStudy").unwrap();
+    t.world.interactables[study].open = false;
+    assert_eq!(
+        t.world.door_gate("D_DoorStudy"),
+        DoorGate::Openable {
+            cost: t.world.difficulty.monster_door_open_time * t.world.difficulty.monster_walk_speed
+        }
+    );
+    t.world.interactables[study].open = true;
+    assert_eq!(t.world.door_gate("D_DoorStudy"), DoorGate::Open);
+
+    // The library closet only lets through once it slid all the way
+    for i in &mut t.world.interactables {
+        if i.name().contains("LibraryMovingCloset") {
+            i.extra_hacky_library_moving_closet_progress = 1.0;
+        }
+    }
+    assert_eq!(t.world.door_gate("I_LibraryMovingCloset"), DoorGate::Open);
+}
+
+#[test]
+fn rooms_are_neighbours_both_ways() {
+    let t = spawned();
+    let neighbours = &t.world.navmesh.room_neighbours;
+    assert!(!neighbours["Kitchen"].is_empty());
+    for (room, others) in neighbours {
+        for other in others {
+            assert!(neighbours[other].contains(room), "{room} -> {other}");
+        }
+    }
+}
+
+#[test]
+fn search_spreads_to_neighbouring_rooms() {
+    let mut t = spawned();
+    let center = t.world.level.room_data["Kitchen"].center();
+    let center = t.world.navmesh.waypoints[t.world.navmesh.closest_waypoint(center)];
+    t.world.monster_search(center);
+    assert_eq!(t.world.monster.state, MonsterState::Search);
+    assert_eq!(t.world.monster.search_room.as_deref(), Some("Kitchen"));
+
+    let room_of = |t: &TestWorld, pos| t.world.room_near(pos).map(|room| room.to_owned());
+    // The last spot of every round is just a random point nearby
+    let spots = t.world.monster.search_spots.clone();
+    for &spot in &spots[..spots.len() - 1] {
+        assert_eq!(room_of(&t, spot).as_deref(), Some("Kitchen"));
+    }
+    let neighbours = t.world.navmesh.rooms_at_depth("Kitchen", 1);
+    let spots = t.world.search_spots(1);
+    for &spot in &spots[..spots.len() - 1] {
+        assert!(neighbours.contains(&room_of(&t, spot).unwrap()));
+    }
+    assert!(
+        t.world
+            .hiding_spots()
+            .iter()
+            .any(|&spot| room_of(&t, spot).map_or(false, |room| neighbours.contains(&room))),
+        "Nowhere to check next to the kitchen"
+    );
+}
+
+#[test]
+fn hiding_in_a_closet() {
+    let mut t = TestWorld::new();
+    let closet = "DL_ClosetDoor_S_Closet.001";
+    t.approach_interactable(closet);
+    let outside = t.world.player.pos;
+    t.world.toggle_hiding();
+    let hiding = t.world.player.hiding.clone().expect("Not hiding");
+    assert_eq!(hiding.spot, closet);
+    assert!(!hiding.seen);
+    let inside = t.world.player.pos;
+    assert!(inside.x < -13.73 && inside.x > -14.56, "{inside:?}");
+
+    // No walking out or turning around
+    t.world.player.rot_h += f32::PI;
+    t.tick(&PlayerInput {
+        movement: vec2(0.0, 1.0),
+        ..default()
+    });
+    assert_eq!(t.world.player.pos, inside);
+    assert!(normalize_angle(t.world.player.rot_h - hiding.rot_h).abs() <= hiding.peek_angle + EPS);
+
+    t.world.monster.pos = outside;
+    t.world.monster.dir = (inside - outside).normalize_or_zero();
+    assert!(!t.world.monster_sees_player());
+
+    t.interact();
+    assert!(t.world.player.hiding.is_none());
+    assert_eq!(t.world.player.pos, outside);
+}
+
+#[test]
+fn hiding_under_the_bed() {
+    let mut t = TestWorld::new();
+    t.approach_hiding_mesh("S_Bed");
+    let outside = t.world.player.pos;
+    t.world.toggle_hiding();
+    let hiding = t.world.player.hiding.clone().expect("Not hiding");
+    assert_eq!(hiding.spot, "S_Bed");
+    assert_eq!(t.world.player.height, 0.2);
+    // Under the mattress
+    let bed = t
+        .world
+        .hiding_mesh_at(t.world.player.pos)
+        .expect("Not under the bed");
+    let bottom = bed
+        .geometry
+        .iter()
+        .map(|v| v.a_v.z)
+        .fold(f32::INFINITY, f32::min);
+    assert!(t.world.camera.pos.z < bottom, "{:?}", t.world.camera.pos);
+
+    t.world.monster.pos = outside;
+    t.world.monster.dir = (t.world.player.pos - outside).normalize_or_zero();
+    assert!(!t.world.monster_sees_player());
+
+    t.world.toggle_hiding();
+    assert!(t.world.player.hiding.is_none());
+    assert_eq!(t.world.player.pos, outside);
+    assert_eq!(t.world.player.height, hiding.exit_height);
+}
+
+#[test]
+fn monster_finds_player_it_saw_hiding() {
+    let mut t = spawned();
+    t.approach_interactable("DL_ClosetDoor_S_Closet.001");
+    let outside = t.world.player.pos;
+    t.world.toggle_hiding();
+    t.world.player.hiding.as_mut().unwrap().seen = true;
+    t.world.monster.pos = outside;
+    t.tick(&default());
+    assert!(t.world.player.hiding.is_none());
+    assert!(t.world.monster.is_chasing());
+}
@@ -0,0 +1,168 @@
+use super::*;
+
+type Triangle = [Vec3<f32>; 3];
+
+fn quad(corners: [Vec3<f32>; 4]) -> [Triangle; 2] {
+    [
+        [corners[0], corners[1], corners[2]],
+        [corners[0], corners[2], corners[3]],
+    ]
+}
+
+/// Floor at height `z` from `x_min` to `x_max`
+fn floor(z: f32, x_min: f32, x_max: f32) -> [Triangle; 2] {
+    quad([
+        vec3(x_min, -5.0, z),
+        vec3(x_max, -5.0, z),
+        vec3(x_max, 5.0, z),
+        vec3(x_min, 5.0, z),
+    ])
+}
+
+/// Wall across the x axis, going up from the ground
+fn wall(x: f32, height: f32) -> [Triangle; 2] {
+    quad([
+        vec3(x, -5.0, 0.0),
+        vec3(x, 5.0, 0.0),
+        vec3(x, 5.0, height),
+        vec3(x, -5.0, height),
+    ])
+}
+
+/// Ledge of `height` at x = 1
+fn ledge(height: f32) -> Vec<Triangle> {
+    [
+        floor(0.0, -5.0, 1.0),
+        wall(1.0, height),
+        floor(height, 1.0, 5.0),
+    ]
+    .concat()
+}
+
+/// Walks along the x axis from the origin, returns where the player ends up
+fn walk(triangles: &[Triangle], speed: f32, time: f32) -> (Vec3<f32>, bool) {
+    let mut pos = vec3(0.0, 0.0, PLAYER_RADIUS);
+    let mut vel = Vec3::ZERO;
+    let mut on_ground = false;
+    for _ in 0..(time / TICK) as usize {
+        vel.x = speed;
+        vel.z -= 15.0 * TICK;
+        on_ground = move_capsule(triangles, &mut pos, &mut vel, 1.0, TICK, on_ground);
+    }
+    (pos, on_ground)
+}
+
+#[test]
+fn capsule_contacts() {
+    let capsule = |z: f32| Capsule {
+        a: vec3(0.0, -2.0, z),
+        b: vec3(0.0, -2.0, z + 1.0),
+        radius: PLAYER_RADIUS,
+    };
+    let [tri, _] = floor(0.0, -5.0, 5.0);
+    let contact = capsule(0.2).contact(tri).unwrap();
+    assert!((contact.normal - vec3(0.0, 0.0, 1.0)).len() < 1e-5);
+    assert!((contact.depth - 0.05).abs() < 1e-5);
+    assert_eq!(capsule(0.3).contact(tri), None);
+
+    // Sunk right through, but most of it is still above
+    let contact = capsule(-0.1).contact(tri).unwrap();
+    assert!((contact.normal - vec3(0.0, 0.0, 1.0)).len() < 1e-5);
+    assert!((contact.depth - 0.35).abs() < 1e-5);
+
+    let [tri, _] = wall(0.2, 2.0);
+    let contact = capsule(0.5).contact(tri).unwrap();
+    assert!((contact.normal - vec3(-1.0, 0.0, 0.0)).len() < 1e-5);
+    assert!((contact.depth - 0.05).abs() < 1e-5);
+}
+
+#[test]
+fn player_walks_up_stairs_but_not_walls() {
+    let (pos, on_ground) = walk(&ledge(0.2), 3.0, 1.0);
+    assert!(pos.x > 2.0, "{pos:?}");
+    assert!((pos.z - 0.2 - PLAYER_RADIUS).abs() < 1e-3, "{pos:?}");
+    assert!(on_ground);
+
+    let (pos, on_ground) = walk(&ledge(1.0), 3.0, 1.0);
+    assert!((pos.x - (1.0 - PLAYER_RADIUS)).abs() < 1e-3, "{pos:?}");
+    assert!((pos.z - PLAYER_RADIUS).abs() < 1e-3, "{pos:?}");
+    assert!(on_ground);
+}
+
+#[test]
+fn player_slides_down_steep_slopes() {
+    let slope = |angle: f32| -> Vec<Triangle> {
+        let top = vec3(1.0 + 4.0 * angle.cos(), 0.0, 4.0 * angle.sin());
+        [
+            floor(0.0, -5.0, 1.0),
+            quad([
+                vec3(1.0, -5.0, 0.0),
+                vec3(top.x, -5.0, top.z),
+                vec3(top.x, 5.0, top.z),
+                vec3(1.0, 5.0, 0.0),
+            ]),
+        ]
+        .concat()
+    };
+    let (pos, _) = walk(&slope(20f32.to_radians()), 3.0, 1.0);
+    assert!(pos.x > 2.0 && pos.z > 0.5, "{pos:?}");
+    let (pos, _) = walk(&slope(60f32.to_radians()), 3.0, 2.0);
+    assert!(pos.x < 1.0 && pos.z < 0.3, "{pos:?}");
+}
+
+#[test]
+fn fast_movement_does_not_go_through_walls() {
+    let triangles = [floor(0.0, -5.0, 5.0), wall(1.0, 2.0)].concat();
+    let mut pos = vec3(0.0, 0.0, PLAYER_RADIUS);
+    let mut vel = vec3(40.0, 0.0, 0.0);
+    move_capsule(&triangles, &mut pos, &mut vel, 1.0, 1.0 / 30.0, true);
+    assert!(pos.x < 1.0, "{pos:?}");
+
+    // Falling from high up lands on the floor
+    let mut pos = vec3(0.0, 0.0, 20.0);
+    let mut vel = vec3(0.0, 0.0, -30.0);
+    let on_ground = move_capsule(&triangles, &mut pos, &mut vel, 1.0, 1.0, false);
+    assert!(on_ground);
+    assert!((pos.z - PLAYER_RADIUS).abs() < 1e-3, "{pos:?}");
+}
+
+#[test]
+fn no_standing_up_under_a_low_table() {
+    let triangles = [floor(0.0, -5.0, 5.0), floor(1.1, -5.0, 5.0)].concat();
+    let pos = vec3(0.0, -2.0, PLAYER_RADIUS);
+    assert!(!is_headroom_blocked(&triangles, pos, 0.5, 0.55));
+    assert!(is_headroom_blocked(&triangles, pos, 0.5, 0.7));
+    assert!(!is_headroom_blocked(&triangles[..2], pos, 0.5, 1.0));
+}
+
+#[test]
+fn god_mode_does_not_fly_through_walls() {
+    let mut t = TestWorld::new();
+    t.world.toggle_god_mode();
+    t.teleport_xy(t.world.area("Storage").unwrap().center().xy());
+    t.world.player.rot_h = 0.0;
+    t.world.update_camera(0.0);
+    let start = t.world.player.pos + vec3(0.0, 0.0, 0.5);
+    let wall = t
+        .world
+        .level
+        .bvh
+        .raycast(
+            Mat4::identity(),
+            0.0,
+            geng::CameraRay {
+                from: start,
+                dir: vec3(0.0, 1.0, 0.0),
+            },
+            CollisionLayer::Player,
+        )
+        .expect("No wall ahead");
+    for _ in 0..60 {
+        t.tick(&PlayerInput {
+            movement: vec2(0.0, 1.0),
+            sprint: true,
+            ..default()
+        });
+    }
+    assert!(t.world.player.pos.y + PLAYER_RADIUS < start.y + wall + EPS);
+}
@@ -0,0 +1,365 @@
+use super::*;
+
+fn square(min: Vec2<f32>, max: Vec2<f32>, z: f32) -> NavPolygon {
+    NavPolygon {
+        vertices: vec![
+            vec3(min.x, min.y, z),
+            vec3(max.x, min.y, z),
+            vec3(max.x, max.y, z),
+            vec3(min.x, max.y, z),
+        ],
+        portals: Vec::new(),
+    }
+}
+
+fn connect(polygons: &mut [NavPolygon], v: usize, u: usize, a: Vec3<f32>, b: Vec3<f32>) {
+    polygons[v].portals.push(NavPortal { to: u, a, b });
+    polygons[u].portals.push(NavPortal { to: v, a, b });
+}
+
+fn vertex(p: Vec3<f32>) -> Vertex {
+    Vertex {
+        a_b: 0.0,
+        a_v: p,
+        a_bv: Vec3::ZERO,
+        a_vt: Vec2::ZERO,
+        a_vn: Vec3::ZERO,
+    }
+}
+
+/// Two triangles, counter-clockwise when looking along the normal
+fn quad(name: &str, corners: [Vec3<f32>; 4]) -> ObjMesh {
+    ObjMesh {
+        name: name.to_owned(),
+        geometry: Geometry::new(
+            [0, 1, 2, 0, 2, 3]
+                .into_iter()
+                .map(|i| vertex(corners[i]))
+                .collect(),
+        ),
+        material: Material::untextured("test"),
+    }
+}
+
+/// L shape going right and then up, and a polygon off on its own
+fn l_shape() -> NavMesh {
+    let mut polygons = vec![
+        square(vec2(0.0, 0.0), vec2(1.0, 1.0), 0.0),
+        square(vec2(1.0, 0.0), vec2(2.0, 1.0), 0.0),
+        square(vec2(1.0, 1.0), vec2(2.0, 2.0), 0.0),
+        square(vec2(5.0, 5.0), vec2(6.0, 6.0), 0.0),
+    ];
+    connect(
+        &mut polygons,
+        0,
+        1,
+        vec3(1.0, 0.0, 0.0),
+        vec3(1.0, 1.0, 0.0),
+    );
+    connect(
+        &mut polygons,
+        1,
+        2,
+        vec3(1.0, 1.0, 0.0),
+        vec3(2.0, 1.0, 0.0),
+    );
+    NavMesh::from_polygons(polygons)
+}
+
+#[test]
+fn path_is_pulled_tight_around_a_corner() {
+    let navmesh = l_shape();
+    let from = vec3(0.5, 0.5, 0.0);
+    let to = vec3(1.5, 1.5, 0.0);
+    let path = navmesh.find_path(from, to).unwrap();
+    assert_eq!(path.nodes, vec![0, 1, 2]);
+    assert_eq!(path.points, vec![vec3(1.0, 1.0, 0.0), to]);
+    assert!((path.length - 2.0 * 0.5f32.hypot(0.5)).abs() < 1e-4);
+    assert!(!path.partial);
+
+    // Nothing to go around when staying in the bottom row
+    let to = vec3(1.8, 0.2, 0.0);
+    assert_eq!(navmesh.find_path(from, to).unwrap().points, vec![to]);
+}
+
+#[test]
+fn trivial_paths() {
+    let navmesh = l_shape();
+    let from = vec3(0.5, 0.5, 0.0);
+    let path = navmesh.find_path(from, from).unwrap();
+    assert_eq!(path.points, vec![from]);
+    assert_eq!(path.nodes, vec![0]);
+    assert_eq!(path.length, 0.0);
+
+    let empty = NavMesh::from_polygons(Vec::new());
+    assert_eq!(empty.find_path(from, from), Err(PathError::EmptyNavMesh));
+}
+
+#[test]
+fn unreachable_goal() {
+    let navmesh = l_shape();
+    let from = vec3(0.5, 0.5, 0.0);
+    let to = vec3(5.5, 5.5, 0.0);
+    assert_eq!(navmesh.find_path(from, to), Err(PathError::Unreachable));
+
+    // Gets to the corner of the L closest to the goal instead
+    let path = navmesh.find_partial_path(from, to).unwrap();
+    assert!(path.partial);
+    assert_eq!(path.nodes, vec![0, 1, 2]);
+    assert_eq!(path.points.last(), Some(&vec3(1.5, 1.5, 0.0)));
+}
+
+#[test]
+fn routes_are_cached() {
+    let navmesh = l_shape();
+    navmesh
+        .find_path(vec3(0.5, 0.5, 0.0), vec3(1.5, 1.5, 0.0))
+        .unwrap();
+    navmesh
+        .find_path(vec3(0.2, 0.2, 0.0), vec3(1.8, 1.8, 0.0))
+        .unwrap();
+    // Both paths go between the same polygons
+    assert_eq!(navmesh.routes.borrow().len(), 1);
+    assert_eq!(navmesh.routes.borrow()[&(0, 2)].nodes, vec![0, 1, 2]);
+
+    navmesh.clear_routes();
+    assert!(navmesh.routes.borrow().is_empty());
+}
+
+#[test]
+fn walking_a_path() {
+    let navmesh = l_shape();
+    let mut path = navmesh
+        .find_path(vec3(0.5, 0.5, 0.0), vec3(1.5, 1.5, 0.0))
+        .unwrap();
+    path.advance(vec3(0.9, 0.9, 0.0), 0.1);
+    assert_eq!(path.next(), Some(vec3(1.0, 1.0, 0.0)));
+    path.advance(vec3(1.0, 0.95, 0.0), 0.1);
+    assert_eq!(path.next(), Some(vec3(1.5, 1.5, 0.0)));
+    path.advance(vec3(1.5, 1.5, 0.0), 0.1);
+    assert_eq!(path.next(), None);
+}
+
+#[test]
+fn paths_go_around_closed_doors() {
+    // 2 by 2 grid, with a door between the bottom two squares
+    let mut polygons = vec![
+        square(vec2(0.0, 0.0), vec2(1.0, 1.0), 0.0),
+        square(vec2(1.0, 0.0), vec2(2.0, 1.0), 0.0),
+        square(vec2(0.0, 1.0), vec2(1.0, 2.0), 0.0),
+        square(vec2(1.0, 1.0), vec2(2.0, 2.0), 0.0),
+    ];
+    connect(
+        &mut polygons,
+        0,
+        1,
+        vec3(1.0, 0.0, 0.0),
+        vec3(1.0, 1.0, 0.0),
+    );
+    connect(
+        &mut polygons,
+        0,
+        2,
+        vec3(0.0, 1.0, 0.0),
+        vec3(1.0, 1.0, 0.0),
+    );
+    connect(
+        &mut polygons,
+        1,
+        3,
+        vec3(1.0, 1.0, 0.0),
+        vec3(2.0, 1.0, 0.0),
+    );
+    connect(
+        &mut polygons,
+        2,
+        3,
+        vec3(1.0, 1.0, 0.0),
+        vec3(1.0, 2.0, 0.0),
+    );
+    let mut navmesh = NavMesh::from_polygons(polygons);
+    navmesh.doors.insert((0, 1), vec!["D_Test".to_owned()]);
+
+    let from = vec3(0.5, 0.5, 0.0);
+    let to = vec3(1.8, 0.5, 0.0);
+    let path = navmesh.find_path(from, to).unwrap();
+    assert_eq!(path.nodes, vec![0, 1]);
+
+    // Opening the door takes longer than walking around
+    navmesh.set_gate("D_Test", DoorGate::Openable { cost: 5.0 });
+    assert!(navmesh.routes.borrow().is_empty());
+    let path = navmesh.find_path(from, to).unwrap();
+    assert_eq!(path.nodes, vec![0, 2, 3, 1]);
+
+    navmesh.set_gate("D_Test", DoorGate::Openable { cost: 0.5 });
+    assert_eq!(navmesh.find_path(from, to).unwrap().nodes, vec![0, 1]);
+
+    navmesh.set_gate("D_Test", DoorGate::Locked);
+    assert_eq!(navmesh.find_path(from, to).unwrap().nodes, vec![0, 2, 3, 1]);
+    assert_eq!(navmesh.edge_cost(0, 1), None);
+}
+
+#[test]
+fn stacked_floors_are_told_apart() {
+    let polygons = vec![
+        square(vec2(0.0, 0.0), vec2(2.0, 2.0), 0.45),
+        square(vec2(0.0, 0.0), vec2(2.0, 2.0), 3.45),
+    ];
+    let navmesh = NavMesh::from_polygons(polygons);
+    assert_eq!(navmesh.polygon_at(vec3(1.0, 1.0, 0.5)), Some(0));
+    assert_eq!(navmesh.closest_waypoint(vec3(1.0, 1.0, 3.0)), 1);
+    assert_eq!(navmesh.polygon_at(vec3(3.0, 1.0, 0.5)), None);
+}
+
+#[test]
+fn built_navmesh_goes_through_the_gap_in_a_wall() {
+    // Wall along x = 0 with a gap at the top
+    let obj = Obj {
+        meshes: vec![
+            quad(
+                "S_Floor",
+                [
+                    vec3(-3.0, -3.0, 0.0),
+                    vec3(3.0, -3.0, 0.0),
+                    vec3(3.0, 3.0, 0.0),
+                    vec3(-3.0, 3.0, 0.0),
+                ],
+            ),
+            quad(
+                "S_Wall",
+                [
+                    vec3(0.0, -3.0, 0.0),
+                    vec3(0.0, 1.0, 0.0),
+                    vec3(0.0, 1.0, 2.0),
+                    vec3(0.0, -3.0, 2.0),
+                ],
+            ),
+        ],
+    };
+    let navmesh = NavMesh::build(
+        &obj,
+        &[],
+        &[],
+        &HashMap::new(),
+        &HashMap::new(),
+        &CollisionRules::default(),
+    );
+    // A few big rectangles on both sides of the wall and over the gap, not a grid
+    assert!(
+        !navmesh.polygons.is_empty() && navmesh.polygons.len() <= 4,
+        "{} polygons",
+        navmesh.polygons.len()
+    );
+
+    let from = vec3(-2.0, -2.0, 0.0);
+    let to = vec3(2.0, -2.0, 0.0);
+    let s = navmesh.closest_waypoint(from);
+    let t = navmesh.closest_waypoint(to);
+    assert!((navmesh.waypoints[s].z - HEIGHT_ABOVE_FLOOR).abs() < 1e-3);
+    assert!(navmesh.route(s, t).reached);
+
+    // Has to walk up to the end of the wall first and around it
+    let path = navmesh
+        .find_path(from.xy().extend(HEIGHT_ABOVE_FLOOR), to)
+        .unwrap()
+        .points;
+    assert!(path[0].x < 0.0 && path[0].y > 1.0 - EPS, "{path:?}");
+    assert!(path.iter().any(|p| p.y > 1.0 + EPS), "{path:?}");
+}
+
+#[test]
+fn built_polygons_stay_inside_rooms() {
+    let obj = Obj {
+        meshes: vec![quad(
+            "S_Floor",
+            [
+                vec3(-3.0, -1.0, 0.0),
+                vec3(3.0, -1.0, 0.0),
+                vec3(3.0, 1.0, 0.0),
+                vec3(-3.0, 1.0, 0.0),
+            ],
+        )],
+    };
+    let rooms = HashMap::from_iter([
+        (
+            "West".to_owned(),
+            cube(vec3(-3.0, -1.0, 0.0), vec3(0.0, 1.0, 2.0)),
+        ),
+        (
+            "East".to_owned(),
+            cube(vec3(0.0, -1.0, 0.0), vec3(3.0, 1.0, 2.0)),
+        ),
+    ]);
+    let mut navmesh = NavMesh::build(
+        &obj,
+        &[],
+        &[],
+        &rooms,
+        &HashMap::new(),
+        &CollisionRules::default(),
+    );
+    for polygon in &navmesh.polygons {
+        let xs: Vec<f32> = polygon.vertices.iter().map(|v| v.x).collect();
+        let min = xs.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = xs.iter().copied().fold(-f32::INFINITY, f32::max);
+        assert!(max <= 1e-3 || min >= -1e-3, "{:?}", polygon.vertices);
+    }
+    navmesh.find_rooms(&rooms);
+    assert_eq!(navmesh.room_neighbours["West"], vec!["East".to_owned()]);
+}
+
+fn cube(min: Vec3<f32>, max: Vec3<f32>) -> TriggerCube {
+    TriggerCube {
+        min_x: min.x,
+        min_y: min.y,
+        min_z: min.z,
+        max_x: max.x,
+        max_y: max.y,
+        max_z: max.z,
+    }
+}
+
+#[test]
+fn report_finds_what_the_monster_can_not_get_to() {
+    let navmesh = l_shape();
+    let rooms = HashMap::from_iter([
+        (
+            "Hall".to_owned(),
+            cube(vec3(0.0, 0.0, 0.0), vec3(2.0, 2.0, 2.0)),
+        ),
+        (
+            "Attic".to_owned(),
+            cube(vec3(4.0, 4.0, 0.0), vec3(7.0, 7.0, 2.0)),
+        ),
+        // Only a corner of a polygon, far from its center
+        (
+            "Closet".to_owned(),
+            cube(vec3(1.7, 0.1, 0.0), vec3(1.9, 0.3, 2.0)),
+        ),
+    ]);
+    let triggers = HashMap::from_iter([
+        (
+            "Corner".to_owned(),
+            cube(vec3(1.7, 1.7, 0.0), vec3(1.9, 1.9, 1.0)),
+        ),
+        (
+            "AtticTrigger".to_owned(),
+            cube(vec3(5.4, 5.4, 0.0), vec3(5.6, 5.6, 1.0)),
+        ),
+    ]);
+    let report = navmesh.report(vec3(0.5, 0.5, 0.0), &rooms, &triggers);
+    assert_eq!(report.islands, vec![(vec3(5.5, 5.5, 0.0), 1)]);
+    assert_eq!(report.empty_rooms, vec!["Attic".to_owned()]);
+    assert_eq!(report.unreachable_triggers, vec!["AtticTrigger".to_owned()]);
+    assert!(report.has_problems());
+
+    let report = navmesh.report(vec3(0.5, 0.5, 0.0), &rooms, &HashMap::new());
+    assert!(report.has_problems());
+    let rooms = HashMap::from_iter([(
+        "Hall".to_owned(),
+        cube(vec3(0.0, 0.0, 0.0), vec3(2.0, 2.0, 2.0)),
+    )]);
+    assert!(!navmesh
+        .report(vec3(0.5, 0.5, 0.0), &rooms, &HashMap::new())
+        .has_problems());
+}
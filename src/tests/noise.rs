@@ -0,0 +1,61 @@
+use super::*;
+
+fn waypoint_near(t: &TestWorld, pos: Vec2<f32>) -> Vec3<f32> {
+    let navmesh = &t.world.navmesh;
+    navmesh.waypoints[navmesh.closest_waypoint(pos.extend(0.5))]
+}
+
+#[test]
+fn navmesh_distance_adds_extra_cost() {
+    let navmesh = NavMesh {
+        waypoints: vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(2.0, 0.0, 0.0),
+        ],
+        edges: vec![vec![1], vec![0, 2], vec![1]],
+        polygons: Vec::new(),
+        doors: HashMap::from_iter([((1, 2), vec!["D_Test".to_owned()])]),
+        room_neighbours: HashMap::new(),
+        gates: HashMap::new(),
+        routes: default(),
+    };
+    let from = navmesh.waypoints[0];
+    let to = navmesh.waypoints[2];
+    assert_eq!(navmesh.distance(from, to, 10.0, |_, _| 0.0), Some(2.0));
+    let door_cost = |v, u| navmesh.doors_between(v, u).len() as f32 * 3.0;
+    assert_eq!(navmesh.distance(from, to, 10.0, door_cost), Some(5.0));
+    assert_eq!(navmesh.distance(from, to, 4.0, door_cost), None);
+}
+
+#[test]
+fn closed_doors_muffle_noises() {
+    let mut t = TestWorld::new();
+    let inside = waypoint_near(&t, vec2(-9.0, 0.7));
+    let outside = waypoint_near(&t, vec2(-9.0, 3.5));
+    t.world.monster.pos = outside;
+    let door = t.interactable_id("D_DoorStudy").unwrap();
+    assert!((0..t.world.navmesh.waypoints.len()).any(|v| {
+        t.world.navmesh.edges[v].iter().any(|&u| {
+            t.world
+                .navmesh
+                .doors_between(v, u)
+                .contains(&"D_DoorStudy".to_owned())
+        })
+    }));
+
+    // Just loud enough to be heard through the open door
+    let free = t
+        .world
+        .navmesh
+        .distance(inside, outside, 1e9, |_, _| 0.0)
+        .unwrap();
+    let attenuation = t.world.config.noise.closed_door_attenuation;
+    t.world.difficulty.monster_hearing =
+        (free + attenuation / 2.0) / t.world.config.noise.loudness[&Noise::Door];
+
+    t.world.interactables[door].open = false;
+    assert!(!t.world.monster_hears(Noise::Door, inside));
+    t.world.interactables[door].open = true;
+    assert!(t.world.monster_hears(Noise::Door, inside));
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+fn parse(source: &str) -> Result<Obj, ObjError> {
+    let materials = HashMap::from_iter([("Red".to_owned(), Material::untextured("Red"))]);
+    Obj::parse("test.obj", source, &materials)
+}
+
+#[test]
+fn obj_faces_without_normals_or_uvs() {
+    let obj = parse(
+        "o Quad\nusemtl Red\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0.5 0.5\nf 1 2 3 4\nf -4/-1 -2/1 -1/1\n",
+    )
+    .unwrap();
+    assert_eq!(obj.meshes.len(), 1);
+    let mesh = &obj.meshes[0];
+    assert_eq!(mesh.name, "Quad");
+    assert_eq!(mesh.material.name, "Red");
+    assert_eq!(mesh.geometry.len(), 9);
+    for v in mesh.geometry.iter() {
+        assert_eq!(v.a_vn, vec3(0.0, 0.0, 1.0));
+    }
+    // Negative indices count back from the last one
+    assert_eq!(mesh.geometry[6].a_v, vec3(0.0, 0.0, 0.0));
+    assert_eq!(mesh.geometry[8].a_v, vec3(0.0, 1.0, 0.0));
+    assert_eq!(mesh.geometry[8].a_vt, vec2(0.5, 0.5));
+    assert_eq!(mesh.geometry[0].a_vt, vec2(0.0, 0.0));
+}
+
+#[test]
+fn obj_errors_point_at_the_line() {
+    let error = |source: &str| {
+        let error = parse(source).err().unwrap();
+        assert_eq!(error.file, "test.obj");
+        (error.line, error.reason)
+    };
+    assert_eq!(error("v 0 0 0\nv 1 x 0\n").0, 2);
+    assert_eq!(error("v 0 0\n").0, 1);
+    let (line, reason) = error("v 0 0 0\nv 1 0 0\nf 1 2 3\n");
+    assert_eq!(line, 3);
+    assert!(reason.contains("out of range"), "{reason}");
+    assert_eq!(error("v 0 0 0\nf 1 1\n").0, 2);
+    assert_eq!(error("o A\n\nusemtl Blue\n").0, 3);
+}
+
+#[test]
+fn mtl_colors_and_textures() {
+    let mtls = parse_mtl(
+        "test.mtl",
+        "# comment\nnewmtl Wall\nKa 0.1 0.2 0.3\nKd 0.5 0.5 0.5\nmap_Kd -bm 1 Wall.jpg\n\
+         newmtl Floor\nmap_Kd \"My Floor.png\"\nnewmtl Nothing\nmap_Kd .\n",
+    )
+    .unwrap();
+    assert_eq!(mtls.len(), 3);
+    assert_eq!(mtls[0].ambient_color, Rgba::new(0.1, 0.2, 0.3, 1.0));
+    assert_eq!(mtls[0].diffuse_color, Rgba::new(0.5, 0.5, 0.5, 1.0));
+    assert_eq!(mtls[0].texture.as_deref(), Some("Wall.jpg"));
+    assert_eq!(mtls[0].dark_texture().as_deref(), Some("Wall_Dark.jpg"));
+    assert_eq!(mtls[1].texture.as_deref(), Some("My Floor.png"));
+    assert_eq!(mtls[1].diffuse_color, Rgba::WHITE);
+    assert_eq!(mtls[2].texture, None);
+
+    let error = parse_mtl("test.mtl", "Kd 1 1 1\n").err().unwrap();
+    assert_eq!((error.file.as_str(), error.line), ("test.mtl", 1));
+    let error = parse_mtl("test.mtl", "newmtl A\nKd 1 one 1\n")
+        .err()
+        .unwrap();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.to_string(), "test.mtl:2: \"one\" is not a number");
+}
@@ -0,0 +1,368 @@
+use super::*;
+
+fn storage_button(t: &TestWorld, index: usize) -> Option<(String, u8)> {
+    let prefix = format!("I_StorageButtonIcon{}", index + 1);
+    t.world.interactables.iter().find_map(|i| {
+        let name = &i.data.obj.meshes[0].name;
+        let value = name.strip_prefix(&prefix)?.parse().unwrap();
+        Some((name.clone(), value))
+    })
+}
+
+#[test]
+fn storage_lock() {
+    let mut t = TestWorld::new();
+    let combination = t.world.level.combinations["storage_lock"].clone();
+    for index in 0..4 {
+        while let Some((name, value)) = storage_button(&t, index) {
+            if value == combination[index] {
+                break;
+            }
+            t.use_interactable(&name);
+            assert_eq!(
+                storage_button(&t, index).map(|(_, value)| value),
+                if t.world.is_solved("storage_lock") {
+                    None
+                } else {
+                    Some((value + 1) % 4)
+                },
+            );
+        }
+    }
+    assert!(t.world.is_solved("storage_lock"));
+    assert!(t.played(Sfx::SymbolsPuzzleSolved));
+    assert!(!t
+        .world
+        .interactables
+        .iter()
+        .any(|i| i.data.obj.meshes[0].name.contains("StorageButton")));
+
+    t.use_interactable("D_DoorStorage");
+    assert!(t.interactable("D_DoorStorage").open);
+}
+
+#[test]
+fn study_key_puzzle() {
+    let mut t = TestWorld::new();
+    assert!(!t.has_interactable("I_HintKey"));
+    t.teleport_xy(vec2(-9.0, 3.5));
+    if !t.interactable("D_DoorStudy").open {
+        t.use_interactable("D_DoorStudy");
+    }
+    t.walk_to(vec3(-9.0, 0.7, 0.0));
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::Entered);
+    assert!(!t.interactable("D_DoorStudy").open);
+
+    t.use_interactable("D_DoorStudy");
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::LightOut);
+    assert_eq!(t.world.ambient_light, Rgba::BLACK);
+    assert!(!t.world.player.flashdark.on);
+    assert!(t.world.session.reached("light_out").is_some());
+
+    t.world.toggle_flashdark(false);
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::Ready);
+    assert!(t.has_interactable("I_HintKey"));
+    assert!(t.item_id("TheStudyKeyPuzzleSolution").is_some());
+
+    // A wrong key does not fit
+    let wrong_key = t
+        .world
+        .items
+        .iter()
+        .find(|item| item.name.contains("StudyKey") && item.name != "TheStudyKeyPuzzleSolution")
+        .unwrap()
+        .name
+        .clone();
+    t.give(&wrong_key);
+    t.use_interactable("I_StudyClosetLock");
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::Ready);
+
+    t.pick_up("TheStudyKeyPuzzleSolution");
+    t.use_interactable("I_StudyClosetLock");
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::Finish);
+    assert!(!t.has_interactable("I_StudyClosetLock"));
+    assert!(!t.has_interactable("I_HintKey"));
+    assert!(!t
+        .world
+        .items
+        .iter()
+        .any(|item| item.name.contains("StudyKey")));
+    assert!(!t
+        .world
+        .player
+        .inventory
+        .slots
+        .iter()
+        .flatten()
+        .any(|item| item.contains("StudyKey")));
+    assert_eq!(t.world.player.inventory.active_item(), None);
+}
+
+#[test]
+fn study_light_out_is_remembered() {
+    let mut t = TestWorld::with_session(Session {
+        intro_seen: true,
+        checkpoints: vec![ReachedCheckpoint {
+            checkpoint: "light_out".to_owned(),
+            passed: Vec::new(),
+            pos: vec3(-9.0, 0.7, 0.0),
+            rot_h: 0.0,
+        }],
+        ..default()
+    });
+    t.teleport_xy(vec2(-9.0, 0.7));
+    t.wait(0.1);
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::LightOut);
+}
+
+#[test]
+fn fuse_and_tv_cutscene() {
+    let mut t = TestWorld::new();
+    t.approach_interactable("I_FusePlaceholder");
+    t.wait(0.1);
+    assert!(t.world.fuse_spawned);
+    assert!(t.events.contains(&WorldEvent::StartTrack(Track::Swing)));
+
+    // Nothing happens without the fuse
+    t.interact();
+    assert!(!t.world.fuse_placed);
+
+    t.pick_up("Fuse");
+    t.use_interactable("I_FusePlaceholder");
+    assert!(t.world.fuse_placed);
+    assert!(t.has_interactable("I_FusePlaced"));
+    assert!(!t.has_interactable("I_FusePlaceholder"));
+    assert_eq!(t.world.player.inventory.active_item(), None);
+    assert!(t.events.contains(&WorldEvent::StartTrack(Track::TvNoise)));
+    // Heard everywhere
+    assert!(t.events.iter().any(|event| matches!(
+        event,
+        WorldEvent::Sfx { sfx: Sfx::Named(name), pos: None, .. } if name == "fusePlaced.mp3"
+    )));
+
+    assert!(t.world.lock_controls);
+    let mut time = 0.0;
+    while !t.world.monster_spawned {
+        t.wait(TICK);
+        time += TICK;
+        assert!(time < 10.0, "Cutscene did not end");
+    }
+    assert!(!t.world.lock_controls);
+    t.wait(TICK);
+    assert!(t.world.session.reached("entered_house").is_some());
+}
+
+#[test]
+fn singing_girl_and_library() {
+    let mut t = TestWorld::new();
+    t.give("Doll");
+    t.use_interactable("B_SingingGirl");
+    assert_eq!(t.world.player.inventory.active_item(), Some("Book5"));
+    assert!(t.has_interactable("B_SingingGirlDone"));
+    assert!(!t.has_interactable("B_SingingGirl"));
+    assert!(t
+        .events
+        .contains(&WorldEvent::StopTrack(Track::CreepySinging)));
+
+    // Wrong book
+    t.give("Book2");
+    t.use_interactable("I_BookshelfLibrary1");
+    assert!(!t.interactable("I_BookshelfLibrary1").open);
+
+    let shelves = [
+        ("I_BookshelfLibrary1", "Book1"),
+        ("I_BookshelfLibrary2", "Book2"),
+        ("I_BookshelfLibrary3", "Book5"),
+        ("I_BookshelfLibrary4", "Book4"),
+        ("I_BookshelfLibrary5", "Book3"),
+    ];
+    for (placed, (shelf, book)) in shelves.into_iter().enumerate() {
+        t.give(book);
+        t.use_interactable(shelf);
+        assert!(t.interactable(shelf).open);
+        assert_eq!(t.world.player.inventory.active_item(), None);
+        let expected = if placed + 1 == shelves.len() {
+            1.0
+        } else {
+            (placed + 1) as f32 / 10.0
+        };
+        assert_eq!(
+            t.interactable("I_LibraryMovingCloset")
+                .extra_hacky_library_moving_closet_progress,
+            expected,
+        );
+    }
+}
+
+#[test]
+fn loose_planks_need_crowbar() {
+    let mut t = TestWorld::new();
+    let plank = t.world.level.planks[0].clone();
+    t.use_interactable(&plank);
+    assert!(t.has_interactable(&plank));
+
+    t.give("Crowbar");
+    t.use_interactable(&plank);
+    assert!(!t.has_interactable(&plank));
+    assert!(t.played(Sfx::Named("plankRemoval.mp3".to_owned())));
+    assert_eq!(t.world.player.inventory.active_item(), Some("Crowbar"));
+}
+
+#[test]
+fn pick_up_and_drop() {
+    let mut t = TestWorld::new();
+    t.pick_up("Book1");
+    assert!(t.item_id("Book1").is_none());
+
+    t.drop_item();
+    assert_eq!(t.world.player.inventory.active_item(), None);
+    assert!(resting_gap(&t, "Book1").abs() < 0.01);
+
+    // Picking something up while holding an item puts it into a free slot
+    t.give("Doll");
+    t.pick_up("Book1");
+    assert_eq!(t.world.player.inventory.slots[0].as_deref(), Some("Doll"));
+
+    // Once every slot is taken, the active item is swapped out
+    t.drop_item();
+    for slot in &mut t.world.player.inventory.slots {
+        *slot = Some("Doll".to_owned());
+    }
+    t.pick_up("Book1");
+    assert!(t
+        .world
+        .items
+        .iter()
+        .any(|item| item.name == "Doll" && item.dropped_mesh.as_deref() == Some("Doll_Floor")));
+}
+
+/// Distance between the bottom of the item and the surface below it
+fn resting_gap(t: &TestWorld, name: &str) -> f32 {
+    let item = &t.world.items[t.item_id(name).unwrap()];
+    let mesh = &t.world.item_spawn(item).mesh;
+    let bottom = mesh
+        .geometry
+        .iter()
+        .map(|v| r32(v.a_v.z))
+        .min()
+        .unwrap()
+        .raw();
+    let center = (t.world.item_matrix(item) * vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+    let (distance, _) = t
+        .world
+        .cast_ray(geng::CameraRay {
+            from: center,
+            dir: vec3(0.0, 0.0, -1.0),
+        })
+        .expect("Nothing below the item");
+    distance + bottom
+}
+
+#[test]
+fn dropping_into_a_drawer() {
+    let mut t = TestWorld::new();
+    let (name, parent) = t
+        .world
+        .items
+        .iter()
+        .find_map(|item| {
+            let parent = item.parent_interactable.as_ref()?;
+            let interactable = t.interactable(parent);
+            matches!(interactable.data.typ, InteractableType::Drawer { .. })
+                .then(|| (item.name.clone(), parent.clone()))
+        })
+        .expect("No item spawned in a drawer");
+    t.pick_up(&name);
+    assert!(t.interactable(&parent).open);
+
+    t.drop_item();
+    let id = t.item_id(&name).unwrap();
+    assert_eq!(
+        t.world.items[id].parent_interactable.as_ref(),
+        Some(&parent)
+    );
+    assert!(resting_gap(&t, &name).abs() < 0.01);
+
+    // The item moves with the drawer
+    let open_pos = (t.world.item_matrix(&t.world.items[id]) * vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+    t.use_interactable(&parent);
+    assert!(!t.interactable(&parent).open);
+    let id = t.item_id(&name).unwrap();
+    let closed_pos = (t.world.item_matrix(&t.world.items[id]) * vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+    assert_ne!(closed_pos, open_pos);
+
+    // and stays where it was when the drawer goes away
+    t.world.apply_effect(&Effect::RemoveInteractables {
+        containing: parent.clone(),
+    });
+    assert!(!t.has_interactable(&parent));
+    let id = t.item_id(&name).unwrap();
+    assert_eq!(t.world.items[id].parent_interactable, None);
+    let removed_pos = (t.world.item_matrix(&t.world.items[id]) * vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+    assert!((removed_pos - closed_pos).len() < EPS);
+}
+
+#[test]
+fn inventory_selection() {
+    let mut inventory = Inventory::new(3);
+    inventory.add("Book1".to_owned());
+    inventory.add("Book2".to_owned());
+    assert_eq!(inventory.active_item(), Some("Book2"));
+    inventory.scroll(-1);
+    assert_eq!(inventory.active_item(), Some("Book1"));
+    inventory.scroll(-1);
+    assert_eq!(inventory.active, 2);
+    assert_eq!(inventory.active_item(), None);
+
+    inventory.toggle_inspect();
+    assert!(!inventory.inspecting);
+    inventory.select(0);
+    inventory.toggle_inspect();
+    assert!(inventory.inspecting);
+    inventory.take_active();
+    assert!(!inventory.inspecting);
+}
+
+#[test]
+fn candles_ending() {
+    let mut t = TestWorld::new();
+    let candles: Vec<String> = t
+        .world
+        .interactables
+        .iter()
+        .map(|i| i.data.obj.meshes[0].name.clone())
+        .filter(|name| name.starts_with("B_Candle"))
+        .collect();
+    assert!(!candles.is_empty());
+    for candle in &candles {
+        assert!(!t.world.ending);
+        t.use_interactable(candle);
+        assert!(t.interactable(candle).open);
+    }
+    assert!(t.world.ending);
+    assert!(t.world.lock_controls);
+    assert!(t.played(Sfx::Ending));
+    assert!(t.events.contains(&WorldEvent::StopSounds));
+}
+
+#[test]
+fn player_and_monster_share_lock_rules() {
+    let mut t = TestWorld::new();
+    let storage = t.interactable("D_DoorStorage");
+    assert_eq!(storage.locked_reason(&t.world), Some(LockedReason::Puzzle));
+    assert!(!storage.can_monster_open(&t.world));
+
+    let study = t.interactable("D_DoorStudy");
+    assert!(study.can_player_use(&t.world));
+    assert!(!study.can_monster_open(&t.world));
+    t.world.player_inside_house = true;
+    assert!(t.interactable("D_DoorStudy").can_monster_open(&t.world));
+    t.world.key_puzzle_state = KeyPuzzleState::Entered;
+    assert!(t.interactable("D_DoorStudy").can_player_use(&t.world));
+    assert!(!t.interactable("D_DoorStudy").can_monster_open(&t.world));
+    t.world.key_puzzle_state = KeyPuzzleState::Ready;
+    assert_eq!(
+        t.interactable("D_DoorStudy").locked_reason(&t.world),
+        Some(LockedReason::KeyPuzzle),
+    );
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+fn json(value: &impl Serialize) -> serde_json::Value {
+    serde_json::to_value(value).unwrap()
+}
+
+#[test]
+fn resumed_run_continues_where_it_was_saved() {
+    let mut t = TestWorld::new();
+    t.approach_interactable("I_FusePlaceholder");
+    t.wait(0.1);
+    t.pick_up("Fuse");
+    t.use_interactable("I_FusePlaceholder");
+    let mut time = 0.0;
+    while !t.world.monster_spawned {
+        t.wait(TICK);
+        time += TICK;
+        assert!(time < 10.0, "Cutscene did not end");
+    }
+    t.pick_up("Crowbar");
+    let plank = t.world.level.planks[0].clone();
+    t.use_interactable(&plank);
+    t.wait(2.0);
+
+    let save = t.world.save_data();
+    let mut resumed = TestWorld::from_save(save.clone());
+    assert_eq!(resumed.world.player.pos, t.world.player.pos);
+    assert_eq!(resumed.world.player.rot_h, t.world.player.rot_h);
+    assert_eq!(
+        resumed.world.player.inventory.slots,
+        t.world.player.inventory.slots
+    );
+    let interactables = |t: &TestWorld| -> Vec<(String, bool, f32)> {
+        t.world
+            .interactables
+            .iter()
+            .map(|i| (i.data.obj.meshes[0].name.clone(), i.open, i.progress))
+            .collect()
+    };
+    assert_eq!(interactables(&resumed), interactables(&t));
+    let items = |t: &TestWorld| -> Vec<(String, Vec3<f32>)> {
+        t.world
+            .items
+            .iter()
+            .map(|item| {
+                let pos = t.world.item_matrix(item) * vec4(0.0, 0.0, 0.0, 1.0);
+                (item.name.clone(), pos.xyz())
+            })
+            .collect()
+    };
+    assert_eq!(items(&resumed), items(&t));
+    assert_eq!(resumed.world.key_puzzle_state, t.world.key_puzzle_state);
+    assert_eq!(resumed.world.monster.state, t.world.monster.state);
+    assert_eq!(resumed.world.monster.pos, t.world.monster.pos);
+    assert_eq!(
+        json(&resumed.world.level.randomization()),
+        json(&t.world.level.randomization())
+    );
+    assert_eq!(json(&resumed.world.save_data()), json(&t.world.save_data()));
+
+    // The same random numbers come up after resuming
+    assert_eq!(
+        resumed.world.run_rng.gen::<u64>(),
+        t.world.run_rng.gen::<u64>()
+    );
+}
@@ -0,0 +1,94 @@
+use super::*;
+
+fn restart(t: &TestWorld, checkpoint: &str) -> TestWorld {
+    TestWorld::with_session(Session {
+        restart_from: Some(checkpoint.to_owned()),
+        ..t.world.session.clone()
+    })
+}
+
+#[test]
+fn storage_opened_is_a_checkpoint() {
+    let mut t = TestWorld::new();
+    t.world.solved_puzzles.insert("storage_lock".to_owned());
+    t.use_interactable("D_DoorStorage");
+    assert!(t.interactable("D_DoorStorage").open);
+    let storage = t.world.area("Storage").unwrap().center().xy();
+    t.teleport_xy(storage);
+    t.wait(0.1);
+    assert!(t.world.session.reached("storage_opened").is_some());
+    assert_eq!(t.world.passed_checkpoints, vec!["storage_opened"]);
+    assert!(t.events.contains(&WorldEvent::CheckpointReached));
+}
+
+#[test]
+fn restarting_restores_the_checkpoints_passed_before() {
+    let mut t = TestWorld::new();
+    t.world.reach_checkpoint("entered_house");
+    let storage = t.world.area("Storage").unwrap().center().xy();
+    t.teleport_xy(storage);
+    t.world.reach_checkpoint("storage_opened");
+
+    let t = restart(&t, "storage_opened");
+    assert_eq!(
+        t.world.passed_checkpoints,
+        vec!["entered_house", "storage_opened"],
+    );
+    assert!(t.world.fuse_placed);
+    assert!(t.world.monster_spawned);
+    assert!(t.world.is_solved("storage_lock"));
+    assert!(!t
+        .world
+        .interactables
+        .iter()
+        .any(|i| i.name().contains("StorageButton")));
+    assert!(t.interactable("D_DoorStorage").open);
+    assert!((t.world.player.pos.xy() - storage).len() < 0.1);
+    assert!(!t.world.cutscene_playing("intro"));
+}
+
+#[test]
+fn restarting_from_an_earlier_checkpoint_keeps_the_later_ones() {
+    let mut t = TestWorld::new();
+    t.world.reach_checkpoint("entered_house");
+    t.world.solve_named_puzzle("light_out");
+    assert_eq!(
+        t.world.session.reached("light_out").unwrap().passed,
+        vec!["entered_house"],
+    );
+
+    let mut t = restart(&t, "entered_house");
+    assert_eq!(t.world.passed_checkpoints, vec!["entered_house"]);
+    assert_eq!(t.world.key_puzzle_state, KeyPuzzleState::Begin);
+    // Still there to restart from
+    assert!(t.world.session.reached("light_out").is_some());
+
+    t.world.reach_checkpoint("storage_opened");
+    assert_eq!(
+        t.world.session.reached("storage_opened").unwrap().passed,
+        vec!["entered_house"],
+    );
+}
+
+#[test]
+fn checkpoints_come_from_the_config() {
+    let mut t = TestWorld::new();
+    let mut config = (*t.world.config).clone();
+    config.checkpoints.push(
+        serde_json::from_str(
+            r#"{
+                "name": "bats",
+                "title": "Bats in the attic",
+                "restore": [{ "type": "set_flag", "flag": "bat_released", "value": true }]
+            }"#,
+        )
+        .unwrap(),
+    );
+    t.world.config = Rc::new(config);
+    assert_eq!(t.world.checkpoint_title("bats"), "Bats in the attic");
+    assert_eq!(t.world.checkpoint_title("nowhere"), "nowhere");
+
+    t.world.restore_checkpoint("bats");
+    assert!(t.world.bat_go);
+    assert_eq!(t.world.passed_checkpoints, vec!["bats"]);
+}
@@ -0,0 +1,342 @@
+use super::*;
+
+/// Leaves are not split any further once they have this many triangles
+const LEAF_SIZE: usize = 4;
+
+fn coord(v: Vec3<f32>, axis: usize) -> f32 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+fn triangle_center(tri: &[Vec3<f32>; 3]) -> Vec3<f32> {
+    (tri[0] + tri[1] + tri[2]) / 3.0
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: Vec3<f32>,
+    max: Vec3<f32>,
+}
+
+impl Bounds {
+    fn of(points: impl IntoIterator<Item = Vec3<f32>>) -> Self {
+        let mut result = Self {
+            min: vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: vec3(-f32::INFINITY, -f32::INFINITY, -f32::INFINITY),
+        };
+        for p in points {
+            result.min = vec3(
+                result.min.x.min(p.x),
+                result.min.y.min(p.y),
+                result.min.z.min(p.z),
+            );
+            result.max = vec3(
+                result.max.x.max(p.x),
+                result.max.y.max(p.y),
+                result.max.z.max(p.z),
+            );
+        }
+        result
+    }
+
+    fn extend(self, ext: f32) -> Self {
+        let ext = vec3(ext, ext, ext);
+        Self {
+            min: self.min - ext,
+            max: self.max + ext,
+        }
+    }
+
+    fn distance(&self, p: Vec3<f32>) -> f32 {
+        let clamped = vec3(
+            p.x.clamp(self.min.x, self.max.x),
+            p.y.clamp(self.min.y, self.max.y),
+            p.z.clamp(self.min.z, self.max.z),
+        );
+        (p - clamped).len()
+    }
+
+    /// Where the ray enters the box, or 0 if it starts inside
+    fn ray_entry(&self, ray: geng::CameraRay) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let from = coord(ray.from, axis);
+            let dir = coord(ray.dir, axis);
+            let min = coord(self.min, axis);
+            let max = coord(self.max, axis);
+            if dir.abs() < EPS {
+                if from < min || from > max {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min - from) / dir;
+            let t2 = (max - from) / dir;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+}
+
+#[derive(Debug)]
+struct BvhNode {
+    bounds: Bounds,
+    /// Layers of any of the triangles in the subtree
+    layers: CollisionLayers,
+    /// Triangles of the whole subtree
+    triangles: std::ops::Range<usize>,
+    /// None for leaves
+    children: Option<[usize; 2]>,
+}
+
+/// Bounding volume hierarchy over triangles, so that queries only look at the ones nearby
+/// on the [CollisionLayer] asked for
+#[derive(Debug)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    triangles: Vec<([Vec3<f32>; 3], CollisionLayers)>,
+}
+
+impl Bvh {
+    pub fn new(triangles: Vec<([Vec3<f32>; 3], CollisionLayers)>) -> Self {
+        let mut result = Self {
+            nodes: Vec::new(),
+            triangles,
+        };
+        if !result.triangles.is_empty() {
+            result.build_node(0..result.triangles.len());
+        }
+        result
+    }
+
+    /// Splits at the median along the axis where the triangles are spread out the most
+    fn build_node(&mut self, range: std::ops::Range<usize>) -> usize {
+        let triangles = &mut self.triangles[range.clone()];
+        let index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds: Bounds::of(triangles.iter().flat_map(|(tri, _)| *tri)),
+            layers: triangles
+                .iter()
+                .fold(CollisionLayers::NONE, |layers, &(_, tri_layers)| {
+                    layers.union(tri_layers)
+                }),
+            triangles: range.clone(),
+            children: None,
+        });
+        if range.len() <= LEAF_SIZE {
+            return index;
+        }
+        let centers = Bounds::of(triangles.iter().map(|(tri, _)| triangle_center(tri)));
+        let size = centers.max - centers.min;
+        let axis = if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        };
+        let mid = range.len() / 2;
+        triangles
+            .select_nth_unstable_by_key(mid, |(tri, _)| r32(coord(triangle_center(tri), axis)));
+        let left = self.build_node(range.start..range.start + mid);
+        let right = self.build_node(range.start + mid..range.end);
+        self.nodes[index].children = Some([left, right]);
+        index
+    }
+
+    /// Triangles of a leaf that are on the layer
+    fn leaf_triangles(
+        &self,
+        node: &BvhNode,
+        layer: CollisionLayer,
+    ) -> impl Iterator<Item = [Vec3<f32>; 3]> + '_ {
+        self.triangles[node.triangles.clone()]
+            .iter()
+            .filter(move |(_, layers)| layers.contains(layer))
+            .map(|&(tri, _)| tri)
+    }
+
+    /// Same as [intersect_ray_with_triangle] for the closest triangle hit
+    pub fn raycast(&self, ray: geng::CameraRay, ext: f32, layer: CollisionLayer) -> Option<f32> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        // Expanding a triangle moves its vertices by up to twice as much
+        let bounds_ext = 2.0 * ext.max(0.0) + EPS;
+        let mut closest: Option<f32> = None;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.layers.contains(layer) {
+                continue;
+            }
+            match node.bounds.extend(bounds_ext).ray_entry(ray) {
+                Some(t) if closest.map_or(true, |closest| t < closest) => {}
+                _ => continue,
+            }
+            match node.children {
+                Some(children) => stack.extend(children),
+                None => {
+                    for tri in self.leaf_triangles(node, layer) {
+                        if let Some(t) = intersect_ray_with_triangle(tri, ext, ray) {
+                            if closest.map_or(true, |closest| t < closest) {
+                                closest = Some(t);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    /// Same as [vector_from_triangle] for the closest triangle
+    pub fn closest_vector(&self, p: Vec3<f32>, layer: CollisionLayer) -> Option<Vec3<f32>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut closest: Option<Vec3<f32>> = None;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.layers.contains(layer)
+                || closest.map_or(false, |closest| node.bounds.distance(p) >= closest.len())
+            {
+                continue;
+            }
+            match node.children {
+                Some([left, right]) => {
+                    // The closer one goes on top to be looked at first
+                    if self.nodes[left].bounds.distance(p) < self.nodes[right].bounds.distance(p) {
+                        stack.extend([right, left]);
+                    } else {
+                        stack.extend([left, right]);
+                    }
+                }
+                None => {
+                    for tri in self.leaf_triangles(node, layer) {
+                        let v = vector_from_triangle(tri, p);
+                        if closest.map_or(true, |closest| v.len() < closest.len()) {
+                            closest = Some(v);
+                        }
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    /// Triangles with bounding boxes closer than `radius` to `p`
+    pub fn triangles_near(
+        &self,
+        p: Vec3<f32>,
+        radius: f32,
+        layer: CollisionLayer,
+    ) -> Vec<[Vec3<f32>; 3]> {
+        let mut result = Vec::new();
+        if self.nodes.is_empty() {
+            return result;
+        }
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.layers.contains(layer) || node.bounds.distance(p) >= radius {
+                continue;
+            }
+            match node.children {
+                Some(children) => stack.extend(children),
+                None => result.extend(
+                    self.leaf_triangles(node, layer)
+                        .filter(|&tri| Bounds::of(tri).distance(p) < radius),
+                ),
+            }
+        }
+        result
+    }
+}
+
+/// [Bvh]s over an [Obj] in its own coordinates, queried through the matrix it is drawn with.
+/// The matrix has to keep distances, which is true for everything that moves in the level
+#[derive(Debug)]
+pub struct ObjBvh {
+    /// Everything as it is drawn, for raycasts, same as [intersect_ray_with_obj]
+    pub ray: Bvh,
+    /// Billboards collapsed into their centers, for bumping into, same as [vector_from_obj]
+    pub solid: Bvh,
+}
+
+impl ObjBvh {
+    /// Meshes on none of the layers are left out
+    pub fn new(obj: &Obj, rules: &CollisionRules) -> Self {
+        let triangles = |pos: fn(&Vertex) -> Vec3<f32>| {
+            obj.meshes
+                .iter()
+                .map(|mesh| (mesh, rules.layers(mesh)))
+                .filter(|&(_, layers)| layers != CollisionLayers::NONE)
+                .flat_map(|(mesh, layers)| {
+                    mesh.geometry
+                        .chunks(3)
+                        .map(move |tri| ([pos(&tri[0]), pos(&tri[1]), pos(&tri[2])], layers))
+                })
+                .collect()
+        };
+        Self {
+            ray: Bvh::new(triangles(|v| v.a_v + v.a_bv)),
+            solid: Bvh::new(triangles(|v| v.a_v)),
+        }
+    }
+
+    pub fn raycast(
+        &self,
+        matrix: Mat4<f32>,
+        ext: f32,
+        ray: geng::CameraRay,
+        layer: CollisionLayer,
+    ) -> Option<f32> {
+        let inverse = matrix.inverse();
+        self.ray.raycast(
+            geng::CameraRay {
+                from: (inverse * ray.from.extend(1.0)).xyz(),
+                dir: (inverse * ray.dir.extend(0.0)).xyz(),
+            },
+            ext,
+            layer,
+        )
+    }
+
+    /// None if there is nothing on the layer at all
+    pub fn vector_from(
+        &self,
+        matrix: Mat4<f32>,
+        p: Vec3<f32>,
+        layer: CollisionLayer,
+    ) -> Option<Vec3<f32>> {
+        let v = self
+            .solid
+            .closest_vector((matrix.inverse() * p.extend(1.0)).xyz(), layer)?;
+        Some((matrix * v.extend(0.0)).xyz())
+    }
+
+    /// Triangles next to `p`, see [Bvh::triangles_near]
+    pub fn triangles_near(
+        &self,
+        matrix: Mat4<f32>,
+        p: Vec3<f32>,
+        radius: f32,
+        layer: CollisionLayer,
+    ) -> Vec<[Vec3<f32>; 3]> {
+        self.solid
+            .triangles_near((matrix.inverse() * p.extend(1.0)).xyz(), radius, layer)
+            .into_iter()
+            .map(|tri| tri.map(|pos| (matrix * pos.extend(1.0)).xyz()))
+            .collect()
+    }
+}
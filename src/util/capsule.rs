@@ -0,0 +1,120 @@
+use super::*;
+
+/// Where a [Capsule] overlaps a triangle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// Pointing away from the triangle, the way the capsule has to be pushed out
+    pub normal: Vec3<f32>,
+    /// How far the capsule has to be pushed
+    pub depth: f32,
+}
+
+/// Sphere of `radius` swept from `a` to `b`
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule {
+    pub a: Vec3<f32>,
+    pub b: Vec3<f32>,
+    pub radius: f32,
+}
+
+/// Closest points of segments `p1`-`q1` and `p2`-`q2`
+fn closest_points_on_segments(
+    p1: Vec3<f32>,
+    q1: Vec3<f32>,
+    p2: Vec3<f32>,
+    q2: Vec3<f32>,
+) -> (Vec3<f32>, Vec3<f32>) {
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = Vec3::dot(d1, d1);
+    let e = Vec3::dot(d2, d2);
+    let f = Vec3::dot(d2, r);
+    if a <= EPS && e <= EPS {
+        return (p1, p2);
+    }
+    let (s, t) = if a <= EPS {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = Vec3::dot(d1, r);
+        if e <= EPS {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = Vec3::dot(d1, d2);
+            let denom = a * e - b * b;
+            let s = if denom > EPS {
+                ((b * f - c * e) / denom).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let t = (b * s + f) / e;
+            if t < 0.0 {
+                ((-c / a).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / a).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+fn is_inside_triangle(tri: [Vec3<f32>; 3], n: Vec3<f32>, p: Vec3<f32>) -> bool {
+    (0..3).all(|i| {
+        let p1 = tri[i];
+        let p2 = tri[(i + 1) % 3];
+        Vec3::dot(Vec3::cross(n, p2 - p1), p - p1) >= 0.0
+    })
+}
+
+impl Capsule {
+    pub fn contact(&self, tri: [Vec3<f32>; 3]) -> Option<Contact> {
+        let n = Vec3::cross(tri[1] - tri[0], tri[2] - tri[0]).normalize_or_zero();
+        let da = Vec3::dot(self.a - tri[0], n);
+        let db = Vec3::dot(self.b - tri[0], n);
+        if da * db < 0.0 {
+            let p = self.a + (self.b - self.a) * (da / (da - db));
+            if is_inside_triangle(tri, n, p) {
+                // Goes right through, pushed out to the side where most of it is
+                let (normal, behind) = if da.max(db) >= -da.min(db) {
+                    (n, -da.min(db))
+                } else {
+                    (-n, da.max(db))
+                };
+                return Some(Contact {
+                    normal,
+                    depth: self.radius + behind,
+                });
+            }
+        }
+
+        let mut options = vec![
+            vector_from_triangle(tri, self.a),
+            vector_from_triangle(tri, self.b),
+        ];
+        for i in 0..3 {
+            let (on_axis, on_edge) =
+                closest_points_on_segments(self.a, self.b, tri[i], tri[(i + 1) % 3]);
+            options.push(on_axis - on_edge);
+        }
+        let v = options.into_iter().min_by_key(|v| r32(v.len())).unwrap();
+        if v.len() >= self.radius {
+            return None;
+        }
+        let normal = if v.len() > EPS {
+            v / v.len()
+        } else if da.max(db) >= -da.min(db) {
+            n
+        } else {
+            -n
+        };
+        if normal == Vec3::ZERO {
+            return None;
+        }
+        Some(Contact {
+            normal,
+            depth: self.radius - v.len(),
+        })
+    }
+}
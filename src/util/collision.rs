@@ -0,0 +1,75 @@
+use super::*;
+
+/// Kind of query a mesh can get in the way of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionLayer {
+    /// Player movement
+    Player,
+    /// Where the monster can walk, the navmesh is baked with these
+    Monster,
+    /// What the monster sees through
+    Sight,
+    /// What the player looks at and puts items on
+    Raycast,
+}
+
+impl CollisionLayer {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Set of [CollisionLayer]s, a list of them in json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<CollisionLayer>", into = "Vec<CollisionLayer>")]
+pub struct CollisionLayers(u8);
+
+impl CollisionLayers {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(0b1111);
+
+    pub fn contains(self, layer: CollisionLayer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl From<Vec<CollisionLayer>> for CollisionLayers {
+    fn from(layers: Vec<CollisionLayer>) -> Self {
+        Self(layers.into_iter().fold(0, |bits, layer| bits | layer.bit()))
+    }
+}
+
+impl From<CollisionLayers> for Vec<CollisionLayer> {
+    fn from(layers: CollisionLayers) -> Self {
+        [
+            CollisionLayer::Player,
+            CollisionLayer::Monster,
+            CollisionLayer::Sight,
+            CollisionLayer::Raycast,
+        ]
+        .into_iter()
+        .filter(|&layer| layers.contains(layer))
+        .collect()
+    }
+}
+
+/// [CollisionLayers] of meshes by the start of their name, the longest match wins.
+/// Meshes that do not match anything get in the way of everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CollisionRules(pub HashMap<String, CollisionLayers>);
+
+impl CollisionRules {
+    pub fn layers(&self, mesh: &ObjMesh) -> CollisionLayers {
+        self.0
+            .iter()
+            .filter(|(prefix, _)| mesh.name.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(CollisionLayers::ALL, |(_, &layers)| layers)
+    }
+}
@@ -1,8 +1,14 @@
 use super::*;
 
+mod bvh;
+mod capsule;
+mod collision;
 mod rng;
 mod spatial_map;
 
+pub use bvh::*;
+pub use capsule::*;
+pub use collision::*;
 pub use rng::*;
 pub use spatial_map::*;
 
@@ -94,9 +100,12 @@ pub fn intersect_ray_with_obj(
     matrix: Mat4<f32>,
     ext: f32,
     ray: geng::CameraRay,
+    rules: &CollisionRules,
+    layer: CollisionLayer,
 ) -> Option<f32> {
     obj.meshes
         .iter()
+        .filter(|mesh| rules.layers(mesh).contains(layer))
         .flat_map(|mesh| intersect_ray_with_mesh(mesh, matrix, ext, ray))
         .min_by_key(|&x| r32(x))
 }
@@ -136,18 +145,16 @@ pub fn vector_from_triangle(tri: [Vec3<f32>; 3], p: Vec3<f32>) -> Vec3<f32> {
     options.into_iter().min_by_key(|v| r32(v.len())).unwrap()
 }
 
-pub fn vector_from_obj(mesh: &Obj, matrix: Mat4<f32>, p: Vec3<f32>) -> Vec3<f32> {
+pub fn vector_from_obj(
+    mesh: &Obj,
+    matrix: Mat4<f32>,
+    p: Vec3<f32>,
+    rules: &CollisionRules,
+    layer: CollisionLayer,
+) -> Vec3<f32> {
     mesh.meshes
         .iter()
-        .filter(|mesh| {
-            if mesh.name.starts_with("B_SmallGrass")
-                || mesh.name.starts_with("B_TallGrass")
-                || mesh.name.starts_with("B_Tree")
-            {
-                return false;
-            }
-            true
-        })
+        .filter(|mesh| rules.layers(mesh).contains(layer))
         .flat_map(|mesh| {
             mesh.geometry.chunks(3).map(|tri| {
                 vector_from_triangle(